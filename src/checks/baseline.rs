@@ -0,0 +1,326 @@
+//! Baseline of accepted findings
+//!
+//! `zenvo doctor --write-baseline` records the current warnings and errors in
+//! `.zenvo/baseline.toml`, each with a fingerprint, a reason and an optional expiry
+//! date. `zenvo verify` only fails on findings that aren't in the baseline; once an
+//! entry expires its finding counts again. Rewriting the baseline after a partial run
+//! (`--category`, or without `--workspaces`) keeps the entries of the checks that didn't run.
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{CheckResult, CheckSeverity};
+use crate::config::discovery::find_nearest;
+
+/// Baseline location, relative to the project root
+pub const BASELINE_FILE: &str = ".zenvo/baseline.toml";
+
+/// Reason recorded when none is given
+pub const DEFAULT_REASON: &str = "Accepted when the baseline was written";
+
+/// Accepted findings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    #[serde(default, rename = "finding")]
+    pub findings: Vec<BaselineEntry>,
+}
+
+/// One accepted finding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    /// Hash of the check's category, name and message
+    pub fingerprint: String,
+    pub check: String,
+    pub category: String,
+    /// Workspace package (path) the finding came from, for `doctor --workspaces` results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    pub severity: String,
+    pub message: String,
+    /// Why the finding is accepted
+    pub reason: String,
+    /// Day the finding was recorded
+    pub recorded: NaiveDate,
+    /// Last day the entry applies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<NaiveDate>,
+}
+
+impl BaselineEntry {
+    pub fn is_expired(&self, today: NaiveDate) -> bool {
+        self.expires.is_some_and(|expires| expires < today)
+    }
+}
+
+/// Stable identifier for a finding
+///
+/// The message is part of the fingerprint, so a new instance of an accepted check
+/// (another package, another version) is not covered by the old entry.
+pub fn fingerprint(result: &CheckResult) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(result.category.as_bytes());
+    hasher.update([0]);
+    hasher.update(result.name.as_bytes());
+    hasher.update([0]);
+    hasher.update(result.message.as_bytes());
+    hex::encode(hasher.finalize())[..16].to_string()
+}
+
+fn is_finding(result: &CheckResult) -> bool {
+    matches!(
+        result.severity,
+        CheckSeverity::Error | CheckSeverity::Warning
+    )
+}
+
+/// Findings split by how the baseline treats them
+#[derive(Debug, Default)]
+pub struct Triage<'a> {
+    /// Not in the baseline
+    pub new: Vec<&'a CheckResult>,
+    /// Covered by a current entry
+    pub accepted: Vec<&'a CheckResult>,
+    /// Covered by an entry that has expired
+    pub expired: Vec<(&'a CheckResult, &'a BaselineEntry)>,
+    /// Entries whose finding no longer occurs
+    pub stale: Vec<&'a BaselineEntry>,
+}
+
+impl Baseline {
+    /// Path of the nearest baseline between the current directory and the repo root
+    pub fn find() -> Option<PathBuf> {
+        find_nearest(Path::new("."), BASELINE_FILE)
+    }
+
+    /// Load the nearest baseline, if there is one
+    pub fn load_if_exists() -> Result<Option<Self>> {
+        match Self::find() {
+            Some(path) => Self::load_from(&path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline: {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse baseline: {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let content = toml::to_string_pretty(self).context("Failed to serialize baseline")?;
+        let header = "# Accepted findings - written by `zenvo doctor --write-baseline`\n# `zenvo verify` ignores these until they expire. Keep each reason up to date.\n\n";
+        fs::write(path, format!("{}{}", header, content))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Record the warnings and errors in `results`
+    ///
+    /// Findings already in `previous` keep their reason, expiry and date; new ones get
+    /// `reason` and `expires`.
+    pub fn from_results(
+        results: &[CheckResult],
+        previous: Option<&Baseline>,
+        reason: Option<&str>,
+        expires: Option<NaiveDate>,
+        today: NaiveDate,
+    ) -> Self {
+        let mut baseline = Self::default();
+        baseline.record(None, results, previous, reason, expires, today);
+        baseline
+    }
+
+    /// Record the warnings and errors in `results` of workspace `package` (None for the
+    /// project itself), the way `from_results` does
+    pub fn record(
+        &mut self,
+        package: Option<&str>,
+        results: &[CheckResult],
+        previous: Option<&Baseline>,
+        reason: Option<&str>,
+        expires: Option<NaiveDate>,
+        today: NaiveDate,
+    ) {
+        let same = |entry: &BaselineEntry, fingerprint: &str| {
+            entry.fingerprint == fingerprint && entry.package.as_deref() == package
+        };
+        for result in results.iter().filter(|r| is_finding(r)) {
+            let fingerprint = fingerprint(result);
+            if self.findings.iter().any(|f| same(f, &fingerprint)) {
+                continue;
+            }
+            let existing = previous.and_then(|b| b.findings.iter().find(|f| same(f, &fingerprint)));
+            self.findings.push(BaselineEntry {
+                check: result.name.clone(),
+                category: result.category.clone(),
+                package: package.map(str::to_string),
+                severity: format!("{:?}", result.severity).to_lowercase(),
+                message: result.message.clone(),
+                reason: existing
+                    .map(|e| e.reason.clone())
+                    .unwrap_or_else(|| reason.unwrap_or(DEFAULT_REASON).to_string()),
+                recorded: existing.map_or(today, |e| e.recorded),
+                expires: existing.map_or(expires, |e| e.expires),
+                fingerprint,
+            });
+        }
+    }
+
+    /// Keep the entries of `previous` for checks that didn't run this time (`not_run`)
+    pub fn carry_over(&mut self, previous: &Baseline, not_run: impl Fn(&BaselineEntry) -> bool) {
+        for entry in previous.findings.iter().filter(|e| not_run(e)) {
+            let recorded = self
+                .findings
+                .iter()
+                .any(|f| f.fingerprint == entry.fingerprint && f.package == entry.package);
+            if !recorded {
+                self.findings.push(entry.clone());
+            }
+        }
+    }
+
+    /// Split the warnings and errors in `results` into new, accepted and expired findings
+    pub fn triage<'a>(&'a self, results: &'a [CheckResult], today: NaiveDate) -> Triage<'a> {
+        let mut triage = Triage::default();
+        let mut seen = Vec::new();
+        for result in results.iter().filter(|r| is_finding(r)) {
+            let fingerprint = fingerprint(result);
+            let entry = self
+                .findings
+                .iter()
+                .find(|f| f.package.is_none() && f.fingerprint == fingerprint);
+            match entry {
+                Some(entry) if entry.is_expired(today) => triage.expired.push((result, entry)),
+                Some(_) => triage.accepted.push(result),
+                None => triage.new.push(result),
+            }
+            seen.push(fingerprint);
+        }
+        triage.stale = self
+            .findings
+            .iter()
+            .filter(|f| f.package.is_none() && !seen.contains(&f.fingerprint))
+            .collect();
+        triage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_baseline_round_trip_and_triage() {
+        let results = vec![
+            CheckResult::pass("Lockfile exists", "lockfile"),
+            CheckResult::warning("Peer dependencies", "deps", "react@17 wants react-dom@17"),
+            CheckResult::error(
+                "Node version match",
+                "toolchain",
+                "Expected 20.11.0, found 18.19.0",
+            ),
+        ];
+
+        let first = Baseline::from_results(
+            &results[..2],
+            None,
+            Some("Upgrading in Q3"),
+            Some(date("2026-09-30")),
+            date("2026-06-01"),
+        );
+        let baseline =
+            Baseline::from_results(&results, Some(&first), None, None, date("2026-07-01"));
+        assert_eq!(baseline.findings.len(), 2);
+        assert_eq!(baseline.findings[0].reason, "Upgrading in Q3");
+        assert_eq!(baseline.findings[0].recorded, date("2026-06-01"));
+        assert_eq!(baseline.findings[1].reason, DEFAULT_REASON);
+        assert_eq!(baseline.findings[1].expires, None);
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(BASELINE_FILE);
+        baseline.save(&path).unwrap();
+        let loaded = Baseline::load_from(&path).unwrap();
+        assert_eq!(loaded.findings[0].expires, Some(date("2026-09-30")));
+
+        // A new instance of an accepted check is a new finding
+        let mut current = results.clone();
+        current.push(CheckResult::warning(
+            "Peer dependencies",
+            "deps",
+            "vue@3 wants vue-router@4",
+        ));
+        current.remove(2);
+
+        let triage = loaded.triage(&current, date("2026-08-01"));
+        assert_eq!(triage.accepted.len(), 1);
+        assert_eq!(triage.new.len(), 1);
+        assert_eq!(triage.new[0].message, "vue@3 wants vue-router@4");
+        assert_eq!(triage.stale.len(), 1);
+
+        let triage = loaded.triage(&current, date("2026-10-01"));
+        assert!(triage.accepted.is_empty());
+        assert_eq!(triage.expired.len(), 1);
+    }
+
+    #[test]
+    fn test_baseline_keeps_entries_of_checks_not_run() {
+        let today = date("2026-06-01");
+        let previous = Baseline::from_results(
+            &[
+                CheckResult::warning("Peer dependencies", "deps", "react@17 wants react-dom@17"),
+                CheckResult::error("Node version match", "toolchain", "Expected 20, found 18"),
+            ],
+            None,
+            Some("Upgrading in Q3"),
+            Some(date("2026-09-30")),
+            today,
+        );
+
+        // `--category deps`: the peer warning is gone, the toolchain entry wasn't checked
+        let results = vec![CheckResult::warning("Unused dependencies", "deps", "left-pad")];
+        let mut baseline = Baseline::from_results(&results, Some(&previous), None, None, today);
+        baseline.record(
+            Some("packages/web"),
+            &[CheckResult::warning("Unused dependencies", "deps", "left-pad")],
+            Some(&previous),
+            None,
+            None,
+            today,
+        );
+        baseline.carry_over(&previous, |entry| entry.category != "deps");
+
+        let checks: Vec<(&str, Option<&str>)> = baseline
+            .findings
+            .iter()
+            .map(|f| (f.check.as_str(), f.package.as_deref()))
+            .collect();
+        assert_eq!(
+            checks,
+            vec![
+                ("Unused dependencies", None),
+                ("Unused dependencies", Some("packages/web")),
+                ("Node version match", None),
+            ]
+        );
+        assert_eq!(baseline.findings[2].reason, "Upgrading in Q3");
+        assert_eq!(baseline.findings[2].expires, Some(date("2026-09-30")));
+
+        // Package entries are only matched against `doctor --workspaces` results
+        let triage = baseline.triage(&results, today);
+        assert_eq!(triage.accepted.len(), 1);
+        assert_eq!(triage.stale.len(), 1);
+    }
+}
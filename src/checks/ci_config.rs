@@ -0,0 +1,511 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::CheckResult;
+use crate::lockfile::EnvLock;
+
+/// Directory containing GitHub Actions workflows
+const GITHUB_WORKFLOWS_DIR: &str = ".github/workflows";
+
+/// GitLab CI configuration file
+const GITLAB_CI_FILE: &str = ".gitlab-ci.yml";
+
+/// A Node.js version reference found in a Dockerfile or CI config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeVersionRef {
+    /// File the reference was found in (relative to project root)
+    pub file: String,
+    /// 1-based line number
+    pub line: usize,
+    /// Raw version spec (image tag, setup-node input, or version file content)
+    pub spec: String,
+    /// Human-readable description of where the version came from
+    pub source: String,
+}
+
+/// How a version spec compares to the locked Node version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeSpecMatch {
+    /// Spec selects the locked version
+    Matches,
+    /// Spec selects a different version
+    Mismatch,
+    /// Spec is a floating tag, expression or digest that can't be compared
+    Unresolved,
+}
+
+/// Compare a Node version spec (image tag, setup-node input) with the locked version
+///
+/// Partial versions like `20` or `20.11` match any locked version with the same prefix,
+/// since that is what Docker tags and setup-node resolve them to.
+pub fn compare_node_spec(spec: &str, locked: &str) -> NodeSpecMatch {
+    let spec = spec.trim().trim_matches(|c| c == '"' || c == '\'');
+    let locked = locked.trim().trim_start_matches('v');
+
+    if spec.is_empty() || spec.contains("${") || spec.contains('@') {
+        return NodeSpecMatch::Unresolved;
+    }
+
+    // Docker tags carry a variant suffix: 20.11.0-alpine3.19, 20-bookworm-slim
+    let version_part = spec.trim_start_matches('v').split('-').next().unwrap_or("");
+
+    let is_plain_version = !version_part.is_empty()
+        && version_part
+            .split('.')
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit() || c == 'x'));
+
+    if is_plain_version {
+        let spec_parts: Vec<&str> = version_part.split('.').collect();
+        let locked_parts: Vec<&str> = locked.split('.').collect();
+        let matches = spec_parts
+            .iter()
+            .enumerate()
+            .all(|(i, p)| *p == "x" || locked_parts.get(i) == Some(p));
+        return if matches {
+            NodeSpecMatch::Matches
+        } else {
+            NodeSpecMatch::Mismatch
+        };
+    }
+
+    // Semver ranges are accepted by setup-node (e.g. ">=20.10 <21")
+    if spec.starts_with(['^', '~', '>', '<', '=']) {
+        if let (Ok(req), Ok(version)) = (
+            semver::VersionReq::parse(&spec.split_whitespace().collect::<Vec<_>>().join(", ")),
+            semver::Version::parse(locked),
+        ) {
+            return if req.matches(&version) {
+                NodeSpecMatch::Matches
+            } else {
+                NodeSpecMatch::Mismatch
+            };
+        }
+    }
+
+    // lts, lts/*, lts/iron, current, latest, alpine, ...
+    NodeSpecMatch::Unresolved
+}
+
+/// Parse `FROM node:<tag>` lines from a Dockerfile, including every stage of a multi-stage build
+///
+/// `ARG` defaults declared before a `FROM` are substituted so that
+/// `FROM node:${NODE_VERSION}-alpine` can still be compared.
+pub fn parse_dockerfile(file: &str, content: &str) -> Vec<NodeVersionRef> {
+    let mut refs = Vec::new();
+    let mut args: HashMap<String, String> = HashMap::new();
+
+    for (idx, raw_line) in content.lines().enumerate() {
+        let line = raw_line.trim();
+        let mut words = line.split_whitespace();
+        let instruction = match words.next() {
+            Some(w) => w.to_ascii_uppercase(),
+            None => continue,
+        };
+
+        if instruction == "ARG" {
+            if let Some((name, value)) = words.next().and_then(|a| a.split_once('=')) {
+                args.insert(
+                    name.to_string(),
+                    value.trim_matches(|c| c == '"' || c == '\'').to_string(),
+                );
+            }
+            continue;
+        }
+
+        if instruction != "FROM" {
+            continue;
+        }
+
+        // Skip flags like --platform=linux/amd64
+        let image = match words.find(|w| !w.starts_with("--")) {
+            Some(img) => substitute_args(img, &args),
+            None => continue,
+        };
+
+        if let Some(tag) = node_image_tag(&image) {
+            refs.push(NodeVersionRef {
+                file: file.to_string(),
+                line: idx + 1,
+                spec: tag,
+                source: format!("FROM {}", image),
+            });
+        }
+    }
+
+    refs
+}
+
+/// Replace `$NAME` and `${NAME}` with known ARG defaults
+fn substitute_args(value: &str, args: &HashMap<String, String>) -> String {
+    let mut names: Vec<&String> = args.keys().collect();
+    // Longest first so $NODE doesn't clobber $NODE_VERSION
+    names.sort_by_key(|n| std::cmp::Reverse(n.len()));
+
+    let mut result = value.to_string();
+    for name in names {
+        let default = &args[name];
+        result = result
+            .replace(&format!("${{{}}}", name), default)
+            .replace(&format!("${}", name), default);
+    }
+    result
+}
+
+/// Return the tag of a Node.js image reference, or None if the image is not Node.js
+///
+/// Accepts `node`, `library/node`, `docker.io/library/node`, `cimg/node`, etc.
+/// An untagged image resolves to `latest`.
+fn node_image_tag(image: &str) -> Option<String> {
+    // Digest references can't be compared to a version
+    let (name, tag) = if let Some((name, digest)) = image.split_once('@') {
+        (name, format!("@{}", digest))
+    } else {
+        // The tag separator is the last ':' after the last '/' (registry may have a port)
+        let last_segment_start = image.rfind('/').map(|i| i + 1).unwrap_or(0);
+        match image[last_segment_start..].rfind(':') {
+            Some(i) => (
+                &image[..last_segment_start + i],
+                image[last_segment_start + i + 1..].to_string(),
+            ),
+            None => (image, "latest".to_string()),
+        }
+    };
+
+    let base = name.rsplit('/').next().unwrap_or(name);
+    if base.eq_ignore_ascii_case("node") {
+        Some(tag)
+    } else {
+        None
+    }
+}
+
+/// Parse `actions/setup-node` steps from a GitHub Actions workflow
+///
+/// `node-version-file` inputs are resolved by reading the referenced file.
+pub fn parse_github_workflow(file: &str, content: &str) -> Vec<NodeVersionRef> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut refs = Vec::new();
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+
+        if !trimmed.starts_with("- ") {
+            i += 1;
+            continue;
+        }
+
+        // A step spans until the next line indented at or above its dash
+        let step_indent = line.len() - trimmed.len();
+        let mut end = i + 1;
+        while end < lines.len() {
+            let next = lines[end];
+            let next_trimmed = next.trim_start();
+            if !next_trimmed.is_empty()
+                && !next_trimmed.starts_with('#')
+                && next.len() - next_trimmed.len() <= step_indent
+            {
+                break;
+            }
+            end += 1;
+        }
+
+        let step = &lines[i..end];
+        let is_setup_node = step.iter().any(|l| {
+            yaml_value(l, "uses")
+                .map(|v| v.starts_with("actions/setup-node"))
+                .unwrap_or(false)
+        });
+
+        if is_setup_node {
+            for (offset, l) in step.iter().enumerate() {
+                let line_no = i + offset + 1;
+                if let Some(version) = yaml_value(l, "node-version") {
+                    refs.push(NodeVersionRef {
+                        file: file.to_string(),
+                        line: line_no,
+                        spec: version.to_string(),
+                        source: format!("setup-node node-version: {}", version),
+                    });
+                } else if let Some(version_file) = yaml_value(l, "node-version-file") {
+                    let spec = read_node_version_file(version_file).unwrap_or_default();
+                    refs.push(NodeVersionRef {
+                        file: file.to_string(),
+                        line: line_no,
+                        spec,
+                        source: format!("setup-node node-version-file: {}", version_file),
+                    });
+                }
+            }
+            // Steps don't nest, skip past this one
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+
+    refs
+}
+
+/// Parse `image:` entries (both `image: node:20` and `image: { name: node:20 }`) from GitLab CI
+pub fn parse_gitlab_ci(file: &str, content: &str) -> Vec<NodeVersionRef> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut refs = Vec::new();
+
+    for (idx, line) in lines.iter().enumerate() {
+        let value = match yaml_value(line, "image") {
+            Some(v) => Some((idx, v)),
+            None if line.trim() == "image:" => lines[idx + 1..]
+                .iter()
+                .enumerate()
+                .take_while(|(_, l)| l.trim().is_empty() || indent_of(l) > indent_of(line))
+                .find_map(|(o, l)| yaml_value(l, "name").map(|v| (idx + 1 + o, v))),
+            None => None,
+        };
+
+        if let Some((line_idx, image)) = value {
+            if let Some(tag) = node_image_tag(image) {
+                refs.push(NodeVersionRef {
+                    file: file.to_string(),
+                    line: line_idx + 1,
+                    spec: tag,
+                    source: format!("image: {}", image),
+                });
+            }
+        }
+    }
+
+    refs
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Extract the scalar value of `key: value` on a YAML line (list markers and quotes stripped)
+fn yaml_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let trimmed = line.trim_start().trim_start_matches("- ").trim_start();
+    let rest = trimmed.strip_prefix(key)?.trim_start().strip_prefix(':')?;
+    let value = rest.split(" #").next().unwrap_or(rest).trim();
+    let value = value.trim_matches(|c| c == '"' || c == '\'');
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// Read the Node version from a file referenced by `node-version-file`
+fn read_node_version_file(path: &str) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let file_name = Path::new(path).file_name()?.to_string_lossy().to_string();
+
+    match file_name.as_str() {
+        "package.json" => {
+            let pkg: serde_json::Value = serde_json::from_str(&content).ok()?;
+            pkg.get("volta")
+                .and_then(|v| v.get("node"))
+                .or_else(|| pkg.get("engines").and_then(|e| e.get("node")))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+        }
+        ".tool-versions" => content.lines().find_map(|l| {
+            let mut parts = l.split_whitespace();
+            match parts.next() {
+                Some("nodejs") | Some("node") => parts.next().map(|s| s.to_string()),
+                _ => None,
+            }
+        }),
+        _ => content
+            .lines()
+            .map(|l| l.trim())
+            .find(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|s| s.to_string()),
+    }
+}
+
+/// Collect Node version references from Dockerfiles and CI configs in the current directory
+fn collect_node_version_refs() -> Vec<NodeVersionRef> {
+    let mut refs = Vec::new();
+
+    // Dockerfile, Dockerfile.dev, Dockerfile-prod, ...
+    if let Ok(entries) = fs::read_dir(".") {
+        let mut dockerfiles: Vec<String> = entries
+            .flatten()
+            .filter(|e| e.path().is_file())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("Dockerfile"))
+            .collect();
+        dockerfiles.sort();
+
+        for name in dockerfiles {
+            if let Ok(content) = fs::read_to_string(&name) {
+                refs.extend(parse_dockerfile(&name, &content));
+            }
+        }
+    }
+
+    if let Ok(entries) = fs::read_dir(GITHUB_WORKFLOWS_DIR) {
+        let mut workflows: Vec<_> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| {
+                p.extension()
+                    .map(|ext| ext == "yml" || ext == "yaml")
+                    .unwrap_or(false)
+            })
+            .collect();
+        workflows.sort();
+
+        for path in workflows {
+            if let Ok(content) = fs::read_to_string(&path) {
+                let file = path.to_string_lossy().replace('\\', "/");
+                refs.extend(parse_github_workflow(&file, &content));
+            }
+        }
+    }
+
+    if let Ok(content) = fs::read_to_string(GITLAB_CI_FILE) {
+        refs.extend(parse_gitlab_ci(GITLAB_CI_FILE, &content));
+    }
+
+    refs
+}
+
+pub fn run_checks(env_lock: &Option<EnvLock>) -> Result<Vec<CheckResult>> {
+    let mut results = Vec::new();
+
+    let lock = match env_lock {
+        Some(lock) => lock,
+        None => return Ok(results),
+    };
+
+    let refs = collect_node_version_refs();
+    if refs.is_empty() {
+        return Ok(results);
+    }
+
+    let locked = &lock.toolchain.node;
+    let mut unresolved = Vec::new();
+    let mut has_mismatch = false;
+
+    for r in &refs {
+        match compare_node_spec(&r.spec, locked) {
+            NodeSpecMatch::Matches => {}
+            NodeSpecMatch::Mismatch => {
+                has_mismatch = true;
+                results.push(
+                    CheckResult::warning(
+                        "CI Node version match",
+                        "toolchain",
+                        &format!(
+                            "{}:{} uses Node {} ({}) but env.lock pins {}",
+                            r.file, r.line, r.spec, r.source, locked
+                        ),
+                    )
+                    .with_fix(&format!(
+                        "Update {} line {} to Node {}, or run `zenvo lock` if the new version is intended",
+                        r.file, r.line, locked
+                    )),
+                );
+            }
+            NodeSpecMatch::Unresolved => {
+                unresolved.push(format!("{}:{} ({})", r.file, r.line, r.source));
+            }
+        }
+    }
+
+    if !unresolved.is_empty() {
+        let msg = if unresolved.len() <= 3 {
+            format!("Floating Node versions can't be compared: {}", unresolved.join(", "))
+        } else {
+            format!(
+                "Floating Node versions can't be compared: {}, and {} more",
+                unresolved[..3].join(", "),
+                unresolved.len() - 3
+            )
+        };
+        results.push(
+            CheckResult::info("CI Node version pinned", "toolchain", &msg)
+                .with_fix(&format!("Pin an explicit version such as node:{}", locked)),
+        );
+    }
+
+    if !has_mismatch {
+        results.push(CheckResult::pass("CI Node version match", "toolchain"));
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_node_spec() {
+        assert_eq!(compare_node_spec("20", "20.11.0"), NodeSpecMatch::Matches);
+        assert_eq!(compare_node_spec("20.11-alpine", "20.11.0"), NodeSpecMatch::Matches);
+        assert_eq!(compare_node_spec("v20.11.0", "20.11.0"), NodeSpecMatch::Matches);
+        assert_eq!(compare_node_spec("20.x", "20.11.0"), NodeSpecMatch::Matches);
+        assert_eq!(compare_node_spec("18-slim", "20.11.0"), NodeSpecMatch::Mismatch);
+        assert_eq!(compare_node_spec("20.10.0", "20.11.0"), NodeSpecMatch::Mismatch);
+        assert_eq!(compare_node_spec(">=20.10", "20.11.0"), NodeSpecMatch::Matches);
+        assert_eq!(compare_node_spec("^18", "20.11.0"), NodeSpecMatch::Mismatch);
+        assert_eq!(compare_node_spec("lts-alpine", "20.11.0"), NodeSpecMatch::Unresolved);
+        assert_eq!(compare_node_spec("${{ matrix.node }}", "20.11.0"), NodeSpecMatch::Unresolved);
+    }
+
+    #[test]
+    fn test_parse_multi_stage_dockerfile() {
+        let content = "ARG NODE_VERSION=20.11.0\n\
+                       FROM --platform=linux/amd64 node:${NODE_VERSION}-alpine AS build\n\
+                       RUN npm ci\n\
+                       FROM build AS test\n\
+                       FROM docker.io/library/node:18 AS runtime\n\
+                       FROM nginx:1.25\n";
+
+        let refs = parse_dockerfile("Dockerfile", content);
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].line, 2);
+        assert_eq!(refs[0].spec, "20.11.0-alpine");
+        assert_eq!(refs[1].line, 5);
+        assert_eq!(refs[1].spec, "18");
+    }
+
+    #[test]
+    fn test_parse_github_workflow() {
+        let content = r#"jobs:
+  build:
+    steps:
+      - uses: actions/checkout@v4
+      - name: Setup
+        with:
+          node-version: '18.x'
+        uses: actions/setup-node@v4
+      - uses: some/other-action@v1
+        with:
+          node-version: 16
+"#;
+
+        let refs = parse_github_workflow(".github/workflows/ci.yml", content);
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].line, 7);
+        assert_eq!(refs[0].spec, "18.x");
+    }
+
+    #[test]
+    fn test_parse_gitlab_ci() {
+        let content = "image: node:20.11.0\n\
+                       test:\n  image:\n    name: registry.example.com:5000/node:18\n    entrypoint: [\"\"]\n\
+                       lint:\n  image: python:3.12\n";
+
+        let refs = parse_gitlab_ci(".gitlab-ci.yml", content);
+        assert_eq!(refs.len(), 2);
+        assert_eq!((refs[0].line, refs[0].spec.as_str()), (1, "20.11.0"));
+        assert_eq!((refs[1].line, refs[1].spec.as_str()), (4, "18"));
+    }
+}
@@ -0,0 +1,320 @@
+//! Custom checks declared in `.env.doctor.toml`
+//!
+//! ```toml
+//! [[checks.custom]]
+//! name = "No auth tokens in .npmrc"
+//! kind = "file_contains"
+//! path = ".npmrc"
+//! pattern = "_authToken="
+//! negate = true
+//! fix = "Move the token to ~/.npmrc or an environment variable"
+//! ```
+//!
+//! `command` checks only run when the project's own config declares them: those from
+//! `extends` presets are reported as skipped, and the MCP server only runs them when a
+//! tool call sets `run_commands`.
+
+use anyhow::Result;
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use super::{CheckCategory, CheckResult, CheckSeverity};
+use crate::config::{CustomCheck, CustomCheckCategory, CustomCheckKind, ZenvoConfig};
+use crate::utils::{run_command_with_timeout, CommandResult, DEFAULT_COMMAND_TIMEOUT};
+
+/// Whether a check in `category` runs for the `--category` filter
+fn in_category(category: CustomCheckCategory, filter: Option<CheckCategory>) -> bool {
+    match filter {
+        None => true,
+        Some(CheckCategory::Toolchain) => category == CustomCheckCategory::Toolchain,
+        Some(CheckCategory::Lockfile) => category == CustomCheckCategory::Lockfile,
+        Some(CheckCategory::Deps) => category == CustomCheckCategory::Deps,
+        Some(CheckCategory::Frameworks) => category == CustomCheckCategory::Frameworks,
+    }
+}
+
+/// Look up a dotted path (`engines.node`, `workspaces[0]`, `$.scripts.build`) in a JSON value
+fn json_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let path = path.trim_start_matches('$').trim_start_matches('.');
+    let mut current = value;
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        let (key, indices) = match segment.find('[') {
+            Some(i) => (&segment[..i], &segment[i..]),
+            None => (segment, ""),
+        };
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indices.split('[').filter(|s| !s.is_empty()) {
+            let index: usize = index.trim_end_matches(']').parse().ok()?;
+            current = current.get(index)?;
+        }
+    }
+    Some(current)
+}
+
+/// Run the check, returning why it failed
+fn evaluate(kind: &CustomCheckKind, default_timeout: Duration) -> std::result::Result<(), String> {
+    match kind {
+        CustomCheckKind::FileExists { path } => {
+            if Path::new(path).exists() {
+                Ok(())
+            } else {
+                Err(format!("{} does not exist", path))
+            }
+        }
+        CustomCheckKind::FileContains {
+            path,
+            pattern,
+            negate,
+        } => {
+            let regex =
+                Regex::new(pattern).map_err(|e| format!("Invalid pattern `{}`: {}", pattern, e))?;
+            let content = match fs::read_to_string(path) {
+                Ok(content) => content,
+                // Nothing to forbid in a file that doesn't exist
+                Err(_) if *negate => return Ok(()),
+                Err(e) => return Err(format!("Cannot read {}: {}", path, e)),
+            };
+            match (regex.find(&content), negate) {
+                (Some(_), false) | (None, true) => Ok(()),
+                (None, false) => Err(format!("{} does not match `{}`", path, pattern)),
+                (Some(m), true) => {
+                    let line = content[..m.start()].matches('\n').count() + 1;
+                    Err(format!("{}:{} matches `{}`", path, line, pattern))
+                }
+            }
+        }
+        CustomCheckKind::JsonPathEquals {
+            path,
+            json_path: selector,
+            value,
+        } => {
+            let content =
+                fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+            let json: serde_json::Value = serde_json::from_str(&content)
+                .map_err(|e| format!("{} is not valid JSON: {}", path, e))?;
+            let expected = serde_json::to_value(value).map_err(|e| e.to_string())?;
+            match json_path(&json, selector) {
+                Some(actual) if *actual == expected => Ok(()),
+                Some(actual) => Err(format!(
+                    "{} in {} is {}, expected {}",
+                    selector, path, actual, expected
+                )),
+                None => Err(format!("{} is not set in {}", selector, path)),
+            }
+        }
+        CustomCheckKind::Command {
+            command,
+            timeout_seconds,
+        } => {
+            let timeout = timeout_seconds
+                .map(Duration::from_secs)
+                .unwrap_or(default_timeout);
+
+            // On Windows run_command_with_timeout already goes through cmd.exe
+            #[cfg(windows)]
+            let result = run_command_with_timeout(command, &[], timeout);
+            #[cfg(not(windows))]
+            let result = run_command_with_timeout("sh", &["-c", command], timeout);
+
+            match result {
+                CommandResult::Success(_) => Ok(()),
+                CommandResult::Failed(output) => {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    let detail = stderr
+                        .lines()
+                        .find(|l| !l.trim().is_empty())
+                        .unwrap_or("")
+                        .trim();
+                    let code = output
+                        .status
+                        .code()
+                        .map(|c| c.to_string())
+                        .unwrap_or_else(|| "signal".to_string());
+                    if detail.is_empty() {
+                        Err(format!("`{}` exited with {}", command, code))
+                    } else {
+                        Err(format!("`{}` exited with {}: {}", command, code, detail))
+                    }
+                }
+                CommandResult::TimedOut => Err(format!(
+                    "`{}` did not finish within {}s",
+                    command,
+                    timeout.as_secs()
+                )),
+                CommandResult::SpawnError(e) => Err(e),
+            }
+        }
+    }
+}
+
+fn to_result(check: &CustomCheck, outcome: std::result::Result<(), String>) -> CheckResult {
+    let category = check.category.as_str();
+    let message = match outcome {
+        Ok(()) => return CheckResult::pass(&check.name, category),
+        Err(message) => message,
+    };
+
+    let result = match CheckSeverity::from(check.severity.clone()) {
+        CheckSeverity::Error => CheckResult::error(&check.name, category, &message),
+        CheckSeverity::Warning => CheckResult::warning(&check.name, category, &message),
+        CheckSeverity::Info => CheckResult::info(&check.name, category, &message),
+        CheckSeverity::Pass => CheckResult::pass(&check.name, category),
+    };
+    match &check.fix {
+        Some(fix) => result.with_fix(fix),
+        None => result,
+    }
+}
+
+/// Run every custom check from the config that belongs to `category`
+pub fn run_checks(
+    config: &Option<ZenvoConfig>,
+    category: Option<CheckCategory>,
+) -> Result<Vec<CheckResult>> {
+    let Some(config) = config else {
+        return Ok(Vec::new());
+    };
+
+    let default_timeout = config
+        .checks
+        .timeout_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_COMMAND_TIMEOUT);
+
+    let mut results: Vec<CheckResult> = config
+        .checks
+        .custom
+        .iter()
+        .filter(|check| in_category(check.category, category))
+        .map(|check| {
+            let is_command = matches!(check.kind, CustomCheckKind::Command { .. });
+            if is_command && config.checks.commands_disabled {
+                return CheckResult::info(
+                    &check.name,
+                    check.category.as_str(),
+                    "Not run: the MCP server only runs command checks with `run_commands: true`",
+                );
+            }
+            to_result(check, evaluate(&check.kind, default_timeout))
+        })
+        .collect();
+
+    // Preset checks were dropped before their category was known
+    if category.is_none() {
+        for name in &config.checks.preset_commands {
+            results.push(CheckResult::info(
+                name,
+                CustomCheckCategory::Project.as_str(),
+                "Not run: command checks from extends presets never run; \
+                 declare it in the project's config to run it",
+            ));
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_custom_check_kinds() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join(".npmrc"),
+            "registry=https://registry.npmjs.org/\n//registry.npmjs.org/:_authToken=abc\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("package.json"),
+            r#"{ "engines": { "node": ">=20" }, "workspaces": ["apps/*"] }"#,
+        )
+        .unwrap();
+        let file = |name: &str| root.join(name).display().to_string();
+        let timeout = Duration::from_secs(5);
+
+        assert!(evaluate(
+            &CustomCheckKind::FileExists {
+                path: file(".npmrc")
+            },
+            timeout
+        )
+        .is_ok());
+        assert!(evaluate(
+            &CustomCheckKind::FileExists {
+                path: file(".nvmrc")
+            },
+            timeout
+        )
+        .is_err());
+
+        let forbidden = CustomCheckKind::FileContains {
+            path: file(".npmrc"),
+            pattern: "_authToken=".to_string(),
+            negate: true,
+        };
+        assert!(evaluate(&forbidden, timeout)
+            .unwrap_err()
+            .contains(".npmrc:2 matches"));
+
+        let engines = |value: &str| CustomCheckKind::JsonPathEquals {
+            path: file("package.json"),
+            json_path: "$.engines.node".to_string(),
+            value: toml::Value::String(value.to_string()),
+        };
+        assert!(evaluate(&engines(">=20"), timeout).is_ok());
+        assert!(evaluate(&engines(">=18"), timeout)
+            .unwrap_err()
+            .contains("expected \">=18\""));
+
+        let first_workspace = CustomCheckKind::JsonPathEquals {
+            path: file("package.json"),
+            json_path: "workspaces[0]".to_string(),
+            value: toml::Value::String("apps/*".to_string()),
+        };
+        assert!(evaluate(&first_workspace, timeout).is_ok());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_custom_command_check() {
+        let timeout = Duration::from_secs(5);
+        let command = |command: &str| CustomCheckKind::Command {
+            command: command.to_string(),
+            timeout_seconds: None,
+        };
+        assert!(evaluate(&command("true"), timeout).is_ok());
+        assert!(evaluate(&command("echo nope >&2; exit 3"), timeout)
+            .unwrap_err()
+            .contains("exited with 3: nope"));
+
+        let slow = CustomCheckKind::Command {
+            command: "sleep 5".to_string(),
+            timeout_seconds: Some(1),
+        };
+        assert!(evaluate(&slow, timeout).unwrap_err().contains("within 1s"));
+    }
+
+    #[test]
+    fn test_command_checks_not_run_when_untrusted() {
+        let mut config: ZenvoConfig = toml::from_str(
+            "[[checks.custom]]\nname = \"Fails\"\nkind = \"command\"\ncommand = \"exit 1\"\n",
+        )
+        .unwrap();
+        config.checks.commands_disabled = true;
+        config.checks.preset_commands = vec!["From preset".to_string()];
+
+        let results = run_checks(&Some(config), None).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.severity == CheckSeverity::Info));
+        assert!(results[0].message.contains("MCP server"));
+        assert_eq!(results[1].name, "From preset");
+    }
+}
@@ -1,14 +1,13 @@
 use anyhow::Result;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use super::CheckResult;
+use super::imports::find_imports;
+use super::{installed_tree, CheckResult};
+use crate::config::{ImportsConfig, ZenvoConfig};
 use crate::utils::{run_command_with_timeout, CommandResult, DEFAULT_COMMAND_TIMEOUT};
 
-/// Maximum depth for source directory scanning
-const MAX_SOURCE_SCAN_DEPTH: usize = 10;
-
 /// Get the installed version of a package from node_modules
 fn get_installed_version(package_name: &str) -> Option<String> {
     let pkg_json_path = Path::new("node_modules").join(package_name).join("package.json");
@@ -115,12 +114,45 @@ fn get_lockfile_versions() -> Result<HashMap<String, String>> {
 }
 
 /// Check if node_modules matches lockfile versions
+///
+/// npm (v2/v3 lockfiles) and pnpm (isolated layout) installs are verified at every depth;
+/// other layouts fall back to comparing the top-level versions of direct dependencies.
 fn check_node_modules_match(results: &mut Vec<CheckResult>) -> Result<()> {
     // Skip if node_modules doesn't exist
     if !Path::new("node_modules").exists() {
         return Ok(());
     }
 
+    let root = Path::new(".");
+    let (deep_report, fix) = if Path::new("package-lock.json").exists() {
+        check_npm_hidden_lockfile(root, results);
+        (installed_tree::verify_npm_tree(root), "Run `npm ci` to reinstall")
+    } else if Path::new("pnpm-lock.yaml").exists() {
+        (
+            installed_tree::verify_pnpm_tree(root),
+            "Run `pnpm install --frozen-lockfile` to reinstall",
+        )
+    } else {
+        (Ok(None), "")
+    };
+
+    // Unreadable lockfiles are reported by the lockfile checks
+    if let Ok(Some(report)) = deep_report {
+        if report.is_clean() {
+            results.push(CheckResult::pass("node_modules matches lockfile", "deps"));
+        } else {
+            results.push(
+                CheckResult::error(
+                    "node_modules matches lockfile",
+                    "deps",
+                    &format!("Installed tree differs from lockfile: {}", report.summary()),
+                )
+                .with_fix(fix),
+            );
+        }
+        return Ok(());
+    }
+
     let lockfile_versions = match get_lockfile_versions() {
         Ok(v) => v,
         Err(_) => return Ok(()), // Can't read lockfile, skip check
@@ -186,7 +218,31 @@ fn check_node_modules_match(results: &mut Vec<CheckResult>) -> Result<()> {
     Ok(())
 }
 
-pub fn run_checks() -> Result<Vec<CheckResult>> {
+/// Compare npm's hidden lockfile with package-lock.json
+fn check_npm_hidden_lockfile(root: &Path, results: &mut Vec<CheckResult>) {
+    match installed_tree::diff_npm_hidden_lockfile(root) {
+        Ok(Some(report)) if report.is_clean() => {
+            results.push(CheckResult::pass("Hidden lockfile in sync", "deps"));
+        }
+        Ok(Some(report)) => {
+            results.push(
+                CheckResult::warning(
+                    "Hidden lockfile in sync",
+                    "deps",
+                    &format!(
+                        "node_modules/.package-lock.json differs from package-lock.json: {}",
+                        report.summary()
+                    ),
+                )
+                .with_fix("Run `npm ci` to reinstall from package-lock.json"),
+            );
+        }
+        // No hidden lockfile (older npm or a different installer)
+        _ => {}
+    }
+}
+
+pub fn run_checks(config: &Option<ZenvoConfig>) -> Result<Vec<CheckResult>> {
     let mut results = Vec::new();
 
     // Only run if node_modules exists
@@ -213,7 +269,11 @@ pub fn run_checks() -> Result<Vec<CheckResult>> {
     check_peer_dependencies(&mut results)?;
 
     // Check 5: Phantom dependencies
-    check_phantom_dependencies(&mut results)?;
+    let imports = config
+        .as_ref()
+        .map(|c| c.checks.imports.clone())
+        .unwrap_or_default();
+    check_phantom_dependencies(&imports, &mut results)?;
 
     Ok(results)
 }
@@ -321,117 +381,18 @@ fn check_peer_dependencies(results: &mut Vec<CheckResult>) -> Result<()> {
 }
 
 /// Check for phantom dependencies (imports without package.json entry)
-fn check_phantom_dependencies(results: &mut Vec<CheckResult>) -> Result<()> {
-    // Get declared dependencies
-    let pkg_json = match fs::read_to_string("package.json") {
-        Ok(content) => content,
-        Err(_) => return Ok(()),
-    };
-
-    let pkg: serde_json::Value = match serde_json::from_str(&pkg_json) {
-        Ok(v) => v,
-        Err(_) => return Ok(()),
+fn check_phantom_dependencies(
+    config: &ImportsConfig,
+    results: &mut Vec<CheckResult>,
+) -> Result<()> {
+    let Some(phantom_deps) = find_phantom_dependencies(Path::new("."), config)? else {
+        return Ok(());
     };
 
-    let mut declared_deps: HashSet<String> = HashSet::new();
-
-    // Collect all declared dependencies
-    if let Some(deps) = pkg.get("dependencies").and_then(|d| d.as_object()) {
-        for key in deps.keys() {
-            declared_deps.insert(key.clone());
-        }
-    }
-    if let Some(deps) = pkg.get("devDependencies").and_then(|d| d.as_object()) {
-        for key in deps.keys() {
-            declared_deps.insert(key.clone());
-        }
-    }
-    if let Some(deps) = pkg.get("peerDependencies").and_then(|d| d.as_object()) {
-        for key in deps.keys() {
-            declared_deps.insert(key.clone());
-        }
-    }
-    if let Some(deps) = pkg.get("optionalDependencies").and_then(|d| d.as_object()) {
-        for key in deps.keys() {
-            declared_deps.insert(key.clone());
-        }
-    }
-
-    // Add Node.js built-in modules
-    let builtins: HashSet<&str> = [
-        "assert",
-        "buffer",
-        "child_process",
-        "cluster",
-        "console",
-        "constants",
-        "crypto",
-        "dgram",
-        "dns",
-        "domain",
-        "events",
-        "fs",
-        "http",
-        "https",
-        "module",
-        "net",
-        "os",
-        "path",
-        "perf_hooks",
-        "process",
-        "punycode",
-        "querystring",
-        "readline",
-        "repl",
-        "stream",
-        "string_decoder",
-        "sys",
-        "timers",
-        "tls",
-        "tty",
-        "url",
-        "util",
-        "v8",
-        "vm",
-        "wasi",
-        "worker_threads",
-        "zlib",
-    ]
-    .into_iter()
-    .collect();
-
-    // Scan source files for imports
-    let mut phantom_deps: HashSet<String> = HashSet::new();
-    let source_dirs = ["src", "lib", "app", "pages", "components"];
-
-    for dir in source_dirs {
-        if Path::new(dir).exists() {
-            scan_directory_for_imports(Path::new(dir), &declared_deps, &builtins, &mut phantom_deps)?;
-        }
-    }
-
-    // Also check root level files
-    for entry in fs::read_dir(".")? {
-        if let Ok(entry) = entry {
-            let path = entry.path();
-            if let Some(ext) = path.extension() {
-                if ext == "js" || ext == "ts" || ext == "jsx" || ext == "tsx" || ext == "mjs" {
-                    if let Some(filename) = path.file_name() {
-                        // Skip config files
-                        let name = filename.to_string_lossy();
-                        if !name.contains("config") && !name.starts_with('.') {
-                            scan_file_for_imports(&path, &declared_deps, &builtins, &mut phantom_deps)?;
-                        }
-                    }
-                }
-            }
-        }
-    }
-
     if phantom_deps.is_empty() {
         results.push(CheckResult::pass("No phantom dependencies", "deps"));
     } else {
-        let phantom_list: Vec<_> = phantom_deps.iter().take(5).collect();
+        let phantom_list: Vec<_> = phantom_deps.keys().take(5).collect();
         let message = if phantom_deps.len() > 5 {
             format!(
                 "Found {} phantom dependencies: {}, and {} more",
@@ -463,173 +424,50 @@ fn check_phantom_dependencies(results: &mut Vec<CheckResult>) -> Result<()> {
     Ok(())
 }
 
-/// Scan a directory for import statements using walkdir for better performance
-fn scan_directory_for_imports(
-    dir: &Path,
-    declared: &HashSet<String>,
-    builtins: &HashSet<&str>,
-    phantoms: &mut HashSet<String>,
-) -> Result<()> {
-    if !dir.is_dir() {
-        return Ok(());
-    }
-
-    // Use walkdir with max_depth for controlled traversal
-    for entry in walkdir::WalkDir::new(dir)
-        .max_depth(MAX_SOURCE_SCAN_DEPTH)
-        .into_iter()
-        .filter_entry(|e| {
-            // Skip node_modules and hidden directories
-            e.file_name()
-                .to_str()
-                .map(|s| s != "node_modules" && !s.starts_with('.'))
-                .unwrap_or(false)
-                || e.depth() == 0 // Always include the root
-        })
-        .filter_map(|e| e.ok())
-    {
-        let path = entry.path();
-
-        if path.is_file() {
-            if let Some(ext) = path.extension() {
-                if ext == "js" || ext == "ts" || ext == "jsx" || ext == "tsx" || ext == "mjs" {
-                    scan_file_for_imports(path, declared, builtins, phantoms)?;
-                }
-            }
-        }
-    }
-
-    Ok(())
-}
-
-/// Scan a single file for import/require statements
-fn scan_file_for_imports(
-    file: &Path,
-    declared: &HashSet<String>,
-    builtins: &HashSet<&str>,
-    phantoms: &mut HashSet<String>,
-) -> Result<()> {
-    let content = match fs::read_to_string(file) {
-        Ok(c) => c,
-        Err(_) => return Ok(()),
+/// Packages imported by the source files under `root` but not declared in its
+/// package.json, with the files importing each
+///
+/// Returns `None` without a readable package.json.
+pub fn find_phantom_dependencies(
+    root: &Path,
+    config: &ImportsConfig,
+) -> Result<Option<BTreeMap<String, Vec<PathBuf>>>> {
+    // Get declared dependencies
+    let pkg_json = match fs::read_to_string(root.join("package.json")) {
+        Ok(content) => content,
+        Err(_) => return Ok(None),
     };
 
-    for line in content.lines() {
-        let line = line.trim();
-
-        // ES6 import
-        if line.starts_with("import ") || line.contains(" from ") {
-            if let Some(pkg) = extract_package_from_import(line) {
-                check_package(&pkg, declared, builtins, phantoms);
-            }
-        }
+    let pkg: serde_json::Value = match serde_json::from_str(&pkg_json) {
+        Ok(v) => v,
+        Err(_) => return Ok(None),
+    };
 
-        // CommonJS require
-        if line.contains("require(") {
-            for pkg in extract_packages_from_require(line) {
-                check_package(&pkg, declared, builtins, phantoms);
-            }
-        }
+    let mut declared_deps: HashSet<String> = HashSet::new();
 
-        // Dynamic import
-        if line.contains("import(") {
-            if let Some(pkg) = extract_package_from_dynamic_import(line) {
-                check_package(&pkg, declared, builtins, phantoms);
-            }
+    // Collect all declared dependencies
+    if let Some(deps) = pkg.get("dependencies").and_then(|d| d.as_object()) {
+        for key in deps.keys() {
+            declared_deps.insert(key.clone());
         }
     }
-
-    Ok(())
-}
-
-fn extract_package_from_import(line: &str) -> Option<String> {
-    // Find the quoted string after 'from'
-    let from_idx = line.find(" from ")?;
-    let after_from = &line[from_idx + 6..];
-
-    extract_quoted_string(after_from)
-}
-
-fn extract_packages_from_require(line: &str) -> Vec<String> {
-    let mut packages = Vec::new();
-    let mut search_start = 0;
-
-    while let Some(require_idx) = line[search_start..].find("require(") {
-        let start = search_start + require_idx + 8;
-        if let Some(pkg) = extract_quoted_string(&line[start..]) {
-            packages.push(pkg);
+    if let Some(deps) = pkg.get("devDependencies").and_then(|d| d.as_object()) {
+        for key in deps.keys() {
+            declared_deps.insert(key.clone());
         }
-        search_start = start;
-    }
-
-    packages
-}
-
-fn extract_package_from_dynamic_import(line: &str) -> Option<String> {
-    let import_idx = line.find("import(")?;
-    let after_import = &line[import_idx + 7..];
-
-    extract_quoted_string(after_import)
-}
-
-fn extract_quoted_string(s: &str) -> Option<String> {
-    let s = s.trim();
-
-    let (quote_char, start_idx) = if s.starts_with('"') {
-        ('"', 1)
-    } else if s.starts_with('\'') {
-        ('\'', 1)
-    } else if s.starts_with('`') {
-        ('`', 1)
-    } else {
-        return None;
-    };
-
-    let end_idx = s[start_idx..].find(quote_char)?;
-    Some(s[start_idx..start_idx + end_idx].to_string())
-}
-
-fn check_package(
-    import_path: &str,
-    declared: &HashSet<String>,
-    builtins: &HashSet<&str>,
-    phantoms: &mut HashSet<String>,
-) {
-    // Skip relative imports
-    if import_path.starts_with('.') || import_path.starts_with('/') {
-        return;
     }
-
-    // Skip node: protocol
-    if import_path.starts_with("node:") {
-        return;
+    if let Some(deps) = pkg.get("peerDependencies").and_then(|d| d.as_object()) {
+        for key in deps.keys() {
+            declared_deps.insert(key.clone());
+        }
     }
-
-    // Extract package name (handle scoped packages)
-    let package_name = if import_path.starts_with('@') {
-        // Scoped package: @scope/package or @scope/package/subpath
-        let parts: Vec<&str> = import_path.splitn(3, '/').collect();
-        if parts.len() >= 2 {
-            format!("{}/{}", parts[0], parts[1])
-        } else {
-            import_path.to_string()
+    if let Some(deps) = pkg.get("optionalDependencies").and_then(|d| d.as_object()) {
+        for key in deps.keys() {
+            declared_deps.insert(key.clone());
         }
-    } else {
-        // Regular package: package or package/subpath
-        import_path
-            .split('/')
-            .next()
-            .unwrap_or(import_path)
-            .to_string()
-    };
-
-    // Skip built-in modules
-    if builtins.contains(package_name.as_str()) {
-        return;
     }
 
-    // Check if declared
-    if !declared.contains(&package_name) {
-        phantoms.insert(package_name);
-    }
+    let mut phantom_deps = find_imports(root, config)?;
+    phantom_deps.retain(|name, _| !declared_deps.contains(name));
+    Ok(Some(phantom_deps))
 }
@@ -0,0 +1,842 @@
+//! Finding the packages a project imports
+//!
+//! Source files are tokenized rather than matched line by line, so multi-line imports,
+//! `export ... from`, `import type`, `require.resolve` and imports in Vue, Svelte and
+//! Astro files are found, and nothing inside comments, strings or regexes is. Specifiers
+//! that don't name an npm package are dropped: relative paths, Node built-ins, other
+//! protocols (`node:`, `bun:`, `virtual:`), package.json `imports` (`#internal`),
+//! tsconfig `paths` and `baseUrl`, configured aliases and the package itself. Workspace
+//! siblings are kept: they are used like any other dependency, and importing one without
+//! declaring it is as much a phantom dependency. Both the phantom and the unused
+//! dependency checks build on this.
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::workspace::segment_matches;
+use crate::config::ImportsConfig;
+
+/// How deep below a root to look for source files
+const MAX_SOURCE_SCAN_DEPTH: usize = 10;
+
+/// Files larger than this are bundles or generated code
+const MAX_SOURCE_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Extensions of files that can import packages
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "js", "jsx", "ts", "tsx", "mjs", "cjs", "mts", "cts", "vue", "svelte", "astro", "mdx",
+];
+
+/// Directories never scanned (hidden ones are skipped too, except these)
+const SKIP_DIRS: &[&str] = &["node_modules", "dist", "build", "out", "coverage", "vendor"];
+const HIDDEN_DIRS_SCANNED: &[&str] = &[".storybook"];
+
+/// Node.js built-in modules importable without the `node:` prefix
+const BUILTINS: &[&str] = &[
+    "assert",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "domain",
+    "events",
+    "fs",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "path",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "repl",
+    "stream",
+    "string_decoder",
+    "sys",
+    "timers",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Punct(char),
+}
+
+/// `jest.*` / `vi.*` functions whose first argument is a module specifier
+const MOCK_FUNCTIONS: &[&str] = &["mock", "doMock", "requireActual", "importActual"];
+
+/// Words after which a `/` starts a regex rather than a division
+const REGEX_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    tokens: Vec<Token>,
+}
+
+impl Lexer {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    /// Whether a `/` here starts a regex, judging by the previous token
+    fn regex_allowed(&self) -> bool {
+        match self.tokens.last() {
+            None => true,
+            Some(Token::Punct(c)) => !matches!(c, ')' | ']' | '}'),
+            Some(Token::Ident(word)) => REGEX_KEYWORDS.contains(&word.as_str()),
+            Some(Token::Str(_)) => false,
+        }
+    }
+
+    /// A quoted string; unterminated ones end at the line break (e.g. `'` in JSX text)
+    fn string(&mut self, quote: char) -> String {
+        let mut value = String::new();
+        self.pos += 1;
+        while let Some(c) = self.peek(0) {
+            self.pos += 1;
+            match c {
+                '\\' => {
+                    if let Some(escaped) = self.peek(0) {
+                        value.push(escaped);
+                        self.pos += 1;
+                    }
+                }
+                '\n' => break,
+                c if c == quote => break,
+                c => value.push(c),
+            }
+        }
+        value
+    }
+
+    /// A template literal; returns its text when it has no substitutions
+    fn template(&mut self) -> Option<String> {
+        let mut value = String::new();
+        let mut plain = true;
+        self.pos += 1;
+        while let Some(c) = self.peek(0) {
+            self.pos += 1;
+            match c {
+                '\\' => {
+                    if let Some(escaped) = self.peek(0) {
+                        value.push(escaped);
+                        self.pos += 1;
+                    }
+                }
+                '`' => break,
+                '$' if self.peek(0) == Some('{') => {
+                    plain = false;
+                    self.pos += 1;
+                    self.substitution();
+                }
+                c => value.push(c),
+            }
+        }
+        plain.then_some(value)
+    }
+
+    /// Skip a `${...}` substitution, nested strings and templates included
+    fn substitution(&mut self) {
+        let mut depth = 1;
+        while let Some(c) = self.peek(0) {
+            match c {
+                '\'' | '"' => {
+                    self.string(c);
+                }
+                '`' => {
+                    self.template();
+                }
+                '{' => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                '}' => {
+                    self.pos += 1;
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn regex(&mut self) {
+        let mut in_class = false;
+        self.pos += 1;
+        while let Some(c) = self.peek(0) {
+            self.pos += 1;
+            match c {
+                '\\' => self.pos += 1,
+                '[' => in_class = true,
+                ']' => in_class = false,
+                '/' if !in_class => break,
+                '\n' => break,
+                _ => {}
+            }
+        }
+        // Flags
+        while self.peek(0).is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+    }
+
+    fn run(mut self) -> Vec<Token> {
+        while let Some(c) = self.peek(0) {
+            match c {
+                '/' if self.peek(1) == Some('/') => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                '/' if self.peek(1) == Some('*') => {
+                    self.pos += 2;
+                    while self.peek(0).is_some()
+                        && !(self.peek(0) == Some('*') && self.peek(1) == Some('/'))
+                    {
+                        self.pos += 1;
+                    }
+                    self.pos += 2;
+                }
+                '/' if self.regex_allowed() => self.regex(),
+                '\'' | '"' => {
+                    let value = self.string(c);
+                    self.tokens.push(Token::Str(value));
+                }
+                '`' => {
+                    // Templates with substitutions can't be module names
+                    let token = match self.template() {
+                        Some(value) => Token::Str(value),
+                        None => Token::Punct('`'),
+                    };
+                    self.tokens.push(token);
+                }
+                c if c.is_alphabetic() || c == '_' || c == '$' => {
+                    let start = self.pos;
+                    while self
+                        .peek(0)
+                        .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$')
+                    {
+                        self.pos += 1;
+                    }
+                    let word: String = self.chars[start..self.pos].iter().collect();
+                    self.tokens.push(Token::Ident(word));
+                }
+                c if c.is_whitespace() => self.pos += 1,
+                c if c.is_ascii_digit() => {
+                    while self
+                        .peek(0)
+                        .is_some_and(|c| c.is_alphanumeric() || c == '.' || c == '_')
+                    {
+                        self.pos += 1;
+                    }
+                    self.tokens.push(Token::Ident("0".to_string()));
+                }
+                c => {
+                    self.tokens.push(Token::Punct(c));
+                    self.pos += 1;
+                }
+            }
+        }
+        self.tokens
+    }
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    Lexer {
+        chars: source.chars().collect(),
+        pos: 0,
+        tokens: Vec::new(),
+    }
+    .run()
+}
+
+fn is_ident(token: Option<&Token>, word: &str) -> bool {
+    matches!(token, Some(Token::Ident(w)) if w == word)
+}
+
+fn is_punct(token: Option<&Token>, c: char) -> bool {
+    token == Some(&Token::Punct(c))
+}
+
+/// The string in `f('x')` when `tokens[i]` is `(`
+fn call_argument(tokens: &[Token], i: usize) -> Option<&str> {
+    match (tokens.get(i), tokens.get(i + 1)) {
+        (Some(Token::Punct('(')), Some(Token::Str(s))) => Some(s),
+        _ => None,
+    }
+}
+
+/// Module specifiers imported, exported from, required or mocked in JS/TS `source`
+pub fn extract_specifiers(source: &str) -> Vec<String> {
+    let tokens = tokenize(source);
+    let mut specifiers = Vec::new();
+    for i in 0..tokens.len() {
+        // `x.import` and `x.require` are properties, not the keywords
+        if i > 0 && is_punct(tokens.get(i - 1), '.') {
+            continue;
+        }
+        let Token::Ident(word) = &tokens[i] else {
+            continue;
+        };
+        match word.as_str() {
+            "import" | "export" => {
+                if word == "import" {
+                    match tokens.get(i + 1) {
+                        Some(Token::Str(s)) => {
+                            specifiers.push(s.clone());
+                            continue;
+                        }
+                        Some(Token::Punct('(')) => {
+                            specifiers.extend(call_argument(&tokens, i + 1).map(str::to_string));
+                            continue;
+                        }
+                        _ => {}
+                    }
+                }
+                // `import ... from 'x'` / `export ... from 'x'`, across lines
+                for j in i + 1..tokens.len() {
+                    match &tokens[j] {
+                        Token::Str(s) => {
+                            if is_ident(tokens.get(j - 1), "from") {
+                                specifiers.push(s.clone());
+                            }
+                            break;
+                        }
+                        Token::Punct(';') | Token::Punct('(') | Token::Punct('=') => break,
+                        Token::Ident(w) if w == "import" || w == "export" => break,
+                        _ => {}
+                    }
+                }
+            }
+            "require" => {
+                // require('x') and require.resolve('x')
+                let arg =
+                    if is_punct(tokens.get(i + 1), '.') && is_ident(tokens.get(i + 2), "resolve") {
+                        call_argument(&tokens, i + 3)
+                    } else {
+                        call_argument(&tokens, i + 1)
+                    };
+                specifiers.extend(arg.map(str::to_string));
+            }
+            "jest" | "vi" if is_punct(tokens.get(i + 1), '.') => {
+                let mocking = matches!(
+                    tokens.get(i + 2),
+                    Some(Token::Ident(m)) if MOCK_FUNCTIONS.contains(&m.as_str())
+                );
+                if mocking {
+                    specifiers.extend(call_argument(&tokens, i + 3).map(str::to_string));
+                }
+            }
+            _ => {}
+        }
+    }
+    specifiers
+}
+
+/// The script parts of a Vue, Svelte or Astro file
+fn script_blocks(source: &str, extension: &str) -> String {
+    let mut scripts = String::new();
+    if extension == "astro" {
+        // Frontmatter between the leading `---` fences
+        if let Some(rest) = source.trim_start().strip_prefix("---") {
+            if let Some(end) = rest.find("\n---") {
+                scripts.push_str(&rest[..end]);
+                scripts.push('\n');
+            }
+        }
+    }
+    let mut rest = source;
+    while let Some(open) = rest.find("<script") {
+        let Some(body_start) = rest[open..].find('>').map(|i| open + i + 1) else {
+            break;
+        };
+        let body_end = rest[body_start..]
+            .find("</script>")
+            .map_or(rest.len(), |i| body_start + i);
+        scripts.push_str(&rest[body_start..body_end]);
+        scripts.push('\n');
+        rest = &rest[body_end..];
+    }
+    scripts
+}
+
+/// Module specifiers in the file at `path`
+fn file_specifiers(path: &Path) -> Vec<String> {
+    if fs::metadata(path).map_or(true, |m| m.len() > MAX_SOURCE_FILE_SIZE) {
+        return Vec::new();
+    }
+    let Ok(source) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    match extension {
+        "vue" | "svelte" | "astro" => extract_specifiers(&script_blocks(&source, extension)),
+        _ => extract_specifiers(&source),
+    }
+}
+
+/// Package an import path refers to (`@scope/pkg/sub` -> `@scope/pkg`, `pkg/sub` -> `pkg`)
+pub fn package_name(import_path: &str) -> String {
+    if import_path.starts_with('@') {
+        // Scoped package: @scope/package or @scope/package/subpath
+        let parts: Vec<&str> = import_path.splitn(3, '/').collect();
+        if parts.len() >= 2 {
+            format!("{}/{}", parts[0], parts[1])
+        } else {
+            import_path.to_string()
+        }
+    } else {
+        // Regular package: package or package/subpath
+        import_path
+            .split('/')
+            .next()
+            .unwrap_or(import_path)
+            .to_string()
+    }
+}
+
+/// JSON with comments and trailing commas (tsconfig.json) as plain JSON
+fn strip_jsonc(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut out = String::with_capacity(source.len());
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '"' => {
+                out.push('"');
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' && i + 1 < chars.len() {
+                        out.push(chars[i]);
+                        i += 1;
+                    }
+                    out.push(chars[i]);
+                    i += 1;
+                }
+                out.push('"');
+                i += 1;
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            ',' => {
+                // Drop commas that only whitespace or comments separate from `}` or `]`
+                let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+                if !matches!(next, Some('}') | Some(']')) {
+                    out.push(',');
+                }
+                i += 1;
+            }
+            c => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Decides which specifiers name npm packages
+#[derive(Debug, Default)]
+struct Resolver {
+    /// tsconfig `paths` keys and configured aliases (`@/*`, `~`)
+    aliases: Vec<String>,
+    /// tsconfig `baseUrl`, as a directory
+    base_url: Option<PathBuf>,
+    /// Name of the package itself
+    own_name: Option<String>,
+}
+
+impl Resolver {
+    fn new(root: &Path, config: &ImportsConfig) -> Self {
+        let mut resolver = Resolver {
+            aliases: config.aliases.clone(),
+            ..Default::default()
+        };
+        for file in ["tsconfig.json", "jsconfig.json"] {
+            resolver.read_tsconfig(&root.join(file), 0);
+        }
+        resolver.own_name = fs::read_to_string(root.join("package.json"))
+            .ok()
+            .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+            .and_then(|pkg| pkg.get("name")?.as_str().map(str::to_string));
+        resolver
+    }
+
+    /// Collect `paths` and `baseUrl` from a tsconfig and the configs it extends
+    fn read_tsconfig(&mut self, path: &Path, depth: usize) {
+        if depth > 5 {
+            return;
+        }
+        let Some(config) = fs::read_to_string(path)
+            .ok()
+            .and_then(|c| serde_json::from_str::<serde_json::Value>(&strip_jsonc(&c)).ok())
+        else {
+            return;
+        };
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let options = config.get("compilerOptions");
+        if let Some(paths) = options
+            .and_then(|o| o.get("paths"))
+            .and_then(|p| p.as_object())
+        {
+            self.aliases.extend(paths.keys().cloned());
+        }
+        if self.base_url.is_none() {
+            if let Some(base) = options
+                .and_then(|o| o.get("baseUrl"))
+                .and_then(|b| b.as_str())
+            {
+                self.base_url = Some(dir.join(base));
+            }
+        }
+        // Only relative `extends` point at files in the project
+        let extends: Vec<&str> = match config.get("extends") {
+            Some(serde_json::Value::String(s)) => vec![s.as_str()],
+            Some(serde_json::Value::Array(list)) => {
+                list.iter().filter_map(|v| v.as_str()).collect()
+            }
+            _ => Vec::new(),
+        };
+        for parent in extends.into_iter().filter(|e| e.starts_with('.')) {
+            let mut parent_path = dir.join(parent);
+            if parent_path.extension().is_none() {
+                parent_path.set_extension("json");
+            }
+            self.read_tsconfig(&parent_path, depth + 1);
+        }
+    }
+
+    fn is_alias(&self, specifier: &str) -> bool {
+        self.aliases
+            .iter()
+            .any(|alias| match alias.strip_suffix('*') {
+                Some(prefix) => specifier.starts_with(prefix),
+                None => {
+                    let alias = alias.trim_end_matches('/');
+                    specifier == alias || specifier.starts_with(&format!("{}/", alias))
+                }
+            })
+    }
+
+    /// Whether `specifier` is a file or directory under `baseUrl`
+    fn under_base_url(&self, specifier: &str) -> bool {
+        let Some(base) = &self.base_url else {
+            return false;
+        };
+        let first = specifier.split('/').next().unwrap_or(specifier);
+        let candidate = base.join(first);
+        candidate.exists()
+            || SOURCE_EXTENSIONS
+                .iter()
+                .any(|ext| candidate.with_extension(ext).is_file())
+    }
+
+    /// The npm package `specifier` refers to, if any
+    fn package(&self, specifier: &str) -> Option<String> {
+        if specifier.is_empty()
+            || specifier.starts_with(['.', '/', '#'])
+            // node:, bun:, virtual:, https:, data: ...
+            || specifier.contains(':')
+            || self.is_alias(specifier)
+        {
+            return None;
+        }
+        let name = package_name(specifier);
+        let first = specifier.split('/').next().unwrap_or(specifier);
+        if BUILTINS.contains(&first)
+            || self.own_name.as_deref() == Some(name.as_str())
+            || self.under_base_url(specifier)
+        {
+            return None;
+        }
+        Some(name)
+    }
+}
+
+/// Whether the relative `path` matches `pattern` (`*` within a segment, `**` across them)
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    fn matches(pattern: &[&str], path: &[&str]) -> bool {
+        match pattern.split_first() {
+            None => path.is_empty(),
+            Some((&"**", rest)) => (0..=path.len()).any(|skip| matches(rest, &path[skip..])),
+            Some((segment, rest)) => {
+                !path.is_empty() && segment_matches(segment, path[0]) && matches(rest, &path[1..])
+            }
+        }
+    }
+    let pattern: Vec<&str> = pattern
+        .trim_start_matches("./")
+        .trim_end_matches('/')
+        .split('/')
+        .collect();
+    let path: Vec<&str> = path.split('/').collect();
+    // A pattern matching a directory covers everything in it
+    (1..=path.len()).any(|len| matches(&pattern, &path[..len]))
+}
+
+/// Source files under `root`, relative to it, per `config`
+fn source_files(root: &Path, config: &ImportsConfig) -> Vec<PathBuf> {
+    let roots: Vec<PathBuf> = if config.roots.is_empty() {
+        vec![root.to_path_buf()]
+    } else {
+        config.roots.iter().map(|r| root.join(r)).collect()
+    };
+    let relative = |path: &Path| -> String {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    };
+    let ignored = |path: &Path| {
+        let rel = relative(path);
+        !rel.is_empty() && config.ignore.iter().any(|p| glob_matches(p, &rel))
+    };
+
+    let mut files = Vec::new();
+    for scan_root in roots {
+        if scan_root.is_file() {
+            files.push(scan_root);
+            continue;
+        }
+        let walker = walkdir::WalkDir::new(&scan_root)
+            .max_depth(MAX_SOURCE_SCAN_DEPTH)
+            .into_iter()
+            .filter_entry(|e| {
+                if e.depth() == 0 {
+                    return true;
+                }
+                if ignored(e.path()) {
+                    return false;
+                }
+                if !e.file_type().is_dir() {
+                    return true;
+                }
+                let name = e.file_name().to_string_lossy();
+                let hidden = name.starts_with('.') && !HIDDEN_DIRS_SCANNED.contains(&name.as_ref());
+                // Nested packages import on their own account
+                !hidden
+                    && !SKIP_DIRS.contains(&name.as_ref())
+                    && !e.path().join("package.json").exists()
+            });
+        for entry in walker.flatten() {
+            let is_source = entry.file_type().is_file()
+                && entry
+                    .path()
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| SOURCE_EXTENSIONS.contains(&e));
+            if is_source {
+                files.push(entry.into_path());
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    files
+        .into_iter()
+        .map(|f| f.strip_prefix(root).map(Path::to_path_buf).unwrap_or(f))
+        .collect()
+}
+
+/// Imported package -> files importing it
+pub type Imports = BTreeMap<String, Vec<PathBuf>>;
+
+/// Packages imported by the source files under `root`, with the files (relative to
+/// `root`) importing each
+pub fn find_imports(root: &Path, config: &ImportsConfig) -> Result<Imports> {
+    let resolver = Resolver::new(root, config);
+    let mut imports = Imports::new();
+    for file in source_files(root, config) {
+        for specifier in file_specifiers(&root.join(&file)) {
+            if let Some(package) = resolver.package(&specifier) {
+                let files = imports.entry(package).or_default();
+                if !files.contains(&file) {
+                    files.push(file.clone());
+                }
+            }
+        }
+    }
+    Ok(imports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_extract_specifiers() {
+        let source = r#"
+import {
+  a,
+  b,
+} from "multi-line";
+import type { Props } from '@scope/types/sub';
+import def, * as ns from 'default-and-ns';
+import 'side-effect';
+export * from "re-export";
+export { x } from 'named-export';
+export const notAnImport = 1;
+const lazy = await import('dynamic');
+const { c } = require("required");
+const path = require.resolve('resolved');
+jest.mock('mocked');
+// import 'in-line-comment'
+/* require('in-block-comment') */
+const text = "import x from 'in-a-string'";
+const re = /import 'in-a-regex'/g;
+const half = total / 2; const quote = 'it\'s';
+const tpl = `${require('in-template')} and import('nope')`;
+obj.import('not-keyword');
+"#;
+        assert_eq!(
+            extract_specifiers(source),
+            vec![
+                "multi-line",
+                "@scope/types/sub",
+                "default-and-ns",
+                "side-effect",
+                "re-export",
+                "named-export",
+                "dynamic",
+                "required",
+                "resolved",
+                "mocked",
+            ]
+        );
+
+        let vue = "<template><p>Don't</p></template>\n\
+                   <script setup lang=\"ts\">\nimport { ref } from 'vue'\n</script>\n";
+        assert_eq!(extract_specifiers(&script_blocks(vue, "vue")), vec!["vue"]);
+        let astro = "---\nimport Layout from '../layouts/Layout.astro';\n\
+                     import { z } from 'zod';\n---\n<Layout />\n";
+        assert_eq!(
+            extract_specifiers(&script_blocks(astro, "astro")),
+            vec!["../layouts/Layout.astro", "zod"]
+        );
+    }
+
+    #[test]
+    fn test_find_imports_resolves_aliases() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("package.json"),
+            r##"{"name": "app", "workspaces": ["packages/*"], "imports": {"#db": "./src/db.js"}}"##,
+        )
+        .unwrap();
+        fs::write(
+            root.join("tsconfig.json"),
+            "{\n  // comments and trailing commas\n  \"compilerOptions\": {\n    \
+             \"baseUrl\": \"src\",\n    \"paths\": { \"@/*\": [\"./src/*\"], },\n  },\n}\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("packages/ui")).unwrap();
+        fs::write(
+            root.join("packages/ui/package.json"),
+            r#"{"name": "@app/ui"}"#,
+        )
+        .unwrap();
+        fs::write(root.join("packages/ui/index.ts"), "import 'ui-only';\n").unwrap();
+        fs::create_dir_all(root.join("src/components")).unwrap();
+        fs::write(
+            root.join("src/index.ts"),
+            "import fs from 'node:fs';\nimport path from 'path';\nimport db from '#db';\n\
+             import Button from '@/components/Button';\nimport Card from 'components/Card';\n\
+             import { Box } from '@app/ui';\nimport self from 'app/utils';\n\
+             import virtual from 'virtual:pwa';\nimport icons from '~icons/x';\n\
+             import util from '~/util';\n\
+             import React from 'react';\nimport { debounce } from 'lodash/debounce';\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("src/fixtures")).unwrap();
+        fs::write(
+            root.join("src/fixtures/data.ts"),
+            "import 'fixture-only';\n",
+        )
+        .unwrap();
+
+        let config = ImportsConfig {
+            ignore: vec!["**/fixtures".to_string()],
+            aliases: vec!["~icons/*".to_string(), "~/".to_string()],
+            ..Default::default()
+        };
+        let imports = find_imports(root, &config).unwrap();
+        assert_eq!(
+            imports.keys().collect::<Vec<_>>(),
+            vec!["@app/ui", "lodash", "react"]
+        );
+        assert_eq!(imports["react"], vec![PathBuf::from("src/index.ts")]);
+
+        let only_packages = ImportsConfig {
+            roots: vec!["packages".to_string()],
+            ..Default::default()
+        };
+        // Nested packages are skipped even when a root contains them
+        assert!(find_imports(root, &only_packages).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("**/fixtures", "src/a/fixtures/x.ts"));
+        assert!(glob_matches("legacy/**", "legacy/old.js"));
+        assert!(glob_matches("src/*.gen.ts", "src/api.gen.ts"));
+        assert!(!glob_matches("src/*.gen.ts", "src/nested/api.gen.ts"));
+        assert!(glob_matches("scripts", "scripts/build.mjs"));
+    }
+}
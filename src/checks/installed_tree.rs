@@ -0,0 +1,653 @@
+//! Deep verification of the installed node_modules tree against the lockfile.
+//! npm trees are compared path by path (including nested node_modules and the
+//! hidden `node_modules/.package-lock.json`), pnpm trees via the `.pnpm` virtual store.
+
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// Differences between the lockfile and what is installed on disk
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeReport {
+    /// Locked packages that are not installed (`path@version` or `name@version`)
+    pub missing: Vec<String>,
+    /// Installed packages that the lockfile doesn't know about
+    pub extra: Vec<String>,
+    /// Installed packages whose version differs (`name: expected X but found Y`)
+    pub mismatched: Vec<String>,
+}
+
+impl TreeReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mismatched.is_empty()
+    }
+
+    /// One-line summary listing the first few entries of each kind
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        for (label, items) in [
+            ("mismatched", &self.mismatched),
+            ("missing", &self.missing),
+            ("extra", &self.extra),
+        ] {
+            if items.is_empty() {
+                continue;
+            }
+            let shown: Vec<&str> = items.iter().take(3).map(|s| s.as_str()).collect();
+            let more = if items.len() > 3 {
+                format!(", and {} more", items.len() - 3)
+            } else {
+                String::new()
+            };
+            parts.push(format!("{} {}: {}{}", items.len(), label, shown.join(", "), more));
+        }
+        parts.join("; ")
+    }
+}
+
+/// Read the `version` field of `<dir>/package.json`
+fn read_installed_version(dir: &Path) -> Option<String> {
+    let content = fs::read_to_string(dir.join("package.json")).ok()?;
+    let pkg: serde_json::Value = serde_json::from_str(&content).ok()?;
+    pkg.get("version").and_then(|v| v.as_str()).map(|s| s.to_string())
+}
+
+/// Entry of an npm `packages` map that is relevant for installation
+#[derive(Debug, Clone)]
+struct NpmLockEntry {
+    version: Option<String>,
+    link: bool,
+    optional: bool,
+    dev: bool,
+}
+
+impl NpmLockEntry {
+    /// Whether the entry has to be installed, given whether devDependencies were omitted
+    fn required(&self, omit_dev: bool) -> bool {
+        !(self.optional || (self.dev && omit_dev))
+    }
+}
+
+/// Read the `packages` map of an npm v2/v3 lockfile (or the hidden lockfile)
+fn read_npm_packages(path: &Path) -> Result<Option<BTreeMap<String, NpmLockEntry>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)?;
+    let lockfile: serde_json::Value = serde_json::from_str(&content)?;
+
+    let packages = match lockfile.get("packages").and_then(|p| p.as_object()) {
+        Some(p) => p,
+        // lockfile v1 has no flat package map
+        None => return Ok(None),
+    };
+
+    let entries = packages
+        .iter()
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, value)| {
+            let flag = |name: &str| value.get(name).and_then(|v| v.as_bool()).unwrap_or(false);
+            (
+                key.clone(),
+                NpmLockEntry {
+                    version: value.get("version").and_then(|v| v.as_str()).map(String::from),
+                    link: flag("link"),
+                    optional: flag("optional") || flag("devOptional"),
+                    dev: flag("dev"),
+                },
+            )
+        })
+        .collect();
+
+    Ok(Some(entries))
+}
+
+/// Whether the install left out devDependencies (`npm ci --omit=dev`, `NODE_ENV=production`),
+/// judged by none of the locked dev packages being `present`
+fn dev_omitted(locked: &BTreeMap<String, NpmLockEntry>, present: impl Fn(&str) -> bool) -> bool {
+    let mut dev = locked.iter().filter(|(_, entry)| entry.dev).peekable();
+    dev.peek().is_some() && dev.all(|(path, _)| !present(path))
+}
+
+/// Walk `node_modules` recursively and collect installed package paths (lockfile-style keys)
+/// with their versions. Symlinked packages are recorded but not descended into.
+fn walk_npm_node_modules(root: &Path, prefix: &str, installed: &mut BTreeMap<String, Option<String>>) {
+    let node_modules = root.join(prefix).join("node_modules");
+    let entries = match fs::read_dir(&node_modules) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let mut package_dirs = Vec::new();
+        if name.starts_with('@') {
+            if let Ok(scoped) = fs::read_dir(entry.path()) {
+                for scoped_entry in scoped.flatten() {
+                    let scoped_name = scoped_entry.file_name().to_string_lossy().to_string();
+                    package_dirs.push(format!("{}/{}", name, scoped_name));
+                }
+            }
+        } else {
+            package_dirs.push(name);
+        }
+
+        for pkg in package_dirs {
+            let key = format!("{}node_modules/{}", prefix, pkg);
+            let abs = root.join(&key);
+            let is_symlink = fs::symlink_metadata(&abs)
+                .map(|m| m.file_type().is_symlink())
+                .unwrap_or(false);
+
+            installed.insert(key.clone(), read_installed_version(&abs));
+
+            if !is_symlink {
+                walk_npm_node_modules(root, &format!("{}/", key), installed);
+            }
+        }
+    }
+}
+
+/// Verify an npm install against package-lock.json at every depth
+///
+/// Returns None if there is no v2/v3 lockfile to compare with.
+pub fn verify_npm_tree(root: &Path) -> Result<Option<TreeReport>> {
+    let locked = match read_npm_packages(&root.join("package-lock.json"))? {
+        Some(l) => l,
+        None => return Ok(None),
+    };
+
+    let mut installed = BTreeMap::new();
+    walk_npm_node_modules(root, "", &mut installed);
+
+    // The hidden lockfile lists exactly what was installed, so prefer it over the disk
+    let hidden = read_npm_packages(&root.join("node_modules").join(".package-lock.json"))
+        .ok()
+        .flatten();
+    let omit_dev = match &hidden {
+        Some(hidden) => dev_omitted(&locked, |path| hidden.contains_key(path)),
+        None => dev_omitted(&locked, |path| installed.contains_key(path)),
+    };
+
+    let mut report = TreeReport::default();
+
+    for (path, entry) in &locked {
+        let on_disk = if path.starts_with("node_modules/") {
+            installed.get(path).cloned()
+        } else {
+            // Workspace members and their nested deps live outside the root node_modules
+            let abs = root.join(path);
+            abs.exists().then(|| read_installed_version(&abs))
+        };
+
+        match on_disk {
+            None => {
+                if entry.required(omit_dev) {
+                    report.missing.push(format!(
+                        "{}@{}",
+                        path,
+                        entry.version.as_deref().unwrap_or("link")
+                    ));
+                }
+            }
+            Some(installed_version) => {
+                if entry.link {
+                    continue;
+                }
+                if let (Some(expected), Some(found)) = (&entry.version, &installed_version) {
+                    if expected != found {
+                        report.mismatched.push(format!(
+                            "{}: expected {} but found {}",
+                            path, expected, found
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    for (path, version) in &installed {
+        if !locked.contains_key(path) {
+            report.extra.push(format!(
+                "{}@{}",
+                path,
+                version.as_deref().unwrap_or("unknown")
+            ));
+        }
+    }
+
+    Ok(Some(report))
+}
+
+/// Diff npm's hidden lockfile (`node_modules/.package-lock.json`) against package-lock.json
+///
+/// Returns None if either file is missing or in the legacy format.
+pub fn diff_npm_hidden_lockfile(root: &Path) -> Result<Option<TreeReport>> {
+    let locked = match read_npm_packages(&root.join("package-lock.json"))? {
+        Some(l) => l,
+        None => return Ok(None),
+    };
+    let hidden = match read_npm_packages(&root.join("node_modules").join(".package-lock.json"))? {
+        Some(h) => h,
+        None => return Ok(None),
+    };
+
+    let omit_dev = dev_omitted(&locked, |path| hidden.contains_key(path));
+    let mut report = TreeReport::default();
+
+    for (path, entry) in &locked {
+        match hidden.get(path) {
+            None => {
+                if entry.required(omit_dev) {
+                    report.missing.push(format!(
+                        "{}@{}",
+                        path,
+                        entry.version.as_deref().unwrap_or("link")
+                    ));
+                }
+            }
+            Some(h) => {
+                if h.version != entry.version {
+                    report.mismatched.push(format!(
+                        "{}: expected {} but found {}",
+                        path,
+                        entry.version.as_deref().unwrap_or("none"),
+                        h.version.as_deref().unwrap_or("none")
+                    ));
+                }
+            }
+        }
+    }
+
+    for (path, entry) in &hidden {
+        if !locked.contains_key(path) {
+            report.extra.push(format!(
+                "{}@{}",
+                path,
+                entry.version.as_deref().unwrap_or("link")
+            ));
+        }
+    }
+
+    Ok(Some(report))
+}
+
+/// Split a pnpm lockfile key or virtual store directory into (name, version)
+///
+/// Handles `/name@1.0.0`, `/@scope/name@1.0.0(peer@1.0.0)`, `name@1.0.0` (v9),
+/// `/name/1.0.0_peer@1.0.0` (v5), and store directories like `@scope+name@1.0.0_hash`.
+pub fn parse_pnpm_package_ref(reference: &str) -> Option<(String, String)> {
+    let reference = reference.trim_start_matches('/');
+    // Peer dependency suffixes
+    let reference = reference.split('(').next().unwrap_or(reference);
+
+    let (name, version) = if let Some(unscoped) = reference.strip_prefix('@') {
+        let at = unscoped.find('@').map(|i| i + 1);
+        match at {
+            Some(i) => (&reference[..i], &reference[i + 1..]),
+            // v5 scoped: @scope/name/1.0.0
+            None => {
+                let idx = reference.rfind('/')?;
+                (&reference[..idx], &reference[idx + 1..])
+            }
+        }
+    } else if let Some(i) = reference.find('@') {
+        (&reference[..i], &reference[i + 1..])
+    } else {
+        // v5: name/1.0.0
+        let idx = reference.rfind('/')?;
+        (&reference[..idx], &reference[idx + 1..])
+    };
+
+    let version = version.split('_').next().unwrap_or(version);
+    if name.is_empty() || !version.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((name.replace('+', "/"), version.to_string()))
+}
+
+/// A locked pnpm package as (name, version)
+type PnpmPackage = (String, String);
+
+/// Read (name, version) pairs from pnpm-lock.yaml `packages` (and v9 `snapshots`),
+/// along with the parsed lockfile
+fn read_pnpm_locked_packages(
+    root: &Path,
+) -> Result<Option<(BTreeSet<PnpmPackage>, serde_yaml::Value)>> {
+    let path = root.join("pnpm-lock.yaml");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)?;
+    let lockfile: serde_yaml::Value = serde_yaml::from_str(&content)?;
+
+    let mut packages = BTreeSet::new();
+    for section in ["packages", "snapshots"] {
+        if let Some(map) = lockfile.get(section).and_then(|p| p.as_mapping()) {
+            for key in map.keys().filter_map(|k| k.as_str()) {
+                if let Some(parsed) = parse_pnpm_package_ref(key) {
+                    packages.insert(parsed);
+                }
+            }
+        }
+    }
+
+    Ok(Some((packages, lockfile)))
+}
+
+/// Dependency kinds a pnpm install included (`included` in `.modules.yaml`)
+#[derive(Debug, Clone, Copy)]
+struct PnpmIncluded {
+    dev: bool,
+    optional: bool,
+}
+
+/// (name, version) of each entry in a pnpm dependency map
+///
+/// Values are versions (`1.0.0(react@18.2.0)`), aliases (`other@1.0.0`) or, in importers
+/// since lockfile v6, `{ specifier, version }`. `link:` dependencies are left out.
+fn pnpm_dependency_refs(deps: Option<&serde_yaml::Value>) -> Vec<PnpmPackage> {
+    let Some(deps) = deps.and_then(|d| d.as_mapping()) else {
+        return Vec::new();
+    };
+    deps.iter()
+        .filter_map(|(name, spec)| {
+            let name = name.as_str()?;
+            let version = spec
+                .as_str()
+                .or_else(|| spec.get("version").and_then(|v| v.as_str()))?;
+            if version.starts_with(|c: char| c.is_ascii_digit()) {
+                parse_pnpm_package_ref(&format!("{}@{}", name, version))
+            } else {
+                parse_pnpm_package_ref(version)
+            }
+        })
+        .collect()
+}
+
+/// Locked packages an install with `included` needs: everything reachable from the
+/// importers through the included dependency fields
+fn pnpm_required_packages(
+    lockfile: &serde_yaml::Value,
+    included: PnpmIncluded,
+) -> BTreeSet<PnpmPackage> {
+    let mut fields = vec!["dependencies"];
+    if included.optional {
+        fields.push("optionalDependencies");
+    }
+
+    let mut children: BTreeMap<PnpmPackage, Vec<PnpmPackage>> = BTreeMap::new();
+    for section in ["packages", "snapshots"] {
+        let Some(map) = lockfile.get(section).and_then(|p| p.as_mapping()) else {
+            continue;
+        };
+        for (key, entry) in map {
+            let Some(package) = key.as_str().and_then(parse_pnpm_package_ref) else {
+                continue;
+            };
+            let deps = children.entry(package).or_default();
+            for field in &fields {
+                deps.extend(pnpm_dependency_refs(entry.get(*field)));
+            }
+        }
+    }
+
+    // Lockfiles before v6 of a single project keep the root dependencies at the top level
+    let mut importers = vec![lockfile];
+    if let Some(map) = lockfile.get("importers").and_then(|i| i.as_mapping()) {
+        importers.extend(map.values());
+    }
+    let mut root_fields = fields.clone();
+    if included.dev {
+        root_fields.push("devDependencies");
+    }
+    let mut pending: Vec<PnpmPackage> = importers
+        .iter()
+        .flat_map(|importer| {
+            root_fields
+                .iter()
+                .flat_map(|field| pnpm_dependency_refs(importer.get(*field)))
+        })
+        .collect();
+
+    let mut required = BTreeSet::new();
+    while let Some(package) = pending.pop() {
+        if let Some(deps) = children.get(&package) {
+            if !required.contains(&package) {
+                pending.extend(deps.iter().cloned());
+            }
+        }
+        required.insert(package);
+    }
+    required
+}
+
+/// Verify a pnpm install (`node_modules/.modules.yaml` + `.pnpm` virtual store) against pnpm-lock.yaml
+///
+/// Returns None if there is no lockfile or the install doesn't use the isolated layout.
+pub fn verify_pnpm_tree(root: &Path) -> Result<Option<TreeReport>> {
+    let (locked, lockfile) = match read_pnpm_locked_packages(root)? {
+        Some(l) => l,
+        None => return Ok(None),
+    };
+
+    let modules_yaml = root.join("node_modules").join(".modules.yaml");
+    let modules: serde_yaml::Value = match fs::read_to_string(&modules_yaml) {
+        Ok(content) => serde_yaml::from_str(&content).unwrap_or(serde_yaml::Value::Null),
+        Err(_) => serde_yaml::Value::Null,
+    };
+
+    // Hoisted installs (node-linker=hoisted) have no virtual store
+    if modules.get("nodeLinker").and_then(|v| v.as_str()) == Some("hoisted") {
+        return Ok(None);
+    }
+
+    let virtual_store = modules
+        .get("virtualStoreDir")
+        .and_then(|v| v.as_str())
+        .map(|dir| {
+            let p = Path::new(dir);
+            if p.is_absolute() {
+                p.to_path_buf()
+            } else {
+                root.join("node_modules").join(p)
+            }
+        })
+        .unwrap_or_else(|| root.join("node_modules").join(".pnpm"));
+
+    if !virtual_store.is_dir() {
+        return Ok(None);
+    }
+
+    // Optional packages pnpm deliberately skipped for this platform
+    let skipped: BTreeSet<(String, String)> = modules
+        .get("skipped")
+        .and_then(|s| s.as_sequence())
+        .map(|seq| {
+            seq.iter()
+                .filter_map(|v| v.as_str())
+                .filter_map(parse_pnpm_package_ref)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // `pnpm install --prod` / `--no-optional` leave whole dependency kinds out
+    let included = |kind: &str| {
+        modules
+            .get("included")
+            .and_then(|i| i.get(kind))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true)
+    };
+    let included = PnpmIncluded {
+        dev: included("devDependencies"),
+        optional: included("optionalDependencies"),
+    };
+    let required = (!included.dev || !included.optional)
+        .then(|| pnpm_required_packages(&lockfile, included));
+
+    let mut report = TreeReport::default();
+    let mut installed = BTreeSet::new();
+
+    for entry in fs::read_dir(&virtual_store)?.flatten() {
+        let dir_name = entry.file_name().to_string_lossy().to_string();
+        let (name, version) = match parse_pnpm_package_ref(&dir_name) {
+            Some(p) => p,
+            None => continue,
+        };
+
+        let package_dir = entry.path().join("node_modules").join(&name);
+        match read_installed_version(&package_dir) {
+            Some(found) if found != version => report.mismatched.push(format!(
+                "{}: expected {} but found {}",
+                name, version, found
+            )),
+            None => report.missing.push(format!("{}@{}", name, version)),
+            _ => {}
+        }
+
+        installed.insert((name, version));
+    }
+
+    for (name, version) in &locked {
+        let key = (name.clone(), version.clone());
+        let needed = required.as_ref().map_or(true, |r| r.contains(&key));
+        if needed && !installed.contains(&key) && !skipped.contains(&key) {
+            report.missing.push(format!("{}@{}", name, version));
+        }
+    }
+
+    for (name, version) in &installed {
+        if !locked.contains(&(name.clone(), version.clone())) {
+            report.extra.push(format!("{}@{}", name, version));
+        }
+    }
+
+    Ok(Some(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &TempDir, path: &str, content: &str) {
+        let full = dir.path().join(path);
+        fs::create_dir_all(full.parent().unwrap()).unwrap();
+        fs::write(full, content).unwrap();
+    }
+
+    #[test]
+    fn test_parse_pnpm_package_ref() {
+        let parse = |s| parse_pnpm_package_ref(s).map(|(n, v)| format!("{}@{}", n, v));
+        assert_eq!(parse("/lodash@4.17.21"), Some("lodash@4.17.21".into()));
+        assert_eq!(parse("@types/node@20.1.0"), Some("@types/node@20.1.0".into()));
+        assert_eq!(parse("/react-dom@18.2.0(react@18.2.0)"), Some("react-dom@18.2.0".into()));
+        assert_eq!(parse("/lodash/4.17.21"), Some("lodash@4.17.21".into()));
+        assert_eq!(parse("@babel+core@7.23.0_supports-color@5.5.0"), Some("@babel/core@7.23.0".into()));
+        assert_eq!(parse("node_modules"), None);
+        assert_eq!(parse("lock.yaml"), None);
+    }
+
+    #[test]
+    fn test_verify_npm_tree_nested() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "package-lock.json", r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app" },
+                "node_modules/a": { "version": "1.0.0" },
+                "node_modules/a/node_modules/b": { "version": "2.0.0" },
+                "node_modules/@s/c": { "version": "3.0.0" },
+                "node_modules/fsevents": { "version": "2.3.3", "optional": true }
+            }
+        }"#);
+        write(&dir, "node_modules/a/package.json", r#"{"version": "1.0.0"}"#);
+        write(&dir, "node_modules/a/node_modules/b/package.json", r#"{"version": "2.1.0"}"#);
+        write(&dir, "node_modules/stray/package.json", r#"{"version": "0.1.0"}"#);
+
+        let report = verify_npm_tree(dir.path()).unwrap().unwrap();
+        assert_eq!(
+            report.mismatched,
+            vec!["node_modules/a/node_modules/b: expected 2.0.0 but found 2.1.0"]
+        );
+        assert_eq!(report.missing, vec!["node_modules/@s/c@3.0.0"]);
+        assert_eq!(report.extra, vec!["node_modules/stray@0.1.0"]);
+    }
+
+    #[test]
+    fn test_verify_pnpm_tree() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "pnpm-lock.yaml", "lockfileVersion: '9.0'\npackages:\n  lodash@4.17.21:\n    resolution: {integrity: x}\n  '@types/node@20.1.0':\n    resolution: {integrity: y}\n  fsevents@2.3.3:\n    resolution: {integrity: z}\n");
+        write(&dir, "node_modules/.modules.yaml", "skipped:\n  - fsevents@2.3.3\nvirtualStoreDir: .pnpm\n");
+        write(&dir, "node_modules/.pnpm/lodash@4.17.21/node_modules/lodash/package.json", r#"{"version": "4.17.20"}"#);
+        write(&dir, "node_modules/.pnpm/left-pad@1.3.0/node_modules/left-pad/package.json", r#"{"version": "1.3.0"}"#);
+
+        let report = verify_pnpm_tree(dir.path()).unwrap().unwrap();
+        assert_eq!(report.mismatched, vec!["lodash: expected 4.17.21 but found 4.17.20"]);
+        assert_eq!(report.missing, vec!["@types/node@20.1.0"]);
+        assert_eq!(report.extra, vec!["left-pad@1.3.0"]);
+    }
+
+    #[test]
+    fn test_verify_npm_tree_without_dev() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "package-lock.json", r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app" },
+                "node_modules/a": { "version": "1.0.0" },
+                "node_modules/jest": { "version": "29.7.0", "dev": true }
+            }
+        }"#);
+        write(&dir, "node_modules/a/package.json", r#"{"version": "1.0.0"}"#);
+
+        // `npm ci --omit=dev`: no dev package on disk
+        let report = verify_npm_tree(dir.path()).unwrap().unwrap();
+        assert!(report.is_clean(), "{}", report.summary());
+
+        write(&dir, "node_modules/.package-lock.json", r#"{
+            "lockfileVersion": 3,
+            "packages": { "node_modules/a": { "version": "1.0.0" } }
+        }"#);
+        assert!(verify_npm_tree(dir.path()).unwrap().unwrap().is_clean());
+        assert!(diff_npm_hidden_lockfile(dir.path()).unwrap().unwrap().is_clean());
+
+        // A regular install that lost a package still reports the dev entries
+        write(&dir, "package-lock.json", r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app" },
+                "node_modules/a": { "version": "1.0.0", "dev": true },
+                "node_modules/jest": { "version": "29.7.0", "dev": true }
+            }
+        }"#);
+        let report = verify_npm_tree(dir.path()).unwrap().unwrap();
+        assert_eq!(report.missing, vec!["node_modules/jest@29.7.0"]);
+    }
+
+    #[test]
+    fn test_verify_pnpm_tree_prod_install() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "pnpm-lock.yaml", "lockfileVersion: '9.0'\n\
+            importers:\n  .:\n    dependencies:\n      react:\n        specifier: ^18.2.0\n        version: 18.2.0\n\
+            \x20   devDependencies:\n      vitest:\n        specifier: ^1.6.0\n        version: 1.6.0\n\
+            packages:\n  react@18.2.0:\n    resolution: {integrity: a}\n  loose-envify@1.4.0:\n    resolution: {integrity: b}\n\
+            \x20 vitest@1.6.0:\n    resolution: {integrity: c}\n  tinypool@0.8.4:\n    resolution: {integrity: d}\n\
+            snapshots:\n  react@18.2.0:\n    dependencies:\n      loose-envify: 1.4.0\n  loose-envify@1.4.0: {}\n\
+            \x20 vitest@1.6.0:\n    dependencies:\n      tinypool: 0.8.4\n  tinypool@0.8.4: {}\n");
+        write(&dir, "node_modules/.modules.yaml", "included:\n  dependencies: true\n  devDependencies: false\n  optionalDependencies: true\nvirtualStoreDir: .pnpm\n");
+        write(&dir, "node_modules/.pnpm/react@18.2.0/node_modules/react/package.json", r#"{"version": "18.2.0"}"#);
+
+        // Dev packages and their dependencies aren't expected; a prod one still is
+        let report = verify_pnpm_tree(dir.path()).unwrap().unwrap();
+        assert!(report.mismatched.is_empty() && report.extra.is_empty());
+        assert_eq!(report.missing, vec!["loose-envify@1.4.0"]);
+    }
+}
@@ -5,7 +5,7 @@ use std::fs;
 use std::io::Read;
 use std::path::Path;
 
-use super::{CheckResult, CurrentEnvironment};
+use super::{lockfile_sync, CheckResult, CurrentEnvironment};
 use crate::lockfile::EnvLock;
 use crate::utils::{run_command_with_timeout, CommandResult, SHORT_COMMAND_TIMEOUT};
 
@@ -155,149 +155,56 @@ fn extract_package_json_dependencies() -> Result<HashSet<String>> {
     Ok(deps)
 }
 
-/// Extract dependency names from lockfile
-fn extract_lockfile_dependencies(lockfile_type: &str) -> Result<HashSet<String>> {
+/// Package names bun has installed, from `bun pm ls` (bun.lockb is binary)
+fn extract_bun_dependencies() -> Result<HashSet<String>> {
     let mut deps = HashSet::new();
 
-    match lockfile_type {
-        "npm" => {
-            let content = fs::read_to_string("package-lock.json")?;
-            let lockfile: serde_json::Value = serde_json::from_str(&content)?;
-
-            if let Some(packages) = lockfile.get("packages").and_then(|v| v.as_object()) {
-                for key in packages.keys() {
-                    // Keys are like "node_modules/react" or "" for root
-                    if key.starts_with("node_modules/") {
-                        let pkg_name = key.strip_prefix("node_modules/").unwrap();
-                        // Handle scoped packages
-                        if pkg_name.starts_with('@') {
-                            // Take @scope/name
-                            let parts: Vec<&str> = pkg_name.splitn(3, '/').collect();
-                            if parts.len() >= 2 {
-                                deps.insert(format!("{}/{}", parts[0], parts[1]));
-                            }
-                        } else {
-                            // Take first part before any /
-                            if let Some(name) = pkg_name.split('/').next() {
-                                deps.insert(name.to_string());
-                            }
-                        }
-                    }
-                }
+    if let CommandResult::Success(output) = run_command_with_timeout(
+        "bun",
+        &["pm", "ls"],
+        SHORT_COMMAND_TIMEOUT,
+    ) {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // Parse bun pm ls output - format: "├── package@version" or "└── package@version"
+        for line in stdout.lines() {
+            let line = line.trim();
+            // Skip empty lines and header
+            if line.is_empty() || line.starts_with('/') {
+                continue;
             }
-            // Fallback to dependencies object for older lockfile versions
-            else if let Some(dependencies) = lockfile.get("dependencies").and_then(|v| v.as_object()) {
-                for key in dependencies.keys() {
-                    deps.insert(key.clone());
-                }
-            }
-        }
-        "pnpm" => {
-            let content = fs::read_to_string("pnpm-lock.yaml")?;
-            let lockfile: serde_yaml::Value = serde_yaml::from_str(&content)?;
-
-            // pnpm lockfile uses "packages" mapping with keys like "name@version"
-            if let Some(packages) = lockfile.get("packages").and_then(|v| v.as_mapping()) {
-                for key in packages.keys() {
-                    if let Some(key_str) = key.as_str() {
-                        // Strip leading / and version suffix
-                        let pkg_ref = key_str.trim_start_matches('/');
-                        if pkg_ref.starts_with('@') {
-                            // Scoped package: @scope/name@version
-                            let parts: Vec<&str> = pkg_ref.splitn(3, '/').collect();
-                            if parts.len() >= 2 {
-                                // Second part may contain @version, strip it
-                                let name_with_version = parts[1];
-                                let name = name_with_version.split('@').next().unwrap_or(name_with_version);
-                                deps.insert(format!("{}/{}", parts[0], name));
-                            }
-                        } else {
-                            // Regular package: name@version
-                            if let Some(name) = pkg_ref.split('@').next() {
-                                if !name.is_empty() {
-                                    deps.insert(name.to_string());
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        "yarn" => {
-            let content = fs::read_to_string("yarn.lock")?;
-            // Parse yarn.lock entries (lines like '"package@version":' or 'package@version:')
-            for line in content.lines() {
-                let line = line.trim();
-                if line.ends_with(':') && !line.starts_with('#') {
-                    let entry = line.trim_end_matches(':').trim_matches('"');
-                    // Entry is like "react@^18.2.0" or "@scope/pkg@^1.0.0"
-                    if entry.starts_with('@') {
-                        // Scoped package
-                        let parts: Vec<&str> = entry.splitn(3, '/').collect();
-                        if parts.len() >= 2 {
-                            let name_with_version = parts[1];
-                            let name = name_with_version.split('@').next().unwrap_or(name_with_version);
-                            deps.insert(format!("{}/{}", parts[0], name));
-                        }
-                    } else if let Some(name) = entry.split('@').next() {
-                        if !name.is_empty() {
-                            deps.insert(name.to_string());
-                        }
-                    }
-                }
+            // Extract package name from lines like "├── react@18.2.0"
+            let pkg_part = line
+                .trim_start_matches("├──")
+                .trim_start_matches("└──")
+                .trim_start_matches("│")
+                .trim();
+
+            if pkg_part.is_empty() {
+                continue;
             }
-        }
-        "bun" => {
-            // bun.lockb is binary, use `bun pm ls` to get installed packages
-            if let CommandResult::Success(output) = run_command_with_timeout(
-                "bun",
-                &["pm", "ls"],
-                SHORT_COMMAND_TIMEOUT,
-            ) {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                // Parse bun pm ls output - format: "├── package@version" or "└── package@version"
-                for line in stdout.lines() {
-                    let line = line.trim();
-                    // Skip empty lines and header
-                    if line.is_empty() || line.starts_with('/') {
-                        continue;
-                    }
-                    // Extract package name from lines like "├── react@18.2.0"
-                    let pkg_part = line
-                        .trim_start_matches("├──")
-                        .trim_start_matches("└──")
-                        .trim_start_matches("│")
-                        .trim();
-
-                    if pkg_part.is_empty() {
-                        continue;
-                    }
 
-                    // Handle scoped packages (@scope/name@version)
-                    if pkg_part.starts_with('@') {
-                        // Find the second @ which separates name from version
-                        if let Some(at_idx) = pkg_part[1..].find('@') {
-                            let name = &pkg_part[..at_idx + 1];
-                            deps.insert(name.to_string());
-                        }
-                    } else if let Some(name) = pkg_part.split('@').next() {
-                        if !name.is_empty() {
-                            deps.insert(name.to_string());
-                        }
-                    }
+            // Handle scoped packages (@scope/name@version)
+            if let Some(unscoped) = pkg_part.strip_prefix('@') {
+                // Find the second @ which separates name from version
+                if let Some(at_idx) = unscoped.find('@') {
+                    let name = &pkg_part[..at_idx + 1];
+                    deps.insert(name.to_string());
+                }
+            } else if let Some(name) = pkg_part.split('@').next() {
+                if !name.is_empty() {
+                    deps.insert(name.to_string());
                 }
             }
         }
-        _ => {}
     }
 
     Ok(deps)
 }
 
-/// Check if package.json dependencies are in sync with lockfile
-fn check_package_json_sync(lockfile_type: &str) -> Result<Vec<String>> {
+/// Dependencies in package.json that bun hasn't installed
+fn check_bun_package_json_sync() -> Result<Vec<String>> {
     let pkg_deps = extract_package_json_dependencies()?;
-    let lockfile_deps = extract_lockfile_dependencies(lockfile_type)?;
+    let lockfile_deps = extract_bun_dependencies()?;
 
     // Find dependencies in package.json but not in lockfile
     let mut missing: Vec<String> = pkg_deps
@@ -450,35 +357,58 @@ pub fn run_checks(current: &CurrentEnvironment, env_lock: &Option<EnvLock>) -> R
 
     // Check 6: package.json in sync with lockfile
     if let Some(ref lockfile_type) = current.lockfile_type {
-        match check_package_json_sync(lockfile_type) {
-            Ok(missing) => {
-                if missing.is_empty() {
+        let fix = match lockfile_type.as_str() {
+            "bun" => "Run `bun install` to update lockfile",
+            "pnpm" => "Run `pnpm install` to update lockfile",
+            "yarn" => "Run `yarn install` to update lockfile",
+            _ => "Run `npm install` to update lockfile",
+        };
+        match lockfile_sync::find_out_of_sync(Path::new("."), lockfile_type) {
+            Ok(Some(problems)) => {
+                if problems.is_empty() {
                     results.push(CheckResult::pass("package.json in sync", "lockfile"));
                 } else {
-                    let msg = if missing.len() <= 3 {
-                        format!(
-                            "Dependencies in package.json but not in lockfile: {}",
-                            missing.join(", ")
-                        )
-                    } else {
-                        format!(
-                            "Dependencies in package.json but not in lockfile: {}, and {} more",
-                            missing[..3].join(", "),
-                            missing.len() - 3
-                        )
-                    };
-                    let fix = match lockfile_type.as_str() {
-                        "bun" => "Run `bun install` to update lockfile",
-                        "pnpm" => "Run `pnpm install` to update lockfile",
-                        "yarn" => "Run `yarn install` to update lockfile",
-                        _ => "Run `npm install` to update lockfile",
-                    };
+                    let mut shown: Vec<String> =
+                        problems.iter().take(3).map(|p| p.to_string()).collect();
+                    if problems.len() > 3 {
+                        shown.push(format!("and {} more", problems.len() - 3));
+                    }
+                    let msg = format!(
+                        "`{}` would fail: {}",
+                        lockfile_sync::frozen_install_command(Path::new("."), lockfile_type),
+                        shown.join("; ")
+                    );
                     results.push(
-                        CheckResult::warning("package.json in sync", "lockfile", &msg)
+                        CheckResult::error("package.json in sync", "lockfile", &msg)
                             .with_fix(fix)
                     );
                 }
             }
+            // bun.lockb can only be compared by name, through `bun pm ls`
+            Ok(None) => {
+                if let Ok(missing) = check_bun_package_json_sync() {
+                    if missing.is_empty() {
+                        results.push(CheckResult::pass("package.json in sync", "lockfile"));
+                    } else {
+                        let msg = if missing.len() <= 3 {
+                            format!(
+                                "Dependencies in package.json but not in lockfile: {}",
+                                missing.join(", ")
+                            )
+                        } else {
+                            format!(
+                                "Dependencies in package.json but not in lockfile: {}, and {} more",
+                                missing[..3].join(", "),
+                                missing.len() - 3
+                            )
+                        };
+                        results.push(
+                            CheckResult::warning("package.json in sync", "lockfile", &msg)
+                                .with_fix(fix)
+                        );
+                    }
+                }
+            }
             Err(_) => {
                 // If we can't parse, skip the check silently
             }
@@ -0,0 +1,645 @@
+//! Whether a frozen install would accept the lockfile
+//!
+//! `npm ci`, `pnpm install --frozen-lockfile` and `yarn install --frozen-lockfile`
+//! (`--immutable` on Yarn 2+) refuse to install when the lockfile no longer matches
+//! package.json. This repeats each one's comparison for the root and every workspace
+//! package:
+//!
+//! - npm resolves every declared dependency, peers included, through the lockfile's
+//!   `packages` tree the way Node would, and fails when one is missing or locked at a
+//!   version outside its range
+//! - pnpm compares each importer's recorded specifiers with package.json verbatim, so any
+//!   added, changed or removed entry fails; the locked versions are checked as well
+//! - Yarn needs a lockfile entry for every `name@range` it is asked for
+//!
+//! bun.lockb is binary and isn't compared.
+
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use super::installed_tree::parse_pnpm_package_ref;
+use super::monorepo::Catalogs;
+use super::workspace::expand_workspace_globs;
+use crate::utils::npm_range;
+
+/// Fields npm installs from, in the order it reports them
+const NPM_FIELDS: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "optionalDependencies",
+    "peerDependencies",
+];
+
+/// Fields pnpm and Yarn record (neither installs a package's own peers)
+const LOCKED_FIELDS: &[&str] = &["dependencies", "devDependencies", "optionalDependencies"];
+
+/// How a declared dependency disagrees with the lockfile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncProblem {
+    /// Declared but not in the lockfile
+    Missing,
+    /// Locked at a version (or package, for aliases) the spec doesn't allow
+    Unsatisfied { locked: String },
+    /// pnpm recorded a different specifier
+    SpecifierChanged { locked: String },
+    /// pnpm still records it but package.json no longer declares it
+    Undeclared,
+}
+
+/// A package.json entry a frozen install would reject
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutOfSync {
+    /// Workspace package directory, empty for the root
+    pub importer: String,
+    pub name: String,
+    /// Spec in package.json (the locked specifier for `Undeclared`)
+    pub spec: String,
+    pub problem: SyncProblem,
+}
+
+impl fmt::Display for OutOfSync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.importer.is_empty() {
+            write!(f, "{}: ", self.importer)?;
+        }
+        write!(f, "{}@{} ", self.name, self.spec)?;
+        match &self.problem {
+            SyncProblem::Missing => write!(f, "is missing from the lockfile"),
+            SyncProblem::Unsatisfied { locked } => write!(f, "is locked at {}", locked),
+            SyncProblem::SpecifierChanged { locked } => {
+                write!(f, "is locked as {}@{}", self.name, locked)
+            }
+            SyncProblem::Undeclared => write!(f, "is locked but no longer declared"),
+        }
+    }
+}
+
+/// The install command that refuses an out-of-sync lockfile
+pub fn frozen_install_command(root: &Path, lockfile_type: &str) -> &'static str {
+    match lockfile_type {
+        "pnpm" => "pnpm install --frozen-lockfile",
+        "yarn" if is_yarn_berry(root) => "yarn install --immutable",
+        "yarn" => "yarn install --frozen-lockfile",
+        "bun" => "bun install --frozen-lockfile",
+        _ => "npm ci",
+    }
+}
+
+fn is_yarn_berry(root: &Path) -> bool {
+    fs::read_to_string(root.join("yarn.lock")).is_ok_and(|c| c.contains("__metadata:"))
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+/// The root and the workspace packages the package manager knows about, as
+/// (directory, manifest)
+fn importers(root: &Path, lockfile_type: &str) -> Vec<(String, serde_json::Value)> {
+    let Some(manifest) = read_json(&root.join("package.json")) else {
+        return Vec::new();
+    };
+    let patterns: Vec<String> = if lockfile_type == "pnpm" {
+        fs::read_to_string(root.join("pnpm-workspace.yaml"))
+            .ok()
+            .and_then(|c| serde_yaml::from_str::<serde_yaml::Value>(&c).ok())
+            .and_then(|w| w.get("packages")?.as_sequence().cloned())
+            .map(|p| {
+                p.iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        let workspaces = manifest.get("workspaces");
+        workspaces
+            .and_then(|w| w.as_array().or_else(|| w.get("packages")?.as_array()))
+            .map(|p| {
+                p.iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut importers = vec![(String::new(), manifest)];
+    for path in expand_workspace_globs(root, &patterns) {
+        if let Some(member) = read_json(&root.join(&path).join("package.json")) {
+            importers.push((path, member));
+        }
+    }
+    importers
+}
+
+/// (name, spec, field) of every dependency `manifest` declares in `fields`
+fn declared<'a>(
+    manifest: &'a serde_json::Value,
+    fields: &[&'static str],
+) -> Vec<(&'a str, &'a str, &'static str)> {
+    let mut deps = Vec::new();
+    for &field in fields {
+        if let Some(map) = manifest.get(field).and_then(|d| d.as_object()) {
+            for (name, spec) in map {
+                if let Some(spec) = spec.as_str() {
+                    deps.push((name.as_str(), spec, field));
+                }
+            }
+        }
+    }
+    deps
+}
+
+/// The package and range a spec asks for, if it is a semver range or an `npm:` alias
+fn spec_range<'a>(name: &'a str, spec: &'a str) -> Option<(&'a str, &'a str)> {
+    let (name, range) = match spec.strip_prefix("npm:") {
+        Some(alias) => match alias.rfind('@').filter(|&i| i > 0) {
+            Some(i) => (&alias[..i], &alias[i + 1..]),
+            None => (alias, "*"),
+        },
+        None => (name, spec),
+    };
+    npm_range::parse_range(range).map(|_| (name, range))
+}
+
+/// The problem with `locked` (package, version) for a spec, if any
+fn unsatisfied(name: &str, spec: &str, locked: (&str, &str)) -> Option<SyncProblem> {
+    let (wanted, range) = spec_range(name, spec)?;
+    let (package, version) = locked;
+    if package != wanted {
+        return Some(SyncProblem::Unsatisfied {
+            locked: format!("{}@{}", package, version),
+        });
+    }
+    (npm_range::satisfies(version, range) == Some(false)).then(|| SyncProblem::Unsatisfied {
+        locked: version.to_string(),
+    })
+}
+
+/// The (package, version) npm resolves `name` to from the importer at `importer`
+fn npm_resolve(
+    packages: &serde_json::Map<String, serde_json::Value>,
+    importer: &str,
+    name: &str,
+) -> Option<(String, String)> {
+    // Node looks in each ancestor's node_modules, nearest first
+    let mut dir = importer;
+    let entry = loop {
+        let key = if dir.is_empty() {
+            format!("node_modules/{}", name)
+        } else {
+            format!("{}/node_modules/{}", dir, name)
+        };
+        if let Some(entry) = packages.get(&key) {
+            break entry;
+        }
+        if dir.is_empty() {
+            return None;
+        }
+        dir = dir.rsplit_once('/').map_or("", |(parent, _)| parent);
+    };
+
+    // Workspace and file: links point at another entry
+    let entry = if entry.get("link").and_then(|l| l.as_bool()) == Some(true) {
+        let target = entry.get("resolved")?.as_str()?;
+        packages.get(target).unwrap_or(entry)
+    } else {
+        entry
+    };
+    let package = entry.get("name").and_then(|n| n.as_str()).unwrap_or(name);
+    let version = entry
+        .get("version")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    Some((package.to_string(), version.to_string()))
+}
+
+/// The (package, version) a lockfileVersion 1 `dependencies` entry locks
+fn npm_v1_resolve(lock: &serde_json::Value, name: &str) -> Option<(String, String)> {
+    let version = lock
+        .get("dependencies")?
+        .get(name)?
+        .get("version")?
+        .as_str()?;
+    // Aliases are recorded as `npm:real@1.2.3`
+    match version
+        .strip_prefix("npm:")
+        .and_then(|a| a.rfind('@').filter(|&i| i > 0).map(|i| (a, i)))
+    {
+        Some((alias, i)) => Some((alias[..i].to_string(), alias[i + 1..].to_string())),
+        None => Some((name.to_string(), version.to_string())),
+    }
+}
+
+fn check_npm(root: &Path) -> Result<Option<Vec<OutOfSync>>> {
+    let Ok(content) = fs::read_to_string(root.join("package-lock.json")) else {
+        return Ok(None);
+    };
+    let lock: serde_json::Value = serde_json::from_str(&content)?;
+    let packages = lock.get("packages").and_then(|p| p.as_object());
+
+    let mut problems = Vec::new();
+    for (importer, manifest) in importers(root, "npm") {
+        for (name, spec, field) in declared(&manifest, NPM_FIELDS) {
+            // npm rejects workspace: outright; the monorepo checks report it
+            if spec.starts_with("workspace:") {
+                continue;
+            }
+            let locked = match packages {
+                Some(packages) => npm_resolve(packages, &importer, name),
+                None if importer.is_empty() => npm_v1_resolve(&lock, name),
+                None => None,
+            };
+            let problem = match locked {
+                Some((package, version)) => unsatisfied(name, spec, (&package, &version)),
+                None => {
+                    let optional_peer = field == "peerDependencies"
+                        && manifest
+                            .pointer(&format!(
+                                "/peerDependenciesMeta/{}/optional",
+                                name.replace('/', "~1")
+                            ))
+                            .and_then(|o| o.as_bool())
+                            == Some(true);
+                    (!optional_peer).then_some(SyncProblem::Missing)
+                }
+            };
+            if let Some(problem) = problem {
+                problems.push(OutOfSync {
+                    importer: importer.clone(),
+                    name: name.to_string(),
+                    spec: spec.to_string(),
+                    problem,
+                });
+            }
+        }
+    }
+    Ok(Some(problems))
+}
+
+fn yaml_str(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Specifiers and versions a pnpm importer records, by dependency name
+fn pnpm_importer(
+    importer: &serde_yaml::Value,
+) -> (BTreeMap<String, String>, BTreeMap<String, String>) {
+    let mut specifiers = BTreeMap::new();
+    let mut versions = BTreeMap::new();
+    // v5 keeps the specifiers in a map of their own
+    if let Some(map) = importer.get("specifiers").and_then(|s| s.as_mapping()) {
+        for (name, spec) in map {
+            if let (Some(name), Some(spec)) = (name.as_str(), yaml_str(spec)) {
+                specifiers.insert(name.to_string(), spec);
+            }
+        }
+    }
+    for &field in LOCKED_FIELDS {
+        let Some(map) = importer.get(field).and_then(|d| d.as_mapping()) else {
+            continue;
+        };
+        for (name, entry) in map {
+            let Some(name) = name.as_str() else {
+                continue;
+            };
+            if let Some(version) = yaml_str(entry) {
+                versions.insert(name.to_string(), version);
+                continue;
+            }
+            // v6 and v9: { specifier, version }
+            if let Some(spec) = entry.get("specifier").and_then(yaml_str) {
+                specifiers.insert(name.to_string(), spec);
+            }
+            if let Some(version) = entry.get("version").and_then(yaml_str) {
+                versions.insert(name.to_string(), version);
+            }
+        }
+    }
+    (specifiers, versions)
+}
+
+/// The (package, version) a pnpm importer version points at; `None` for links
+fn pnpm_locked(name: &str, version: &str) -> Option<(String, String)> {
+    if version.starts_with(|c: char| c.is_ascii_digit()) {
+        // Peer suffixes: `18.2.0(react@18.2.0)` (v6+) or `18.2.0_react@18.2.0` (v5)
+        let version = version.split(['(', '_']).next().unwrap_or(version);
+        return Some((name.to_string(), version.to_string()));
+    }
+    // Aliases: `/real@1.2.3` or `real@1.2.3`
+    parse_pnpm_package_ref(version)
+}
+
+fn check_pnpm(root: &Path) -> Result<Option<Vec<OutOfSync>>> {
+    let Ok(content) = fs::read_to_string(root.join("pnpm-lock.yaml")) else {
+        return Ok(None);
+    };
+    let lock: serde_yaml::Value = serde_yaml::from_str(&content)?;
+    let catalogs = Catalogs::load(root);
+
+    let mut problems = Vec::new();
+    for (importer, manifest) in importers(root, "pnpm") {
+        let key = if importer.is_empty() {
+            "."
+        } else {
+            importer.as_str()
+        };
+        // Lockfiles without workspaces record the root at the top level
+        let recorded = match lock.get("importers") {
+            Some(importers) => importers.get(key),
+            None if importer.is_empty() => Some(&lock),
+            None => None,
+        };
+        let (specifiers, versions) = recorded.map(pnpm_importer).unwrap_or_default();
+
+        let mut problem = |name: &str, spec: &str, problem| {
+            problems.push(OutOfSync {
+                importer: importer.clone(),
+                name: name.to_string(),
+                spec: spec.to_string(),
+                problem,
+            })
+        };
+        let deps = declared(&manifest, LOCKED_FIELDS);
+        for &(name, spec, _) in &deps {
+            match specifiers.get(name) {
+                None => problem(name, spec, SyncProblem::Missing),
+                Some(locked) if locked != spec => problem(
+                    name,
+                    spec,
+                    SyncProblem::SpecifierChanged {
+                        locked: locked.clone(),
+                    },
+                ),
+                Some(_) => {
+                    let range = catalogs.resolve(spec, name).unwrap_or(spec);
+                    let locked = versions.get(name).and_then(|v| pnpm_locked(name, v));
+                    if let Some(found) =
+                        locked.and_then(|(p, v)| unsatisfied(name, range, (&p, &v)))
+                    {
+                        problem(name, spec, found);
+                    }
+                }
+            }
+        }
+        let names: BTreeSet<&str> = deps.iter().map(|(name, _, _)| *name).collect();
+        for (name, locked) in &specifiers {
+            if !names.contains(name.as_str()) {
+                problem(name, locked, SyncProblem::Undeclared);
+            }
+        }
+    }
+    Ok(Some(problems))
+}
+
+/// Locked version by descriptor (`react@^18.2.0`, `react@npm:^18.2.0`) from a yarn.lock,
+/// classic or Berry
+fn yarn_entries(content: &str) -> BTreeMap<String, String> {
+    let mut entries = BTreeMap::new();
+    let mut descriptors: Vec<String> = Vec::new();
+    for line in content.lines() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            descriptors = line
+                .trim_end_matches(':')
+                .split(", ")
+                .map(|d| d.trim().trim_matches('"').to_string())
+                .collect();
+            continue;
+        }
+        let line = line.trim();
+        // `version "1.2.3"` (classic) or `version: 1.2.3` (Berry)
+        if let Some(version) = line.strip_prefix("version") {
+            let version = version.trim_start_matches(':').trim().trim_matches('"');
+            for descriptor in descriptors.drain(..) {
+                entries.insert(descriptor, version.to_string());
+            }
+        }
+    }
+    entries
+}
+
+fn check_yarn(root: &Path) -> Result<Option<Vec<OutOfSync>>> {
+    let Ok(content) = fs::read_to_string(root.join("yarn.lock")) else {
+        return Ok(None);
+    };
+    let entries = yarn_entries(&content);
+
+    let mut problems = Vec::new();
+    for (importer, manifest) in importers(root, "yarn") {
+        for (name, spec, _) in declared(&manifest, LOCKED_FIELDS) {
+            let locked = [
+                format!("{}@{}", name, spec),
+                format!("{}@npm:{}", name, spec),
+            ]
+            .iter()
+            .find_map(|descriptor| entries.get(descriptor));
+            let problem = match locked {
+                Some(version) => {
+                    // Aliases lock the real package under the alias's descriptor
+                    let package = spec_range(name, spec).map_or(name, |(package, _)| package);
+                    unsatisfied(name, spec, (package, version))
+                }
+                // Protocols (workspace:, patch:, git URLs) resolve to descriptors of
+                // their own; only ranges and tags are known to need an entry
+                None if spec_range(name, spec).is_some() || !spec.contains([':', '/']) => {
+                    Some(SyncProblem::Missing)
+                }
+                None => None,
+            };
+            if let Some(problem) = problem {
+                problems.push(OutOfSync {
+                    importer: importer.clone(),
+                    name: name.to_string(),
+                    spec: spec.to_string(),
+                    problem,
+                });
+            }
+        }
+    }
+    Ok(Some(problems))
+}
+
+/// package.json entries under `root` that a frozen install from its `lockfile_type`
+/// lockfile would reject
+///
+/// Returns `None` when the lockfile can't be compared (bun.lockb) or is missing.
+pub fn find_out_of_sync(root: &Path, lockfile_type: &str) -> Result<Option<Vec<OutOfSync>>> {
+    match lockfile_type {
+        "npm" => check_npm(root),
+        "pnpm" => check_pnpm(root),
+        "yarn" => check_yarn(root),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn messages(problems: Vec<OutOfSync>) -> Vec<String> {
+        problems.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_npm_resolves_through_workspaces() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "package.json",
+            r#"{
+                "workspaces": ["packages/*"],
+                "dependencies": { "react": "^18.2.0", "lodash": "^4.17.21", "str": "npm:string-width@^4" },
+                "peerDependencies": { "typescript": "^5", "sass": "^1" },
+                "peerDependenciesMeta": { "sass": { "optional": true } }
+            }"#,
+        );
+        write(
+            root,
+            "packages/web/package.json",
+            r#"{ "name": "web", "dependencies": { "react": "^17.0.0", "ui": "^1.0.0", "zod": "^3.22.0" } }"#,
+        );
+        write(
+            root,
+            "packages/ui/package.json",
+            r#"{ "name": "ui", "version": "2.0.0" }"#,
+        );
+        write(
+            root,
+            "package-lock.json",
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": { "workspaces": ["packages/*"] },
+                    "node_modules/react": { "version": "18.2.0" },
+                    "node_modules/str": { "name": "string-width", "version": "5.1.2" },
+                    "node_modules/typescript": { "version": "5.4.5", "peer": true },
+                    "node_modules/ui": { "resolved": "packages/ui", "link": true },
+                    "node_modules/zod": { "version": "3.23.8" },
+                    "packages/ui": { "name": "ui", "version": "2.0.0" },
+                    "packages/web/node_modules/react": { "version": "17.0.2" }
+                }
+            }"#,
+        );
+
+        let problems = find_out_of_sync(root, "npm").unwrap().unwrap();
+        assert_eq!(
+            messages(problems),
+            vec![
+                "lodash@^4.17.21 is missing from the lockfile",
+                "str@npm:string-width@^4 is locked at 5.1.2",
+                "packages/web: ui@^1.0.0 is locked at 2.0.0",
+            ]
+        );
+        assert_eq!(frozen_install_command(root, "npm"), "npm ci");
+    }
+
+    #[test]
+    fn test_pnpm_compares_importer_specifiers() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "package.json",
+            r#"{ "dependencies": { "react": "^18.2.0", "vue": "^3.4.0" }, "devDependencies": { "vite": "catalog:" } }"#,
+        );
+        write(
+            root,
+            "pnpm-workspace.yaml",
+            "packages:\n  - apps/*\ncatalog:\n  vite: ^5.0.0\n",
+        );
+        write(
+            root,
+            "apps/docs/package.json",
+            r#"{ "dependencies": { "ui": "workspace:*" } }"#,
+        );
+        write(
+            root,
+            "pnpm-lock.yaml",
+            r#"lockfileVersion: '9.0'
+importers:
+  .:
+    dependencies:
+      react:
+        specifier: ^18.2.0
+        version: 18.2.0
+      vue:
+        specifier: ^3.3.0
+        version: 3.3.4(typescript@5.4.5)
+      left-pad:
+        specifier: ^1.3.0
+        version: 1.3.0
+    devDependencies:
+      vite:
+        specifier: 'catalog:'
+        version: 4.5.3
+"#,
+        );
+
+        let problems = find_out_of_sync(root, "pnpm").unwrap().unwrap();
+        assert_eq!(
+            messages(problems),
+            vec![
+                "vue@^3.4.0 is locked as vue@^3.3.0",
+                "vite@catalog: is locked at 4.5.3",
+                "left-pad@^1.3.0 is locked but no longer declared",
+                "apps/docs: ui@workspace:* is missing from the lockfile",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_yarn_needs_an_entry_per_descriptor() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "package.json",
+            r#"{ "dependencies": { "react": "^18.2.0", "@scope/pkg": "^1.1.0", "local": "file:./local" }, "devDependencies": { "jest": "^29.0.0" } }"#,
+        );
+        write(
+            root,
+            "yarn.lock",
+            r#"# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.
+# yarn lockfile v1
+
+
+"@scope/pkg@^1.0.0", "@scope/pkg@^1.1.0":
+  version "1.0.5"
+  resolved "https://registry.yarnpkg.com/@scope/pkg/-/pkg-1.0.5.tgz"
+
+react@^18.2.0:
+  version "18.2.0"
+"#,
+        );
+
+        let problems = find_out_of_sync(root, "yarn").unwrap().unwrap();
+        assert_eq!(
+            messages(problems),
+            vec![
+                "@scope/pkg@^1.1.0 is locked at 1.0.5",
+                "jest@^29.0.0 is missing from the lockfile",
+            ]
+        );
+        assert_eq!(
+            frozen_install_command(root, "yarn"),
+            "yarn install --frozen-lockfile"
+        );
+    }
+}
@@ -2,6 +2,17 @@ pub mod toolchain;
 pub mod lockfile_checks;
 pub mod dependencies;
 pub mod frameworks;
+pub mod ci_config;
+pub mod installed_tree;
+pub mod patches;
+pub mod overrides;
+pub mod workspace;
+pub mod monorepo;
+pub mod custom;
+pub mod baseline;
+pub mod unused;
+pub mod imports;
+pub mod lockfile_sync;
 
 use anyhow::Result;
 use clap::ValueEnum;
@@ -11,6 +22,81 @@ use std::path::Path;
 use crate::config::ZenvoConfig;
 use crate::lockfile::EnvLock;
 
+/// Names of all built-in checks, for validating `checks.disabled` and `severity_overrides`
+///
+/// `*` stands for a package, package manager or cache name.
+pub const CHECK_NAMES: &[&str] = &[
+    "* accessible",
+    "* empty",
+    "* exists",
+    "* unreadable",
+    "Binaries installed",
+    "CI Node version match",
+    "CI Node version pinned",
+    "Catalog entries resolve",
+    "Consistent external ranges",
+    "Corepack available",
+    "Corepack enabled",
+    "Deprecated: *",
+    "ESLint config",
+    "Engines compliance",
+    "Hidden lockfile in sync",
+    "Internal dependency ranges",
+    "Lockfile exists",
+    "Lockfile integrity",
+    "Lockfile not corrupted",
+    "Next.js cache corrupted",
+    "Next.js cache incomplete",
+    "Next.js cache unreadable",
+    "Next.js cache valid",
+    "Next.js/Node compatibility",
+    "No phantom dependencies",
+    "No unused dependencies",
+    "Node version match",
+    "Node.js accessible",
+    "Orphaned patch",
+    "Override conflict",
+    "Overrides current",
+    "Package manager match",
+    "Package manager version",
+    "Patch applies",
+    "Patch version match",
+    "Patches valid",
+    "Peer dependencies",
+    "Peer dependency conflict",
+    "Phantom dependencies",
+    "Prettier config",
+    "React/ReactDOM match",
+    "Redundant override",
+    "Single lockfile",
+    "Single version policy",
+    "TypeScript config",
+    "TypeScript/Node compatibility",
+    "Unused dependencies",
+    "Unused override",
+    "Workspace detected",
+    "Workspace protocol",
+    "node_modules exists",
+    "node_modules matches lockfile",
+    "package.json exists",
+    "package.json in sync",
+    "package.json readable",
+    "package.json valid",
+];
+
+/// Whether `name` is a built-in check (case-insensitive, like `is_check_disabled`)
+pub fn is_known_check(name: &str) -> bool {
+    let name = name.to_lowercase();
+    CHECK_NAMES.iter().any(|known| {
+        let known = known.to_lowercase();
+        match (known.strip_prefix("* "), known.strip_suffix('*')) {
+            (Some(suffix), _) => name.len() > suffix.len() && name.ends_with(suffix),
+            (_, Some(prefix)) => name.len() > prefix.len() && name.starts_with(prefix),
+            _ => name == known,
+        }
+    })
+}
+
 /// Valid check categories for the doctor command
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum CheckCategory {
@@ -24,6 +110,19 @@ pub enum CheckCategory {
     Frameworks,
 }
 
+impl CheckCategory {
+    /// Category named `name` (as used in `CheckResult::category`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "toolchain" => Some(CheckCategory::Toolchain),
+            "lockfile" => Some(CheckCategory::Lockfile),
+            "deps" => Some(CheckCategory::Deps),
+            "frameworks" => Some(CheckCategory::Frameworks),
+            _ => None,
+        }
+    }
+}
+
 
 /// Result of checking for package.json
 #[derive(Debug)]
@@ -71,87 +170,44 @@ pub fn check_package_json() -> PackageJsonStatus {
 
 /// Check if running in a monorepo/workspace context
 pub fn detect_workspace_root() -> Option<WorkspaceInfo> {
-    let pkg_status = check_package_json();
-
-    if let PackageJsonStatus::Valid(pkg) = pkg_status {
-        // Check for npm/yarn workspaces
-        if let Some(workspaces) = pkg.get("workspaces") {
-            let packages = if let Some(arr) = workspaces.as_array() {
-                arr.iter()
-                    .filter_map(|v| v.as_str())
-                    .map(|s| s.to_string())
-                    .collect()
-            } else if let Some(obj) = workspaces.as_object() {
-                // Yarn workspace format: { "packages": [...] }
-                obj.get("packages")
-                    .and_then(|p| p.as_array())
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|v| v.as_str())
-                            .map(|s| s.to_string())
-                            .collect()
-                    })
-                    .unwrap_or_default()
-            } else {
-                Vec::new()
-            };
-
-            if !packages.is_empty() {
-                return Some(WorkspaceInfo {
-                    workspace_type: WorkspaceType::NpmYarn,
-                    packages,
-                });
-            }
-        }
-    }
-
-    // Check for pnpm workspaces
-    if Path::new("pnpm-workspace.yaml").exists() {
-        if let Ok(content) = std::fs::read_to_string("pnpm-workspace.yaml") {
-            if let Ok(workspace) = serde_yaml::from_str::<serde_yaml::Value>(&content) {
-                if let Some(packages) = workspace.get("packages").and_then(|p| p.as_sequence()) {
-                    let pkg_list: Vec<String> = packages
-                        .iter()
-                        .filter_map(|v| v.as_str())
-                        .map(|s| s.to_string())
-                        .collect();
-
-                    if !pkg_list.is_empty() {
-                        return Some(WorkspaceInfo {
-                            workspace_type: WorkspaceType::Pnpm,
-                            packages: pkg_list,
-                        });
-                    }
-                }
-            }
-        }
-    }
-
-    // Check for Nx monorepo
-    if Path::new("nx.json").exists() {
-        return Some(WorkspaceInfo {
-            workspace_type: WorkspaceType::Nx,
-            packages: Vec::new(), // Nx has different project structure
-        });
-    }
-
-    // Check for Turborepo
-    if Path::new("turbo.json").exists() {
-        return Some(WorkspaceInfo {
-            workspace_type: WorkspaceType::Turbo,
-            packages: Vec::new(),
-        });
-    }
-
-    // Check for Lerna
-    if Path::new("lerna.json").exists() {
-        return Some(WorkspaceInfo {
-            workspace_type: WorkspaceType::Lerna,
-            packages: Vec::new(),
-        });
-    }
-
-    None
+    let root = Path::new(".");
+
+    let has_pm_workspaces = match check_package_json() {
+        PackageJsonStatus::Valid(pkg) => pkg.get("workspaces").is_some_and(|w| {
+            w.as_array()
+                .or_else(|| w.get("packages").and_then(|p| p.as_array()))
+                .is_some_and(|arr| !arr.is_empty())
+        }),
+        _ => false,
+    };
+    let has_pnpm_workspace = std::fs::read_to_string("pnpm-workspace.yaml")
+        .ok()
+        .and_then(|c| serde_yaml::from_str::<serde_yaml::Value>(&c).ok())
+        .and_then(|w| w.get("packages").and_then(|p| p.as_sequence()).map(|s| !s.is_empty()))
+        .unwrap_or(false);
+
+    let workspace_type = if has_pm_workspaces {
+        WorkspaceType::NpmYarn
+    } else if has_pnpm_workspace {
+        WorkspaceType::Pnpm
+    } else if Path::new("nx.json").exists() {
+        WorkspaceType::Nx
+    } else if Path::new("turbo.json").exists() {
+        WorkspaceType::Turbo
+    } else if Path::new("lerna.json").exists() {
+        WorkspaceType::Lerna
+    } else {
+        return None;
+    };
+
+    let patterns = workspace::workspace_patterns(root);
+    let packages = workspace::discover_packages(root, &patterns);
+
+    Some(WorkspaceInfo {
+        workspace_type,
+        patterns,
+        packages,
+    })
 }
 
 /// Type of workspace/monorepo
@@ -180,7 +236,9 @@ impl std::fmt::Display for WorkspaceType {
 #[derive(Debug, Clone)]
 pub struct WorkspaceInfo {
     pub workspace_type: WorkspaceType,
-    pub packages: Vec<String>,
+    /// Member globs as declared (package.json, pnpm-workspace.yaml, lerna.json)
+    pub patterns: Vec<String>,
+    pub packages: Vec<workspace::WorkspacePackage>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -316,17 +374,20 @@ pub fn run_all_checks(
     }
 
     // Check for workspace/monorepo
-    if let Some(workspace) = detect_workspace_root() {
+    let workspace = detect_workspace_root();
+    if let Some(ref workspace) = workspace {
         results.push(CheckResult::info(
             "Workspace detected",
             "project",
             &format!(
                 "Running in {} context{}",
                 workspace.workspace_type,
-                if workspace.packages.is_empty() {
-                    String::new()
-                } else {
+                if !workspace.packages.is_empty() {
                     format!(" ({} packages)", workspace.packages.len())
+                } else if !workspace.patterns.is_empty() {
+                    format!(" (no packages match {})", workspace.patterns.join(", "))
+                } else {
+                    String::new()
                 }
             ),
         ));
@@ -343,6 +404,7 @@ pub fn run_all_checks(
     // Toolchain checks
     if run_toolchain {
         results.extend(toolchain::run_checks(&current, env_lock)?);
+        results.extend(ci_config::run_checks(env_lock)?);
     }
 
     // Lockfile checks
@@ -352,7 +414,13 @@ pub fn run_all_checks(
 
     // Dependency checks
     if run_deps {
-        results.extend(dependencies::run_checks()?);
+        results.extend(dependencies::run_checks(config)?);
+        results.extend(unused::run_checks(config)?);
+        results.extend(patches::run_checks()?);
+        results.extend(overrides::run_checks()?);
+        if let Some(ref workspace) = workspace {
+            results.extend(monorepo::run_checks(workspace, config)?);
+        }
     }
 
     // Framework checks
@@ -360,6 +428,9 @@ pub fn run_all_checks(
         results.extend(frameworks::run_checks()?);
     }
 
+    // Checks declared in the config file
+    results.extend(custom::run_checks(config, category)?);
+
     // Apply config (filter disabled checks, apply severity overrides)
     if let Some(cfg) = config {
         results = apply_config_to_results(results, cfg);
@@ -368,6 +439,45 @@ pub fn run_all_checks(
     Ok(results)
 }
 
+/// Run the checks that apply to a single workspace package in the current directory
+///
+/// Toolchain and lockfile checks belong to the workspace root and are skipped.
+pub fn run_package_checks(
+    category: Option<CheckCategory>,
+    config: &Option<ZenvoConfig>,
+) -> Result<Vec<CheckResult>> {
+    let mut results = Vec::new();
+
+    match check_package_json() {
+        PackageJsonStatus::Valid(_) => results.push(CheckResult::pass("package.json valid", "project")),
+        PackageJsonStatus::Invalid(err) => results.push(
+            CheckResult::error(
+                "package.json valid",
+                "project",
+                &format!("package.json is invalid JSON: {}", err),
+            )
+            .with_fix("Fix the JSON syntax in package.json"),
+        ),
+        PackageJsonStatus::Missing | PackageJsonStatus::Unreadable(_) => return Ok(results),
+    }
+
+    if category.is_none() || category == Some(CheckCategory::Deps) {
+        results.extend(dependencies::run_checks(config)?);
+        results.extend(unused::run_checks(config)?);
+        results.extend(patches::run_checks()?);
+    }
+
+    if category.is_none() || category == Some(CheckCategory::Frameworks) {
+        results.extend(frameworks::run_checks()?);
+    }
+
+    if let Some(cfg) = config {
+        results = apply_config_to_results(results, cfg);
+    }
+
+    Ok(results)
+}
+
 /// Apply configuration settings to check results
 /// - Filters out disabled checks
 /// - Applies severity overrides
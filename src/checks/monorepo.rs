@@ -0,0 +1,429 @@
+//! Consistency checks across workspace packages: `workspace:` protocol usage,
+//! internal ranges, diverging external ranges, pnpm catalogs and the single
+//! version policy.
+
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use super::overrides::load_lock_graph;
+use super::{CheckResult, WorkspaceInfo};
+use crate::config::ZenvoConfig;
+use crate::utils::npm_range;
+
+/// Dependency fields compared across packages; peer ranges are meant to be wide
+const DEP_FIELDS: [&str; 3] = ["dependencies", "devDependencies", "optionalDependencies"];
+
+/// A dependency declared by one workspace package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    /// Declaring package (`(root)` for the workspace root)
+    pub package: String,
+    pub field: String,
+    pub name: String,
+    pub spec: String,
+}
+
+/// pnpm catalogs from pnpm-workspace.yaml (`catalog:` is the `default` catalog)
+#[derive(Debug, Default)]
+pub struct Catalogs {
+    catalogs: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Catalogs {
+    pub fn load(root: &Path) -> Self {
+        let mut catalogs: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        let Some(workspace) = fs::read_to_string(root.join("pnpm-workspace.yaml"))
+            .ok()
+            .and_then(|c| serde_yaml::from_str::<serde_yaml::Value>(&c).ok())
+        else {
+            return Self { catalogs };
+        };
+
+        let read_map = |value: &serde_yaml::Value| -> BTreeMap<String, String> {
+            value
+                .as_mapping()
+                .map(|m| {
+                    m.iter()
+                        .filter_map(|(k, v)| Some((k.as_str()?.to_string(), v.as_str()?.to_string())))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        if let Some(default) = workspace.get("catalog") {
+            catalogs.insert("default".to_string(), read_map(default));
+        }
+        if let Some(named) = workspace.get("catalogs").and_then(|c| c.as_mapping()) {
+            for (name, entries) in named {
+                if let Some(name) = name.as_str() {
+                    catalogs.entry(name.to_string()).or_default().extend(read_map(entries));
+                }
+            }
+        }
+
+        Self { catalogs }
+    }
+
+    /// Resolve `catalog:` / `catalog:<name>` for a dependency
+    pub fn resolve(&self, spec: &str, dependency: &str) -> Option<&str> {
+        let catalog = spec.strip_prefix("catalog:")?.trim();
+        let catalog = if catalog.is_empty() { "default" } else { catalog };
+        self.catalogs
+            .get(catalog)
+            .and_then(|c| c.get(dependency))
+            .map(|s| s.as_str())
+    }
+}
+
+/// Read every dependency declaration from the root and member manifests
+pub fn collect_declarations(root: &Path, workspace: &WorkspaceInfo) -> Vec<Declaration> {
+    let mut manifests = vec![("(root)".to_string(), root.join("package.json"))];
+    manifests.extend(
+        workspace
+            .packages
+            .iter()
+            .map(|p| (p.name.clone(), root.join(&p.path).join("package.json"))),
+    );
+
+    let mut declarations = Vec::new();
+    for (package, path) in manifests {
+        let Some(pkg) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        else {
+            continue;
+        };
+        for field in DEP_FIELDS.iter().chain(std::iter::once(&"peerDependencies")) {
+            if let Some(deps) = pkg.get(*field).and_then(|d| d.as_object()) {
+                for (name, spec) in deps {
+                    if let Some(spec) = spec.as_str() {
+                        declarations.push(Declaration {
+                            package: package.clone(),
+                            field: field.to_string(),
+                            name: name.clone(),
+                            spec: spec.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    declarations
+}
+
+/// Whether the project installs with npm, which rejects the `workspace:` protocol
+fn uses_npm(root: &Path) -> bool {
+    let package_manager = fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .and_then(|p| p.get("packageManager").and_then(|v| v.as_str()).map(|s| s.to_string()));
+    match package_manager {
+        Some(pm) => pm.starts_with("npm@"),
+        None => {
+            root.join("package-lock.json").exists()
+                && !root.join("pnpm-lock.yaml").exists()
+                && !root.join("yarn.lock").exists()
+        }
+    }
+}
+
+/// Format the first few items of a list, then "and N more"
+fn summarize(items: &[String]) -> String {
+    let mut shown: Vec<String> = items.iter().take(3).cloned().collect();
+    if items.len() > 3 {
+        shown.push(format!("and {} more", items.len() - 3));
+    }
+    shown.join(", ")
+}
+
+/// Problems with dependencies on sibling workspace packages
+pub fn check_internal_dependencies(
+    declarations: &[Declaration],
+    workspace: &WorkspaceInfo,
+    npm: bool,
+) -> (Vec<String>, Vec<String>) {
+    let versions: BTreeMap<&str, Option<&str>> = workspace
+        .packages
+        .iter()
+        .map(|p| (p.name.as_str(), p.version.as_deref()))
+        .collect();
+
+    let mut protocol = Vec::new();
+    let mut ranges = Vec::new();
+
+    for decl in declarations {
+        let sibling = versions.get(decl.name.as_str());
+
+        if let Some(range) = decl.spec.strip_prefix("workspace:") {
+            if npm {
+                protocol.push(format!(
+                    "{} uses `{}` for {} but npm doesn't support the workspace: protocol",
+                    decl.package, decl.spec, decl.name
+                ));
+                continue;
+            }
+            // `workspace:alias@range` points at another package
+            let (target, range) = match range.rsplit_once('@') {
+                Some((alias, r)) if !alias.is_empty() => (alias, r),
+                _ => (decl.name.as_str(), range),
+            };
+            let Some(version) = versions.get(target) else {
+                protocol.push(format!(
+                    "{} depends on {} via `{}` but no workspace package is named {}",
+                    decl.package, decl.name, decl.spec, target
+                ));
+                continue;
+            };
+            if matches!(range, "*" | "^" | "~" | "") {
+                continue;
+            }
+            if let Some(version) = version {
+                if npm_range::satisfies(version, range) == Some(false) {
+                    ranges.push(format!(
+                        "{} wants {}@{} but the workspace has {}",
+                        decl.package, target, range, version
+                    ));
+                }
+            }
+        } else if let Some(Some(version)) = sibling {
+            // A plain range that misses the sibling installs a registry copy instead
+            if npm_range::satisfies(version, &decl.spec) == Some(false) {
+                ranges.push(format!(
+                    "{} wants {}@{} but the workspace has {}",
+                    decl.package, decl.name, decl.spec, version
+                ));
+            }
+        }
+    }
+
+    (protocol, ranges)
+}
+
+/// External dependencies declared with different specs across packages
+pub fn find_range_conflicts(
+    declarations: &[Declaration],
+    workspace: &WorkspaceInfo,
+    catalogs: &Catalogs,
+) -> BTreeMap<String, BTreeMap<String, Vec<String>>> {
+    let internal: BTreeSet<&str> = workspace.packages.iter().map(|p| p.name.as_str()).collect();
+
+    // name -> effective spec -> declaring packages
+    let mut specs: BTreeMap<String, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+    for decl in declarations {
+        if internal.contains(decl.name.as_str()) || !DEP_FIELDS.contains(&decl.field.as_str()) {
+            continue;
+        }
+        let spec = catalogs
+            .resolve(&decl.spec, &decl.name)
+            .unwrap_or(&decl.spec)
+            .to_string();
+        let packages = specs.entry(decl.name.clone()).or_default().entry(spec).or_default();
+        if !packages.contains(&decl.package) {
+            packages.push(decl.package.clone());
+        }
+    }
+
+    specs.retain(|_, by_spec| by_spec.len() > 1);
+    specs
+}
+
+pub fn run_checks(workspace: &WorkspaceInfo, config: &Option<ZenvoConfig>) -> Result<Vec<CheckResult>> {
+    let mut results = Vec::new();
+    if workspace.packages.is_empty() {
+        return Ok(results);
+    }
+
+    let root = Path::new(".");
+    let declarations = collect_declarations(root, workspace);
+    let catalogs = Catalogs::load(root);
+
+    // workspace: protocol and internal ranges
+    let (protocol, ranges) = check_internal_dependencies(&declarations, workspace, uses_npm(root));
+    if protocol.is_empty() {
+        results.push(CheckResult::pass("Workspace protocol", "deps"));
+    } else {
+        results.push(
+            CheckResult::error(
+                "Workspace protocol",
+                "deps",
+                &format!("Invalid workspace: references: {}", summarize(&protocol)),
+            )
+            .with_fix("Point workspace: specs at existing workspace packages, or use plain ranges with npm"),
+        );
+    }
+    if ranges.is_empty() {
+        results.push(CheckResult::pass("Internal dependency ranges", "deps"));
+    } else {
+        results.push(
+            CheckResult::warning(
+                "Internal dependency ranges",
+                "deps",
+                &format!(
+                    "{} internal range(s) don't match the local package: {}",
+                    ranges.len(),
+                    summarize(&ranges)
+                ),
+            )
+            .with_fix("Update the range to the sibling's current version (or use `workspace:^` with pnpm/yarn)"),
+        );
+    }
+
+    // catalog: entries
+    let unresolved: Vec<String> = declarations
+        .iter()
+        .filter(|d| d.spec.starts_with("catalog:") && catalogs.resolve(&d.spec, &d.name).is_none())
+        .map(|d| format!("{} {}@{}", d.package, d.name, d.spec))
+        .collect();
+    if declarations.iter().any(|d| d.spec.starts_with("catalog:")) {
+        if unresolved.is_empty() {
+            results.push(CheckResult::pass("Catalog entries resolve", "deps"));
+        } else {
+            results.push(
+                CheckResult::error(
+                    "Catalog entries resolve",
+                    "deps",
+                    &format!("No catalog entry for: {}", summarize(&unresolved)),
+                )
+                .with_fix("Add the missing entries to `catalog`/`catalogs` in pnpm-workspace.yaml"),
+            );
+        }
+    }
+
+    // Diverging external ranges
+    let conflicts = find_range_conflicts(&declarations, workspace, &catalogs);
+    let policy: &[String] = config
+        .as_ref()
+        .map(|c| c.policies.single_version_packages.as_slice())
+        .unwrap_or(&[]);
+    let unpoliced: Vec<String> = conflicts
+        .iter()
+        .filter(|(name, _)| !policy.contains(name))
+        .map(|(name, by_spec)| {
+            let specs: Vec<String> = by_spec
+                .iter()
+                .map(|(spec, packages)| format!("{} in {}", spec, packages.join("/")))
+                .collect();
+            format!("{} ({})", name, specs.join(" vs "))
+        })
+        .collect();
+    if unpoliced.is_empty() {
+        results.push(CheckResult::pass("Consistent external ranges", "deps"));
+    } else {
+        results.push(
+            CheckResult::warning(
+                "Consistent external ranges",
+                "deps",
+                &format!(
+                    "{} dependencies are declared at different ranges: {}",
+                    unpoliced.len(),
+                    summarize(&unpoliced)
+                ),
+            )
+            .with_fix("Align the ranges, or move them to a pnpm catalog"),
+        );
+    }
+
+    // Single version policy: one declared spec and one locked version
+    if !policy.is_empty() {
+        let graph = load_lock_graph(root).ok().flatten();
+        let mut violations = Vec::new();
+        for name in policy {
+            if let Some(by_spec) = conflicts.get(name) {
+                let specs: Vec<&str> = by_spec.keys().map(|s| s.as_str()).collect();
+                violations.push(format!("{} declared as {}", name, specs.join(", ")));
+            } else if let Some(locked) = graph.as_ref().and_then(|g| g.packages.get(name)) {
+                if locked.len() > 1 {
+                    let versions: Vec<&str> = locked.iter().map(|s| s.as_str()).collect();
+                    violations.push(format!("{} locked at {}", name, versions.join(", ")));
+                }
+            }
+        }
+        if violations.is_empty() {
+            results.push(CheckResult::pass("Single version policy", "deps"));
+        } else {
+            results.push(
+                CheckResult::error(
+                    "Single version policy",
+                    "deps",
+                    &format!("Multiple versions of: {}", summarize(&violations)),
+                )
+                .with_fix("Use one range everywhere (or an override) and reinstall"),
+            );
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::workspace::WorkspacePackage;
+    use crate::checks::WorkspaceType;
+
+    fn decl(package: &str, name: &str, spec: &str) -> Declaration {
+        Declaration {
+            package: package.into(),
+            field: "dependencies".into(),
+            name: name.into(),
+            spec: spec.into(),
+        }
+    }
+
+    fn workspace() -> WorkspaceInfo {
+        let package = |name: &str, version: &str| WorkspacePackage {
+            name: name.into(),
+            path: format!("packages/{}", name),
+            version: Some(version.into()),
+            dependencies: Vec::new(),
+        };
+        WorkspaceInfo {
+            workspace_type: WorkspaceType::Pnpm,
+            patterns: vec!["packages/*".into()],
+            packages: vec![package("core", "2.1.0"), package("web", "1.0.0")],
+        }
+    }
+
+    #[test]
+    fn test_internal_dependencies() {
+        let declarations = vec![
+            decl("web", "core", "workspace:^"),
+            decl("web", "core", "workspace:^1.0.0"),
+            decl("web", "core", "^2.0.0"),
+            decl("web", "core", "^1.5.0"),
+            decl("web", "utils", "workspace:*"),
+        ];
+        let (protocol, ranges) = check_internal_dependencies(&declarations, &workspace(), false);
+        assert_eq!(protocol.len(), 1);
+        assert!(protocol[0].contains("utils"));
+        assert_eq!(ranges.len(), 2);
+
+        let (protocol, _) = check_internal_dependencies(&declarations[..1], &workspace(), true);
+        assert!(protocol[0].contains("npm"));
+    }
+
+    #[test]
+    fn test_range_conflicts_with_catalog() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("pnpm-workspace.yaml"),
+            "packages: ['packages/*']\ncatalog:\n  react: ^18.2.0\ncatalogs:\n  legacy:\n    react: ^17.0.0\n",
+        )
+        .unwrap();
+        let catalogs = Catalogs::load(dir.path());
+        assert_eq!(catalogs.resolve("catalog:", "react"), Some("^18.2.0"));
+        assert_eq!(catalogs.resolve("catalog:legacy", "react"), Some("^17.0.0"));
+        assert_eq!(catalogs.resolve("catalog:missing", "react"), None);
+
+        let declarations = vec![
+            decl("web", "react", "catalog:"),
+            decl("core", "react", "^18.2.0"),
+            decl("web", "lodash", "^4.17.0"),
+            decl("core", "lodash", "^4.17.21"),
+        ];
+        let conflicts = find_range_conflicts(&declarations, &workspace(), &catalogs);
+        assert_eq!(conflicts.keys().collect::<Vec<_>>(), vec!["lodash"]);
+    }
+}
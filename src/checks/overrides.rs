@@ -0,0 +1,670 @@
+//! Audit of npm `overrides`, yarn `resolutions` and `pnpm.overrides` against the
+//! lockfile graph: overrides that match nothing, force versions outside a dependent's
+//! declared range, or pin what natural resolution would pick anyway.
+
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use super::installed_tree::parse_pnpm_package_ref;
+use super::CheckResult;
+use crate::utils::npm_range;
+
+/// Name used for edges declared by the project's own package.json
+const ROOT: &str = "(root)";
+
+/// Where an override was declared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverrideSource {
+    Npm,
+    Yarn,
+    Pnpm,
+}
+
+/// A single flattened override rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Override {
+    pub source: OverrideSource,
+    /// Key as written (`foo`, `**/foo`, `a>b`)
+    pub key: String,
+    /// Package the override applies to
+    pub name: String,
+    /// Optional version selector (`foo@^1` only overrides 1.x)
+    pub selector: Option<String>,
+    /// Direct parent the override is scoped to, if any
+    pub parent: Option<String>,
+    /// Replacement spec (`1.2.3`, `^2.0.0`, `$foo`, `-`)
+    pub target: String,
+}
+
+impl Override {
+    /// Human-readable location of the override in package.json
+    pub fn label(&self) -> String {
+        match self.source {
+            OverrideSource::Npm => format!("overrides[\"{}\"]", self.key),
+            OverrideSource::Yarn => format!("resolutions[\"{}\"]", self.key),
+            OverrideSource::Pnpm => format!("pnpm.overrides[\"{}\"]", self.key),
+        }
+    }
+}
+
+/// Split `name@range` (scoped names keep their leading `@`)
+fn split_name_selector(key: &str) -> (String, Option<String>) {
+    let at = if let Some(unscoped) = key.strip_prefix('@') {
+        unscoped.find('@').map(|i| i + 1)
+    } else {
+        key.find('@')
+    };
+    match at {
+        Some(i) => (key[..i].to_string(), Some(key[i + 1..].to_string())),
+        None => (key.to_string(), None),
+    }
+}
+
+/// Flatten npm `overrides`, which nest by parent package
+fn parse_npm_overrides(
+    map: &serde_json::Map<String, serde_json::Value>,
+    parent: Option<&str>,
+    prefix: &str,
+    out: &mut Vec<Override>,
+) {
+    for (key, value) in map {
+        if key == "." {
+            continue;
+        }
+        let (name, selector) = split_name_selector(key);
+        let full_key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        let own_target = match value {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Object(o) => o.get(".").and_then(|v| v.as_str()).map(|s| s.to_string()),
+            _ => None,
+        };
+        if let Some(target) = own_target {
+            out.push(Override {
+                source: OverrideSource::Npm,
+                key: full_key.clone(),
+                name: name.clone(),
+                selector,
+                parent: parent.map(|p| p.to_string()),
+                target,
+            });
+        }
+        if let serde_json::Value::Object(children) = value {
+            parse_npm_overrides(children, Some(&name), &full_key, out);
+        }
+    }
+}
+
+/// Parse a yarn resolution key (`foo`, `**/foo`, `a/foo`, `@s/a/**/@s/b@^1`)
+fn parse_yarn_resolution_key(key: &str) -> Option<(String, Option<String>, Option<String>)> {
+    let mut segments = Vec::new();
+    let mut parts = key.split('/');
+    while let Some(part) = parts.next() {
+        if part.starts_with('@') && !part[1..].contains('@') {
+            segments.push(format!("{}/{}", part, parts.next()?));
+        } else {
+            segments.push(part.to_string());
+        }
+    }
+
+    let (last, ancestors) = segments.split_last()?;
+    let (name, selector) = split_name_selector(last);
+    // `a/**/b` matches b anywhere below a, which the graph can't scope cheaply
+    let parent = match ancestors.last().map(|s| s.as_str()) {
+        Some("**") | None => None,
+        Some(p) => Some(split_name_selector(p).0),
+    };
+    Some((name, selector, parent))
+}
+
+/// Collect overrides from package.json (and pnpm-workspace.yaml)
+pub fn collect_overrides(root: &Path, pkg: &serde_json::Value) -> Vec<Override> {
+    let mut overrides = Vec::new();
+
+    if let Some(map) = pkg.get("overrides").and_then(|o| o.as_object()) {
+        parse_npm_overrides(map, None, "", &mut overrides);
+    }
+
+    if let Some(map) = pkg.get("resolutions").and_then(|o| o.as_object()) {
+        for (key, value) in map {
+            if let (Some((name, selector, parent)), Some(target)) =
+                (parse_yarn_resolution_key(key), value.as_str())
+            {
+                overrides.push(Override {
+                    source: OverrideSource::Yarn,
+                    key: key.clone(),
+                    name,
+                    selector,
+                    parent,
+                    target: target.to_string(),
+                });
+            }
+        }
+    }
+
+    let mut pnpm_overrides: Vec<(String, String)> = pkg
+        .get("pnpm")
+        .and_then(|p| p.get("overrides"))
+        .and_then(|o| o.as_object())
+        .map(|map| {
+            map.iter()
+                .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(workspace) = fs::read_to_string(root.join("pnpm-workspace.yaml"))
+        .ok()
+        .and_then(|c| serde_yaml::from_str::<serde_yaml::Value>(&c).ok())
+    {
+        if let Some(map) = workspace.get("overrides").and_then(|o| o.as_mapping()) {
+            for (k, v) in map {
+                if let (Some(k), Some(v)) = (k.as_str(), v.as_str()) {
+                    pnpm_overrides.push((k.to_string(), v.to_string()));
+                }
+            }
+        }
+    }
+    for (key, target) in pnpm_overrides {
+        let mut chain: Vec<&str> = key.split('>').collect();
+        let last = chain.pop().unwrap_or(&key);
+        let (name, selector) = split_name_selector(last);
+        overrides.push(Override {
+            source: OverrideSource::Pnpm,
+            parent: chain.last().map(|p| split_name_selector(p).0),
+            key,
+            name,
+            selector,
+            target,
+        });
+    }
+
+    overrides
+}
+
+/// A dependency edge in the lockfile graph
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LockEdge {
+    /// Dependent package name (or `(root)`)
+    pub from: String,
+    pub name: String,
+    /// Declared range; pnpm only records resolved versions for transitive edges
+    pub range: Option<String>,
+}
+
+/// Packages and dependency edges recorded in a lockfile
+#[derive(Debug, Default)]
+pub struct LockGraph {
+    /// name -> locked versions
+    pub packages: BTreeMap<String, BTreeSet<String>>,
+    pub edges: BTreeSet<LockEdge>,
+}
+
+impl LockGraph {
+    fn add_package(&mut self, name: &str, version: &str) {
+        self.packages
+            .entry(name.to_string())
+            .or_default()
+            .insert(version.to_string());
+    }
+
+    fn add_edge(&mut self, from: &str, name: &str, range: Option<&str>) {
+        self.edges.insert(LockEdge {
+            from: from.to_string(),
+            name: name.to_string(),
+            range: range.map(|r| r.trim_start_matches("npm:").to_string()),
+        });
+    }
+
+    fn add_dependency_maps(&mut self, from: &str, entry: &serde_json::Value) {
+        for field in ["dependencies", "devDependencies", "optionalDependencies", "peerDependencies", "requires"] {
+            if let Some(deps) = entry.get(field).and_then(|d| d.as_object()) {
+                for (name, range) in deps {
+                    self.add_edge(from, name, range.as_str());
+                }
+            }
+        }
+    }
+
+    /// Build from package-lock.json (`packages` for v2/v3, nested `dependencies` for v1)
+    pub fn from_npm(content: &str) -> Result<Self> {
+        let lock: serde_json::Value = serde_json::from_str(content)?;
+        let mut graph = LockGraph::default();
+
+        if let Some(packages) = lock.get("packages").and_then(|p| p.as_object()) {
+            for (path, entry) in packages {
+                let from = match path.rfind("node_modules/") {
+                    Some(i) => path[i + "node_modules/".len()..].to_string(),
+                    None if path.is_empty() => ROOT.to_string(),
+                    // Workspace package entries
+                    None => entry
+                        .get("name")
+                        .and_then(|n| n.as_str())
+                        .unwrap_or(path)
+                        .to_string(),
+                };
+                if from != ROOT && path.contains("node_modules/") {
+                    if let Some(version) = entry.get("version").and_then(|v| v.as_str()) {
+                        graph.add_package(&from, version);
+                    }
+                }
+                graph.add_dependency_maps(&from, entry);
+            }
+        } else if let Some(deps) = lock.get("dependencies").and_then(|d| d.as_object()) {
+            fn walk(graph: &mut LockGraph, deps: &serde_json::Map<String, serde_json::Value>) {
+                for (name, entry) in deps {
+                    if let Some(version) = entry.get("version").and_then(|v| v.as_str()) {
+                        graph.add_package(name, version);
+                    }
+                    graph.add_dependency_maps(name, entry);
+                    if let Some(nested) = entry.get("dependencies").and_then(|d| d.as_object()) {
+                        walk(graph, nested);
+                    }
+                }
+            }
+            walk(&mut graph, deps);
+        }
+
+        Ok(graph)
+    }
+
+    /// Build from yarn.lock (classic and berry)
+    pub fn from_yarn(content: &str) -> Self {
+        let mut graph = LockGraph::default();
+        let mut current: Option<String> = None;
+        let mut in_deps = false;
+
+        for line in content.lines() {
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            let indent = line.len() - line.trim_start().len();
+            let trimmed = line.trim();
+
+            if indent == 0 {
+                in_deps = false;
+                current = trimmed
+                    .trim_end_matches(':')
+                    .split(',')
+                    .next()
+                    .map(|d| split_name_selector(d.trim().trim_matches('"')).0)
+                    .filter(|n| n != "__metadata");
+            } else if let Some(ref name) = current {
+                if indent == 2 {
+                    in_deps = matches!(
+                        trimmed,
+                        "dependencies:" | "optionalDependencies:" | "peerDependencies:"
+                    );
+                    if let Some(version) = trimmed.strip_prefix("version") {
+                        let version = version.trim_start_matches(':').trim().trim_matches('"');
+                        graph.add_package(name, version);
+                    }
+                } else if in_deps {
+                    let (dep, range) = match trimmed.split_once(": ") {
+                        Some(pair) => pair,
+                        None => trimmed.split_once(' ').unwrap_or((trimmed, "")),
+                    };
+                    graph.add_edge(name, dep.trim_matches('"'), Some(range.trim().trim_matches('"')));
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Build from pnpm-lock.yaml; importer specifiers are the only declared ranges
+    pub fn from_pnpm(content: &str) -> Result<Self> {
+        let lock: serde_yaml::Value = serde_yaml::from_str(content)?;
+        let mut graph = LockGraph::default();
+
+        let mut importers: Vec<&serde_yaml::Value> = Vec::new();
+        if let Some(map) = lock.get("importers").and_then(|i| i.as_mapping()) {
+            importers.extend(map.values());
+        } else {
+            importers.push(&lock);
+        }
+        for importer in importers {
+            for field in ["dependencies", "devDependencies", "optionalDependencies"] {
+                if let Some(deps) = importer.get(field).and_then(|d| d.as_mapping()) {
+                    for (name, spec) in deps {
+                        let Some(name) = name.as_str() else { continue };
+                        // v6+: { specifier, version }; v5: plain version + top-level `specifiers`
+                        let specifier = spec
+                            .get("specifier")
+                            .and_then(|s| s.as_str())
+                            .or_else(|| {
+                                importer
+                                    .get("specifiers")
+                                    .and_then(|s| s.get(name))
+                                    .and_then(|s| s.as_str())
+                            });
+                        graph.add_edge(ROOT, name, specifier);
+                    }
+                }
+            }
+        }
+
+        for section in ["packages", "snapshots"] {
+            if let Some(map) = lock.get(section).and_then(|p| p.as_mapping()) {
+                for (key, entry) in map {
+                    let Some((name, version)) = key.as_str().and_then(parse_pnpm_package_ref) else {
+                        continue;
+                    };
+                    graph.add_package(&name, &version);
+                    for field in ["dependencies", "optionalDependencies"] {
+                        if let Some(deps) = entry.get(field).and_then(|d| d.as_mapping()) {
+                            for dep in deps.keys().filter_map(|k| k.as_str()) {
+                                graph.add_edge(&name, dep, None);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+}
+
+/// Outcome of auditing one override
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverrideFinding {
+    /// No package in the graph matches the override
+    Unused,
+    /// The forced version falls outside these (dependent, declared range) pairs
+    Conflict { version: String, dependents: Vec<(String, String)> },
+    /// Every declared range already resolves inside the override
+    Redundant,
+    /// A pnpm `-` override whose package is still in the lockfile
+    NotRemoved,
+}
+
+/// Resolve `$name` references to the root package's own dependency spec
+fn resolve_target(target: &str, pkg: &serde_json::Value) -> String {
+    let Some(reference) = target.strip_prefix('$') else {
+        return target.to_string();
+    };
+    ["dependencies", "devDependencies", "optionalDependencies", "peerDependencies"]
+        .iter()
+        .find_map(|field| pkg.get(field).and_then(|d| d.get(reference)).and_then(|v| v.as_str()))
+        .unwrap_or(target)
+        .to_string()
+}
+
+/// Evaluate an override against the lockfile graph
+pub fn audit_override(ov: &Override, graph: &LockGraph, pkg: &serde_json::Value) -> Option<OverrideFinding> {
+    let in_scope = |edge: &&LockEdge| {
+        edge.name == ov.name && ov.parent.as_ref().map_or(true, |p| &edge.from == p)
+    };
+
+    let installed = graph.packages.get(&ov.name);
+    let scoped_present = ov.parent.is_none() || graph.edges.iter().any(|e| in_scope(&e));
+    let selector_matches = match (&ov.selector, installed) {
+        (Some(sel), Some(versions)) => versions
+            .iter()
+            .any(|v| npm_range::satisfies(v, sel) != Some(false)),
+        _ => true,
+    };
+    let present = installed.is_some() && scoped_present && selector_matches;
+
+    // `-` removes the dependency in pnpm, so it works exactly when the package is gone
+    let target = resolve_target(&ov.target, pkg);
+    if target == "-" {
+        return present.then_some(OverrideFinding::NotRemoved);
+    }
+
+    if !present {
+        return Some(OverrideFinding::Unused);
+    }
+    // Nothing to compare for specs that aren't ranges (git, file:, npm: aliases)
+    npm_range::parse_range(&target)?;
+
+    let declared: Vec<(String, String)> = graph
+        .edges
+        .iter()
+        .filter(|e| in_scope(e))
+        .filter_map(|e| e.range.as_ref().map(|r| (e.from.clone(), r.clone())))
+        .filter(|(_, r)| npm_range::parse_range(r).is_some())
+        // With a selector, only ranges that could have resolved into it are affected
+        .filter(|(_, r)| match &ov.selector {
+            Some(sel) => npm_range::range_within(r, sel) != Some(false)
+                || npm_range::range_within(sel, r) != Some(false),
+            None => true,
+        })
+        .collect();
+
+    let forced = semver::Version::parse(target.trim_start_matches(['=', 'v']))
+        .ok()
+        .map(|v| v.to_string());
+    if let Some(version) = forced {
+        let dependents: Vec<(String, String)> = declared
+            .iter()
+            .filter(|(_, r)| npm_range::satisfies(&version, r) == Some(false))
+            .cloned()
+            .collect();
+        if !dependents.is_empty() {
+            return Some(OverrideFinding::Conflict { version, dependents });
+        }
+    }
+
+    if !declared.is_empty()
+        && declared
+            .iter()
+            .all(|(_, r)| npm_range::range_within(r, &target) == Some(true))
+    {
+        return Some(OverrideFinding::Redundant);
+    }
+
+    None
+}
+
+/// Load the lockfile graph for whichever lockfile is present
+pub fn load_lock_graph(root: &Path) -> Result<Option<LockGraph>> {
+    if let Ok(content) = fs::read_to_string(root.join("package-lock.json")) {
+        return Ok(Some(LockGraph::from_npm(&content)?));
+    }
+    if let Ok(content) = fs::read_to_string(root.join("pnpm-lock.yaml")) {
+        return Ok(Some(LockGraph::from_pnpm(&content)?));
+    }
+    if let Ok(content) = fs::read_to_string(root.join("yarn.lock")) {
+        return Ok(Some(LockGraph::from_yarn(&content)));
+    }
+    Ok(None)
+}
+
+pub fn run_checks() -> Result<Vec<CheckResult>> {
+    let mut results = Vec::new();
+    let root = Path::new(".");
+
+    let pkg: serde_json::Value = match fs::read_to_string("package.json")
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+    {
+        Some(p) => p,
+        None => return Ok(results),
+    };
+
+    let overrides = collect_overrides(root, &pkg);
+    if overrides.is_empty() {
+        return Ok(results);
+    }
+
+    // A malformed lockfile is reported by the lockfile checks
+    let graph = match load_lock_graph(root) {
+        Ok(Some(g)) => g,
+        _ => return Ok(results),
+    };
+
+    let mut problems = 0;
+    for ov in &overrides {
+        match audit_override(ov, &graph, &pkg) {
+            Some(OverrideFinding::Unused) => {
+                problems += 1;
+                results.push(
+                    CheckResult::warning(
+                        "Unused override",
+                        "deps",
+                        &format!("{} no longer matches any package in the lockfile", ov.label()),
+                    )
+                    .with_fix(&format!("Remove {} from package.json", ov.label())),
+                );
+            }
+            Some(OverrideFinding::Conflict { version, dependents }) => {
+                problems += 1;
+                let mut list: Vec<String> = dependents
+                    .iter()
+                    .take(3)
+                    .map(|(from, range)| format!("{} wants {}", from, range))
+                    .collect();
+                if dependents.len() > 3 {
+                    list.push(format!("and {} more", dependents.len() - 3));
+                }
+                results.push(
+                    CheckResult::warning(
+                        "Override conflict",
+                        "deps",
+                        &format!(
+                            "{} forces {}@{} outside declared ranges: {}",
+                            ov.label(),
+                            ov.name,
+                            version,
+                            list.join(", ")
+                        ),
+                    )
+                    .with_fix("Check the override is still needed or upgrade the dependents"),
+                );
+            }
+            Some(OverrideFinding::Redundant) => {
+                problems += 1;
+                results.push(
+                    CheckResult::info(
+                        "Redundant override",
+                        "deps",
+                        &format!(
+                            "{} is redundant: every dependent already resolves {} within {}",
+                            ov.label(),
+                            ov.name,
+                            ov.target
+                        ),
+                    )
+                    .with_fix(&format!("Remove {} from package.json", ov.label())),
+                );
+            }
+            Some(OverrideFinding::NotRemoved) => {
+                problems += 1;
+                results.push(
+                    CheckResult::warning(
+                        "Override not applied",
+                        "deps",
+                        &format!(
+                            "{} removes {} but the lockfile still has it",
+                            ov.label(),
+                            ov.name
+                        ),
+                    )
+                    .with_fix("Run `pnpm install` to update the lockfile"),
+                );
+            }
+            None => {}
+        }
+    }
+
+    if problems == 0 {
+        results.push(CheckResult::pass("Overrides current", "deps"));
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_collect_overrides() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let pkg = json!({
+            "overrides": { "foo": "1.0.0", "bar": { ".": "2.0.0", "baz@^1": "1.2.0" } },
+            "resolutions": { "**/qux": "3.0.0", "a/@s/b": "1.0.0" },
+            "pnpm": { "overrides": { "x>y@^2": "2.1.0" } }
+        });
+        let overrides = collect_overrides(dir.path(), &pkg);
+        let summary: Vec<(&str, Option<&str>, Option<&str>)> = overrides
+            .iter()
+            .map(|o| (o.name.as_str(), o.parent.as_deref(), o.selector.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("bar", None, None),
+                ("baz", Some("bar"), Some("^1")),
+                ("foo", None, None),
+                ("qux", None, None),
+                ("@s/b", Some("a"), None),
+                ("y", Some("x"), Some("^2")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_audit_override() {
+        let lock = json!({
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "dependencies": { "a": "^1.0.0", "b": "^1.0.0" } },
+                "node_modules/a": { "version": "1.0.0", "dependencies": { "semver": "^7.5.4" } },
+                "node_modules/b": { "version": "1.0.0", "dependencies": { "semver": "^6.0.0" } },
+                "node_modules/semver": { "version": "7.5.4" }
+            }
+        });
+        let graph = LockGraph::from_npm(&lock.to_string()).unwrap();
+        let pkg = json!({});
+        let ov = |name: &str, target: &str, parent: Option<&str>| Override {
+            source: OverrideSource::Npm,
+            key: name.into(),
+            name: name.into(),
+            selector: None,
+            parent: parent.map(|p| p.into()),
+            target: target.into(),
+        };
+
+        assert_eq!(audit_override(&ov("gone", "1.0.0", None), &graph, &pkg), Some(OverrideFinding::Unused));
+        assert_eq!(
+            audit_override(&ov("semver", "7.5.4", None), &graph, &pkg),
+            Some(OverrideFinding::Conflict {
+                version: "7.5.4".into(),
+                dependents: vec![("b".into(), "^6.0.0".into())],
+            })
+        );
+        assert_eq!(
+            audit_override(&ov("semver", "^7.5.2", Some("a")), &graph, &pkg),
+            Some(OverrideFinding::Redundant)
+        );
+        assert_eq!(audit_override(&ov("semver", "7.6.0", Some("a")), &graph, &pkg), None);
+
+        // pnpm `-` removes the package: fine once it's gone, a problem while it's still there
+        assert_eq!(audit_override(&ov("gone", "-", None), &graph, &pkg), None);
+        assert_eq!(audit_override(&ov("semver", "-", Some("b")), &graph, &pkg), Some(OverrideFinding::NotRemoved));
+        assert_eq!(audit_override(&ov("semver", "-", Some("c")), &graph, &pkg), None);
+    }
+
+    #[test]
+    fn test_yarn_graph() {
+        let lock = "# yarn lockfile v1\n\n\
+                    a@^1.0.0:\n  version \"1.0.0\"\n  dependencies:\n    left-pad \"^1.1.0\"\n\n\
+                    \"left-pad@^1.1.0\", left-pad@^1.0.0:\n  version \"1.3.0\"\n";
+        let graph = LockGraph::from_yarn(lock);
+        assert!(graph.packages["left-pad"].contains("1.3.0"));
+        assert!(graph.edges.contains(&LockEdge {
+            from: "a".into(),
+            name: "left-pad".into(),
+            range: Some("^1.1.0".into()),
+        }));
+    }
+}
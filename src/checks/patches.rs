@@ -0,0 +1,629 @@
+//! Validation of dependency patches (patch-package, pnpm `patchedDependencies`,
+//! yarn `patch:` protocol): the target must be installed at the patched version and
+//! the patch must still match the installed files.
+
+use anyhow::Result;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::CheckResult;
+
+/// Directory patch-package writes patches to
+const PATCHES_DIR: &str = "patches";
+
+/// Where a patch was declared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchSource {
+    PatchPackage,
+    Pnpm,
+    Yarn,
+}
+
+impl std::fmt::Display for PatchSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchSource::PatchPackage => write!(f, "patch-package"),
+            PatchSource::Pnpm => write!(f, "pnpm patchedDependencies"),
+            PatchSource::Yarn => write!(f, "yarn patch:"),
+        }
+    }
+}
+
+/// A patch and the package version it was made for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchInfo {
+    pub source: PatchSource,
+    /// Path of the .patch file relative to the project root
+    pub patch_file: String,
+    /// Package name (`lodash`, `@scope/name`)
+    pub package: String,
+    /// Version the patch targets, if recorded
+    pub version: Option<String>,
+    /// Install location relative to the project root (`node_modules/a/node_modules/b`)
+    pub install_path: String,
+}
+
+/// Result of checking a patch against the installed files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchState {
+    /// Every hunk is already present in the installed files
+    Applied,
+    /// The patch isn't applied but would apply cleanly
+    Applicable,
+    /// Some hunks match neither the original nor the patched text
+    Conflicts(Vec<String>),
+}
+
+/// Parse a patch-package file name such as `lodash+4.17.21.patch`,
+/// `@scope+name+1.0.0+dev.patch` or `parent++child+2.0.0+001+fix.patch`
+pub fn parse_patch_package_filename(file_name: &str) -> Option<(String, String, String)> {
+    let stem = file_name.strip_suffix(".patch")?;
+    let segments: Vec<&str> = stem.split("++").collect();
+    let (last, parents) = segments.split_last()?;
+
+    let take_name = |parts: &mut std::slice::Iter<&str>| -> Option<String> {
+        let first = parts.next()?;
+        if first.starts_with('@') {
+            Some(format!("{}/{}", first, parts.next()?))
+        } else {
+            Some(first.to_string())
+        }
+    };
+
+    let mut install_path = String::new();
+    for parent in parents {
+        let parts: Vec<&str> = parent.split('+').collect();
+        let name = take_name(&mut parts.iter())?;
+        install_path.push_str(&format!("node_modules/{}/", name));
+    }
+
+    let parts: Vec<&str> = last.split('+').collect();
+    let mut iter = parts.iter();
+    let name = take_name(&mut iter)?;
+    let version = iter.next()?.to_string();
+    if !version.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    install_path.push_str(&format!("node_modules/{}", name));
+
+    Some((name, version, install_path))
+}
+
+/// Parse a yarn `patch:` descriptor like
+/// `patch:lodash@npm%3A4.17.21#~/.yarn/patches/lodash-npm-4.17.21-abc.patch::version=4.17.21`
+pub fn parse_yarn_patch_spec(spec: &str) -> Option<(String, Option<String>, String)> {
+    let rest = spec.strip_prefix("patch:")?;
+    let (descriptor, path) = rest.split_once('#')?;
+    let descriptor = descriptor.replace("%3A", ":").replace("%40", "@");
+    let path = path.split("::").next().unwrap_or(path);
+    let path = path
+        .trim_start_matches("~/")
+        .trim_start_matches("./")
+        .to_string();
+
+    let at = if let Some(unscoped) = descriptor.strip_prefix('@') {
+        unscoped.find('@').map(|i| i + 1)
+    } else {
+        descriptor.find('@')
+    };
+
+    let (name, range) = match at {
+        Some(i) => (descriptor[..i].to_string(), &descriptor[i + 1..]),
+        None => (descriptor.clone(), ""),
+    };
+    let range = range.trim_start_matches("npm:");
+    let version = semver::Version::parse(range).ok().map(|v| v.to_string());
+
+    Some((name, version, path))
+}
+
+/// Split a `name@version` key from pnpm `patchedDependencies`
+fn split_pnpm_patch_key(key: &str) -> (String, Option<String>) {
+    let at = if let Some(unscoped) = key.strip_prefix('@') {
+        unscoped.find('@').map(|i| i + 1)
+    } else {
+        key.find('@')
+    };
+    match at {
+        Some(i) => (key[..i].to_string(), Some(key[i + 1..].to_string())),
+        None => (key.to_string(), None),
+    }
+}
+
+/// Find all patches declared in the project rooted at `root`
+pub fn discover_patches(root: &Path, pkg: &serde_json::Value) -> Vec<PatchInfo> {
+    let mut patches = Vec::new();
+
+    // pnpm: { "pnpm": { "patchedDependencies": { "name@1.0.0": "patches/name@1.0.0.patch" } } }
+    let mut pnpm_files = HashSet::new();
+    if let Some(patched) = pkg
+        .get("pnpm")
+        .and_then(|p| p.get("patchedDependencies"))
+        .and_then(|p| p.as_object())
+    {
+        for (key, file) in patched {
+            if let Some(file) = file.as_str() {
+                let (name, version) = split_pnpm_patch_key(key);
+                pnpm_files.insert(file.trim_start_matches("./").to_string());
+                patches.push(PatchInfo {
+                    source: PatchSource::Pnpm,
+                    patch_file: file.trim_start_matches("./").to_string(),
+                    install_path: format!("node_modules/{}", name),
+                    package: name,
+                    version,
+                });
+            }
+        }
+    }
+
+    // yarn: patch: descriptors in dependencies or resolutions
+    let mut seen_yarn = HashSet::new();
+    for field in ["dependencies", "devDependencies", "optionalDependencies", "resolutions"] {
+        if let Some(deps) = pkg.get(field).and_then(|d| d.as_object()) {
+            for spec in deps.values().filter_map(|v| v.as_str()) {
+                if let Some((name, version, file)) = parse_yarn_patch_spec(spec) {
+                    if seen_yarn.insert(file.clone()) {
+                        patches.push(PatchInfo {
+                            source: PatchSource::Yarn,
+                            patch_file: file,
+                            install_path: format!("node_modules/{}", name),
+                            package: name,
+                            version,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // patch-package: patches/*.patch not already claimed by pnpm
+    if let Ok(entries) = fs::read_dir(root.join(PATCHES_DIR)) {
+        let mut files: Vec<String> = entries
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|n| n.ends_with(".patch"))
+            .collect();
+        files.sort();
+
+        for file_name in files {
+            let rel = format!("{}/{}", PATCHES_DIR, file_name);
+            if pnpm_files.contains(&rel) {
+                continue;
+            }
+            if let Some((name, version, install_path)) = parse_patch_package_filename(&file_name) {
+                patches.push(PatchInfo {
+                    source: PatchSource::PatchPackage,
+                    patch_file: rel,
+                    package: name,
+                    version: Some(version),
+                    install_path,
+                });
+            }
+        }
+    }
+
+    patches
+}
+
+/// One file section of a unified diff
+#[derive(Debug, Default)]
+struct FilePatch {
+    old_path: Option<String>,
+    new_path: Option<String>,
+    hunks: Vec<Vec<(char, String)>>,
+}
+
+/// Strip the `a/` / `b/` prefix; `/dev/null` means the file doesn't exist on that side
+fn diff_path(raw: &str) -> Option<String> {
+    let raw = raw.split('\t').next().unwrap_or(raw).trim();
+    if raw == "/dev/null" {
+        return None;
+    }
+    Some(
+        raw.strip_prefix("a/")
+            .or_else(|| raw.strip_prefix("b/"))
+            .unwrap_or(raw)
+            .to_string(),
+    )
+}
+
+/// Old and new line counts of a `@@ -a,b +c,d @@` hunk header (an omitted count is 1)
+fn hunk_counts(header: &str) -> Option<(usize, usize)> {
+    let mut ranges = header.strip_prefix("@@ ")?.split_whitespace();
+    let count = |range: &str| match range.split_once(',') {
+        Some((_, n)) => n.parse().ok(),
+        None => Some(1),
+    };
+    let old = count(ranges.next()?.strip_prefix('-')?)?;
+    let new = count(ranges.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+fn parse_unified_diff(content: &str) -> Vec<FilePatch> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    // Old and new lines the current hunk still has to consume; until they are used up
+    // `--- ` and `+++ ` are removed/added lines, not file headers
+    let mut remaining = (0usize, 0usize);
+
+    for line in content.lines() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        if remaining != (0, 0) {
+            if let Some(hunk) = files.last_mut().and_then(|f| f.hunks.last_mut()) {
+                let (old, new) = &mut remaining;
+                // An empty context line may lose its leading space
+                let kind = line.chars().next().unwrap_or(' ');
+                let consumed = match kind {
+                    ' ' => {
+                        *old = old.saturating_sub(1);
+                        *new = new.saturating_sub(1);
+                        true
+                    }
+                    '-' if *old > 0 => {
+                        *old -= 1;
+                        true
+                    }
+                    '+' if *new > 0 => {
+                        *new -= 1;
+                        true
+                    }
+                    // "\ No newline at end of file"
+                    '\\' => continue,
+                    _ => false,
+                };
+                if consumed {
+                    hunk.push((kind, line.get(1..).unwrap_or("").to_string()));
+                    continue;
+                }
+                // The counts were off; read the line as if the hunk had ended
+                remaining = (0, 0);
+            }
+        }
+
+        if let Some(path) = line.strip_prefix("--- ") {
+            files.push(FilePatch {
+                old_path: diff_path(path),
+                ..Default::default()
+            });
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            if let Some(file) = files.last_mut() {
+                file.new_path = diff_path(path);
+            }
+        } else if line.starts_with("@@") {
+            if let Some(file) = files.last_mut() {
+                file.hunks.push(Vec::new());
+                remaining = hunk_counts(line).unwrap_or((0, 0));
+            }
+        } else if let Some(hunk) = files.last_mut().and_then(|f| f.hunks.last_mut()) {
+            // Hunks without usable counts
+            match line.chars().next() {
+                Some(c @ (' ' | '-' | '+')) => hunk.push((c, line[1..].to_string())),
+                None => hunk.push((' ', String::new())),
+                _ => {}
+            }
+        }
+    }
+
+    files
+}
+
+/// Whether `needle` appears as a contiguous run of lines in `haystack`
+fn contains_lines(haystack: &[&str], needle: &[&str]) -> bool {
+    needle.is_empty() || haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// Check a unified diff against installed files
+///
+/// Paths starting with `node_modules/` (patch-package) are resolved from `root`,
+/// others (pnpm, yarn) from `package_dir`.
+pub fn check_patch_state(patch: &str, root: &Path, package_dir: &Path) -> PatchState {
+    let resolve = |p: &str| -> PathBuf {
+        if p.starts_with("node_modules/") {
+            root.join(p)
+        } else {
+            package_dir.join(p)
+        }
+    };
+
+    let mut all_applied = true;
+    let mut all_applicable = true;
+    let mut conflicts = Vec::new();
+
+    for file in parse_unified_diff(patch) {
+        let (applied, applicable, label) = match (&file.old_path, &file.new_path) {
+            // New file
+            (None, Some(new)) => {
+                let exists = resolve(new).exists();
+                (exists, !exists, new.clone())
+            }
+            // Deleted file
+            (Some(old), None) => {
+                let exists = resolve(old).exists();
+                (!exists, exists, old.clone())
+            }
+            (Some(old), Some(new)) => {
+                let content = fs::read_to_string(resolve(new))
+                    .or_else(|_| fs::read_to_string(resolve(old)))
+                    .unwrap_or_default();
+                let lines: Vec<&str> = content.lines().map(|l| l.trim_end_matches('\r')).collect();
+
+                let mut applied = true;
+                let mut applicable = true;
+                for hunk in &file.hunks {
+                    let before: Vec<&str> = hunk
+                        .iter()
+                        .filter(|(c, _)| *c != '+')
+                        .map(|(_, l)| l.as_str())
+                        .collect();
+                    let after: Vec<&str> = hunk
+                        .iter()
+                        .filter(|(c, _)| *c != '-')
+                        .map(|(_, l)| l.as_str())
+                        .collect();
+                    applied &= contains_lines(&lines, &after);
+                    applicable &= contains_lines(&lines, &before);
+                }
+                (applied, applicable, new.clone())
+            }
+            (None, None) => continue,
+        };
+
+        all_applied &= applied;
+        all_applicable &= applicable;
+        if !applied && !applicable {
+            conflicts.push(label);
+        }
+    }
+
+    if all_applied {
+        PatchState::Applied
+    } else if all_applicable {
+        PatchState::Applicable
+    } else {
+        PatchState::Conflicts(conflicts)
+    }
+}
+
+/// Read the version of the package installed at `dir`
+fn installed_version(dir: &Path) -> Option<String> {
+    let content = fs::read_to_string(dir.join("package.json")).ok()?;
+    let pkg: serde_json::Value = serde_json::from_str(&content).ok()?;
+    pkg.get("version").and_then(|v| v.as_str()).map(|s| s.to_string())
+}
+
+pub fn run_checks() -> Result<Vec<CheckResult>> {
+    let mut results = Vec::new();
+    let root = Path::new(".");
+
+    let pkg: serde_json::Value = match fs::read_to_string("package.json")
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+    {
+        Some(p) => p,
+        None => return Ok(results),
+    };
+
+    let patches = discover_patches(root, &pkg);
+    if patches.is_empty() {
+        return Ok(results);
+    }
+
+    // Installed files can only be checked with a node_modules layout (not Yarn PnP)
+    let can_check_installed = root.join("node_modules").exists();
+    let mut problems = 0;
+
+    for patch in &patches {
+        let patch_content = match fs::read_to_string(root.join(&patch.patch_file)) {
+            Ok(c) => c,
+            Err(_) => {
+                problems += 1;
+                results.push(
+                    CheckResult::warning(
+                        "Orphaned patch",
+                        "deps",
+                        &format!(
+                            "{} entry for {} points to missing file {}",
+                            patch.source, patch.package, patch.patch_file
+                        ),
+                    )
+                    .with_fix("Remove the entry or restore the patch file"),
+                );
+                continue;
+            }
+        };
+
+        if !can_check_installed {
+            continue;
+        }
+
+        let package_dir = root.join(&patch.install_path);
+        let installed = match installed_version(&package_dir) {
+            Some(v) => v,
+            None => {
+                problems += 1;
+                results.push(
+                    CheckResult::warning(
+                        "Orphaned patch",
+                        "deps",
+                        &format!(
+                            "{} patches {} which is not installed",
+                            patch.patch_file, patch.package
+                        ),
+                    )
+                    .with_fix(&format!(
+                        "Delete {} if {} is no longer a dependency",
+                        patch.patch_file, patch.package
+                    )),
+                );
+                continue;
+            }
+        };
+
+        if let Some(ref expected) = patch.version {
+            if &installed != expected {
+                problems += 1;
+                results.push(
+                    CheckResult::warning(
+                        "Patch version match",
+                        "deps",
+                        &format!(
+                            "{} was made for {}@{} but {} is installed",
+                            patch.patch_file, patch.package, expected, installed
+                        ),
+                    )
+                    .with_fix(&format!(
+                        "Re-create the patch against {}@{} or pin {} to {}",
+                        patch.package, installed, patch.package, expected
+                    )),
+                );
+            }
+        }
+
+        match check_patch_state(&patch_content, root, &package_dir) {
+            PatchState::Applied => {}
+            PatchState::Applicable => {
+                problems += 1;
+                let fix = match patch.source {
+                    PatchSource::PatchPackage => {
+                        "Run `npx patch-package` (add it to the postinstall script)"
+                    }
+                    PatchSource::Pnpm => "Run `pnpm install` to apply patchedDependencies",
+                    PatchSource::Yarn => "Run `yarn install` to apply the patch",
+                };
+                results.push(
+                    CheckResult::warning(
+                        "Patch applies",
+                        "deps",
+                        &format!(
+                            "{} is not applied to {}@{}",
+                            patch.patch_file, patch.package, installed
+                        ),
+                    )
+                    .with_fix(fix),
+                );
+            }
+            PatchState::Conflicts(files) => {
+                problems += 1;
+                results.push(
+                    CheckResult::error(
+                        "Patch applies",
+                        "deps",
+                        &format!(
+                            "{} no longer applies cleanly to {}@{} ({})",
+                            patch.patch_file,
+                            patch.package,
+                            installed,
+                            files.join(", ")
+                        ),
+                    )
+                    .with_fix(&format!(
+                        "Re-create the patch for {}@{}",
+                        patch.package, installed
+                    )),
+                );
+            }
+        }
+    }
+
+    if problems == 0 {
+        results.push(CheckResult::pass("Patches valid", "deps"));
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_patch_package_filename() {
+        assert_eq!(
+            parse_patch_package_filename("lodash+4.17.21.patch"),
+            Some(("lodash".into(), "4.17.21".into(), "node_modules/lodash".into()))
+        );
+        assert_eq!(
+            parse_patch_package_filename("@babel+core+7.23.0+dev.patch"),
+            Some(("@babel/core".into(), "7.23.0".into(), "node_modules/@babel/core".into()))
+        );
+        assert_eq!(
+            parse_patch_package_filename("a++@s+b+2.0.0+001+fix-typo.patch"),
+            Some(("@s/b".into(), "2.0.0".into(), "node_modules/a/node_modules/@s/b".into()))
+        );
+        assert_eq!(parse_patch_package_filename("README.md"), None);
+    }
+
+    #[test]
+    fn test_parse_yarn_patch_spec() {
+        assert_eq!(
+            parse_yarn_patch_spec(
+                "patch:lodash@npm%3A4.17.21#~/.yarn/patches/lodash-npm-4.17.21-abc.patch::version=4.17.21"
+            ),
+            Some((
+                "lodash".into(),
+                Some("4.17.21".into()),
+                ".yarn/patches/lodash-npm-4.17.21-abc.patch".into()
+            ))
+        );
+        assert_eq!(
+            parse_yarn_patch_spec("patch:@s/b@^1.0.0#./patches/b.patch").map(|p| p.1),
+            Some(None)
+        );
+        assert_eq!(parse_yarn_patch_spec("^1.0.0"), None);
+    }
+
+    #[test]
+    fn test_check_patch_state() {
+        let dir = TempDir::new().unwrap();
+        let pkg_dir = dir.path().join("node_modules/lib");
+        fs::create_dir_all(&pkg_dir).unwrap();
+
+        let patch = "diff --git a/node_modules/lib/index.js b/node_modules/lib/index.js\n\
+                     --- a/node_modules/lib/index.js\n\
+                     +++ b/node_modules/lib/index.js\n\
+                     @@ -1,3 +1,3 @@\n \
+                     function f() {\n\
+                     -  return 1;\n\
+                     +  return 2;\n \
+                     }\n";
+
+        fs::write(pkg_dir.join("index.js"), "function f() {\n  return 1;\n}\n").unwrap();
+        assert_eq!(check_patch_state(patch, dir.path(), &pkg_dir), PatchState::Applicable);
+
+        fs::write(pkg_dir.join("index.js"), "function f() {\n  return 2;\n}\n").unwrap();
+        assert_eq!(check_patch_state(patch, dir.path(), &pkg_dir), PatchState::Applied);
+
+        fs::write(pkg_dir.join("index.js"), "function f() {\n  return 3;\n}\n").unwrap();
+        assert_eq!(
+            check_patch_state(patch, dir.path(), &pkg_dir),
+            PatchState::Conflicts(vec!["node_modules/lib/index.js".into()])
+        );
+    }
+
+    #[test]
+    fn test_check_patch_state_header_like_lines() {
+        let dir = TempDir::new().unwrap();
+        let pkg_dir = dir.path().join("node_modules/lib");
+        fs::create_dir_all(&pkg_dir).unwrap();
+
+        // Removing `-- old` and adding `++ new` gives lines that look like file headers
+        let patch = "--- a/node_modules/lib/schema.sql\n\
+                     +++ b/node_modules/lib/schema.sql\n\
+                     @@ -1,3 +1,3 @@\n \
+                     CREATE TABLE t (id int);\n\
+                     --- old\n\
+                     +++ new\n \
+                     SELECT 1;\n";
+        let files = parse_unified_diff(patch);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].hunks[0].len(), 4);
+
+        fs::write(pkg_dir.join("schema.sql"), "CREATE TABLE t (id int);\n-- old\nSELECT 1;\n").unwrap();
+        assert_eq!(check_patch_state(patch, dir.path(), &pkg_dir), PatchState::Applicable);
+
+        fs::write(pkg_dir.join("schema.sql"), "CREATE TABLE t (id int);\n++ new\nSELECT 1;\n").unwrap();
+        assert_eq!(check_patch_state(patch, dir.path(), &pkg_dir), PatchState::Applied);
+    }
+}
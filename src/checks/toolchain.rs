@@ -541,7 +541,7 @@ fn check_semver_constraint(constraint: &str, version: &semver::Version) -> Optio
             .map(|c| check_semver_constraint(c.trim(), version))
             .collect();
 
-        if results.iter().any(|r| *r == Some(true)) {
+        if results.contains(&Some(true)) {
             return Some(true);
         }
         if results.iter().all(|r| r.is_none()) {
@@ -0,0 +1,381 @@
+//! Unused dependency detection
+//!
+//! The inverse of phantom detection: packages declared in `dependencies` or
+//! `devDependencies` that nothing uses. A package counts as used when source files import
+//! or require it (see [`super::imports`]), when package.json scripts or config files
+//! (eslint, babel, jest, vite, husky hooks, ...) name it or one of its binaries, when
+//! another dependency declares it as a required peer, or, for `typescript`, when there is
+//! a tsconfig. Packages used implicitly in other ways can be listed in
+//! `checks.unused_ignore`.
+
+use anyhow::Result;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::Path;
+
+use super::imports::{find_imports, package_name};
+use super::workspace::segment_matches;
+use super::CheckResult;
+use crate::config::{ImportsConfig, ZenvoConfig};
+
+/// Packages never reported, whatever the config says
+pub const DEFAULT_IGNORE: &[&str] = &["@types/*"];
+
+/// Dependency sections checked for unused packages
+const SECTIONS: &[&str] = &["dependencies", "devDependencies"];
+
+/// Prefixes tools let you leave out when naming a plugin or preset
+const SHORTHAND_PREFIXES: &[&str] = &[
+    "eslint-plugin-",
+    "eslint-config-",
+    "babel-plugin-",
+    "babel-preset-",
+    "prettier-plugin-",
+    "stylelint-config-",
+    "stylelint-plugin-",
+];
+
+/// package.json fields that say nothing about which packages are used
+const INERT_FIELDS: &[&str] = &[
+    "name",
+    "version",
+    "description",
+    "keywords",
+    "author",
+    "contributors",
+    "license",
+    "repository",
+    "homepage",
+    "bugs",
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// Directories whose files count as config
+const CONFIG_DIRS: &[&str] = &[".husky", ".storybook"];
+
+/// A declared package nothing uses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnusedDependency {
+    pub name: String,
+    /// `dependencies` or `devDependencies`
+    pub section: &'static str,
+}
+
+/// Whether a root-level file is tool configuration (`.eslintrc.json`, `jest.config.js`,
+/// `tsconfig.json`, ...)
+fn is_config_file(name: &str) -> bool {
+    (name.starts_with('.') && (name.contains("rc") || name.contains("config")))
+        || name.contains(".config.")
+        || name.contains("config.json")
+}
+
+/// Package-name-like words in `text`, including the packages of import-like paths
+fn collect_words(text: &str, words: &mut HashSet<String>) {
+    let is_word_char = |c: char| c.is_ascii_alphanumeric() || "@/._-".contains(c);
+    for word in text.split(|c: char| !is_word_char(c)) {
+        let word = word.trim_matches(|c| c == '.' || c == '/');
+        if word.is_empty() {
+            continue;
+        }
+        words.insert(package_name(word));
+        words.insert(word.to_string());
+    }
+}
+
+/// Names `package` can be referred to by in config files
+fn shorthands(package: &str) -> Vec<String> {
+    let mut names = vec![package.to_string()];
+    match package.split_once('/') {
+        Some((scope, name)) if scope.starts_with('@') => {
+            // `@scope/eslint-plugin` is `@scope`, `@scope/eslint-plugin-x` is `@scope/x`
+            if name == "eslint-plugin" || name == "eslint-config" {
+                names.push(scope.to_string());
+            }
+            for prefix in SHORTHAND_PREFIXES
+                .iter()
+                .copied()
+                .chain(["preset-", "plugin-"])
+            {
+                if let Some(rest) = name.strip_prefix(prefix) {
+                    names.push(format!("{}/{}", scope, rest));
+                }
+            }
+        }
+        _ => {
+            for prefix in SHORTHAND_PREFIXES {
+                if let Some(rest) = package.strip_prefix(prefix) {
+                    names.push(rest.to_string());
+                }
+            }
+        }
+    }
+    names
+}
+
+/// The installed manifest of `package`
+fn installed_manifest(root: &Path, package: &str) -> Option<serde_json::Value> {
+    let path = root.join("node_modules").join(package).join("package.json");
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+/// Binaries `package` installs into node_modules/.bin
+fn bin_names(package: &str, manifest: &serde_json::Value) -> Vec<String> {
+    match manifest.get("bin") {
+        Some(serde_json::Value::String(_)) => {
+            vec![package.rsplit('/').next().unwrap_or(package).to_string()]
+        }
+        Some(serde_json::Value::Object(bins)) => bins.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Required (non-optional) peer dependencies of an installed package
+fn required_peers(manifest: &serde_json::Value) -> Vec<String> {
+    let optional = |peer: &str| {
+        manifest
+            .pointer(&format!(
+                "/peerDependenciesMeta/{}/optional",
+                peer.replace('/', "~1")
+            ))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    };
+    manifest
+        .get("peerDependencies")
+        .and_then(|p| p.as_object())
+        .map(|peers| {
+            peers
+                .keys()
+                .filter(|peer| !optional(peer))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Words from package.json scripts and settings and from the config files under `root`
+fn referenced_words(root: &Path, pkg: &serde_json::Value) -> HashSet<String> {
+    let mut words = HashSet::new();
+    if let Some(fields) = pkg.as_object() {
+        for (key, value) in fields {
+            if !INERT_FIELDS.contains(&key.as_str()) {
+                collect_words(&value.to_string(), &mut words);
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(root).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        if path.is_file() && is_config_file(&name) {
+            files.push(path);
+        } else if path.is_dir() && CONFIG_DIRS.contains(&name.as_str()) {
+            for nested in fs::read_dir(&path).into_iter().flatten().flatten() {
+                if nested.path().is_file() {
+                    files.push(nested.path());
+                }
+            }
+        }
+    }
+    for file in files {
+        if let Ok(content) = fs::read_to_string(&file) {
+            collect_words(&content, &mut words);
+        }
+    }
+    words
+}
+
+/// Declared packages under `root` that nothing uses, leaving out those matching
+/// `DEFAULT_IGNORE` or `ignore`
+///
+/// Returns `None` without a readable package.json.
+pub fn find_unused_dependencies(
+    root: &Path,
+    imports: &ImportsConfig,
+    ignore: &[String],
+) -> Result<Option<Vec<UnusedDependency>>> {
+    let Some(pkg) = fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+    else {
+        return Ok(None);
+    };
+
+    let mut declared: Vec<(String, &'static str)> = Vec::new();
+    for &section in SECTIONS {
+        if let Some(deps) = pkg.get(section).and_then(|d| d.as_object()) {
+            declared.extend(deps.keys().map(|name| (name.clone(), section)));
+        }
+    }
+
+    let mut used: BTreeSet<String> = find_imports(root, imports)?.into_keys().collect();
+    let words = referenced_words(root, &pkg);
+    for (name, _) in &declared {
+        let Some(manifest) = installed_manifest(root, name) else {
+            continue;
+        };
+        if bin_names(name, &manifest)
+            .iter()
+            .any(|bin| words.contains(bin))
+        {
+            used.insert(name.clone());
+        }
+        used.extend(required_peers(&manifest));
+    }
+    if root.join("tsconfig.json").is_file() {
+        used.insert("typescript".to_string());
+    }
+
+    let ignored = |name: &str| {
+        DEFAULT_IGNORE
+            .iter()
+            .copied()
+            .chain(ignore.iter().map(String::as_str))
+            .any(|pattern| segment_matches(pattern, name))
+    };
+
+    Ok(Some(
+        declared
+            .into_iter()
+            .filter(|(name, _)| {
+                !used.contains(name)
+                    && !shorthands(name).iter().any(|n| words.contains(n))
+                    && !ignored(name)
+            })
+            .map(|(name, section)| UnusedDependency { name, section })
+            .collect(),
+    ))
+}
+
+pub fn run_checks(config: &Option<ZenvoConfig>) -> Result<Vec<CheckResult>> {
+    let (imports, ignore) = match config {
+        Some(c) => (c.checks.imports.clone(), c.checks.unused_ignore.as_slice()),
+        None => (ImportsConfig::default(), [].as_slice()),
+    };
+    let Some(unused) = find_unused_dependencies(Path::new("."), &imports, ignore)? else {
+        return Ok(Vec::new());
+    };
+
+    if unused.is_empty() {
+        return Ok(vec![CheckResult::pass("No unused dependencies", "deps")]);
+    }
+
+    let names: Vec<&str> = unused.iter().take(5).map(|u| u.name.as_str()).collect();
+    let message = if unused.len() > 5 {
+        format!(
+            "Found {} unused dependencies: {}, and {} more",
+            unused.len(),
+            names.join(", "),
+            unused.len() - 5
+        )
+    } else {
+        format!("Found unused dependencies: {}", names.join(", "))
+    };
+    Ok(vec![CheckResult::warning(
+        "Unused dependencies",
+        "deps",
+        &message,
+    )
+    .with_fix(
+        "Remove them from package.json, or list packages used implicitly in checks.unused_ignore",
+    )])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn install(root: &Path, package: &str, manifest: serde_json::Value) {
+        let dir = root.join("node_modules").join(package);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("package.json"), manifest.to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_find_unused_dependencies() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("package.json"),
+            serde_json::json!({
+                "scripts": { "test": "vitest run", "lint": "eslint ." },
+                "dependencies": {
+                    "react": "^18.2.0",
+                    "react-dom": "^18.2.0",
+                    "lodash": "^4.17.21",
+                    "next": "^14.0.0"
+                },
+                "devDependencies": {
+                    "vitest": "^1.6.0",
+                    "eslint": "^8.57.0",
+                    "eslint-plugin-react": "^7.33.0",
+                    "@typescript-eslint/eslint-plugin": "^7.0.0",
+                    "typescript": "^5.4.0",
+                    "@types/node": "^20.0.0",
+                    "husky": "^9.0.0",
+                    "left-pad": "^1.3.0"
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/app.tsx"), "import React from 'react';\n").unwrap();
+        fs::write(
+            root.join(".eslintrc.json"),
+            r#"{ "extends": ["plugin:react/recommended"], "plugins": ["@typescript-eslint"] }"#,
+        )
+        .unwrap();
+        fs::write(root.join("tsconfig.json"), "{}").unwrap();
+        install(
+            root,
+            "vitest",
+            serde_json::json!({ "bin": { "vitest": "vitest.mjs" } }),
+        );
+        install(
+            root,
+            "next",
+            serde_json::json!({
+                "bin": { "next": "dist/bin/next" },
+                "peerDependencies": { "react": "^18", "react-dom": "^18", "sass": "^1" },
+                "peerDependenciesMeta": { "sass": { "optional": true } }
+            }),
+        );
+
+        let unused = find_unused_dependencies(root, &ImportsConfig::default(), &["left-*".to_string()])
+            .unwrap()
+            .unwrap();
+        let names: Vec<&str> = unused.iter().map(|u| u.name.as_str()).collect();
+        // next is only a peer provider here; nothing runs or imports it
+        assert_eq!(names, vec!["lodash", "next", "husky"]);
+        assert_eq!(unused[2].section, "devDependencies");
+    }
+
+    #[test]
+    fn test_imported_workspace_package_is_used() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("package.json"), r#"{ "workspaces": ["packages/*"] }"#).unwrap();
+        fs::create_dir_all(root.join("packages/ui")).unwrap();
+        fs::write(root.join("packages/ui/package.json"), r#"{ "name": "@acme/ui" }"#).unwrap();
+        let web = root.join("packages/web");
+        fs::create_dir_all(web.join("src")).unwrap();
+        fs::write(
+            web.join("package.json"),
+            r#"{ "name": "web", "dependencies": { "@acme/ui": "workspace:*", "@acme/old": "workspace:*" } }"#,
+        )
+        .unwrap();
+        fs::write(web.join("src/index.ts"), "import { Button } from '@acme/ui';\n").unwrap();
+
+        let unused = find_unused_dependencies(&web, &ImportsConfig::default(), &[])
+            .unwrap()
+            .unwrap();
+        let names: Vec<&str> = unused.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["@acme/old"]);
+    }
+}
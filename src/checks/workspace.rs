@@ -0,0 +1,362 @@
+//! Workspace package discovery for npm/yarn/pnpm workspaces, Lerna, Nx and Turborepo
+//!
+//! Turborepo has no package list of its own; its package graph is the package
+//! manager's workspaces plus the dependencies between them, which is what this builds.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use super::{CheckCategory, CheckResult, WorkspaceInfo};
+use crate::config::ZenvoConfig;
+
+/// Directories never searched for workspace members
+const SKIP_DIRS: &[&str] = &["node_modules", "dist", "build", "coverage", "target"];
+
+/// Lerna's default when lerna.json has no `packages`
+const LERNA_DEFAULT_PACKAGES: &str = "packages/*";
+
+/// A package that belongs to the workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorkspacePackage {
+    pub name: String,
+    /// Directory relative to the workspace root, with `/` separators
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Other workspace packages this one depends on
+    pub dependencies: Vec<String>,
+}
+
+/// Check results for one workspace package
+#[derive(Debug, Clone)]
+pub struct PackageReport {
+    pub package: WorkspacePackage,
+    pub results: Vec<CheckResult>,
+}
+
+fn should_skip(name: &str) -> bool {
+    name.starts_with('.') || SKIP_DIRS.contains(&name)
+}
+
+/// Match one path segment against a glob segment (`*` and `?`)
+pub(crate) fn segment_matches(pattern: &str, name: &str) -> bool {
+    fn matches(p: &[char], n: &[char]) -> bool {
+        match (p.first(), n.first()) {
+            (None, None) => true,
+            (Some('*'), _) => matches(&p[1..], n) || (!n.is_empty() && matches(p, &n[1..])),
+            (Some('?'), Some(_)) => matches(&p[1..], &n[1..]),
+            (Some(a), Some(b)) if a == b => matches(&p[1..], &n[1..]),
+            _ => false,
+        }
+    }
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    matches(&p, &n)
+}
+
+fn subdirectories(dir: &Path) -> Vec<String> {
+    let mut dirs: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|n| !should_skip(n))
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+fn expand_segments(root: &Path, rel: &str, segments: &[&str], out: &mut BTreeSet<String>) {
+    let dir = if rel.is_empty() { root.to_path_buf() } else { root.join(rel) };
+    let join = |name: &str| {
+        if rel.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", rel, name)
+        }
+    };
+
+    match segments.split_first() {
+        None => {
+            if dir.join("package.json").is_file() {
+                out.insert(rel.to_string());
+            }
+        }
+        Some((&"**", rest)) => {
+            expand_segments(root, rel, rest, out);
+            for sub in subdirectories(&dir) {
+                expand_segments(root, &join(&sub), segments, out);
+            }
+        }
+        Some((segment, rest)) => {
+            if segment.contains(['*', '?']) {
+                for sub in subdirectories(&dir) {
+                    if segment_matches(segment, &sub) {
+                        expand_segments(root, &join(&sub), rest, out);
+                    }
+                }
+            } else if dir.join(segment).is_dir() {
+                expand_segments(root, &join(segment), rest, out);
+            }
+        }
+    }
+}
+
+/// Expand workspace globs into package directories (those containing a package.json)
+///
+/// Patterns prefixed with `!` (pnpm) exclude matches.
+pub fn expand_workspace_globs(root: &Path, patterns: &[String]) -> BTreeSet<String> {
+    let mut included = BTreeSet::new();
+    let mut excluded = BTreeSet::new();
+
+    for pattern in patterns {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(p) => (true, p),
+            None => (false, pattern.as_str()),
+        };
+        let pattern = pattern
+            .trim_start_matches("./")
+            .trim_end_matches("/package.json")
+            .trim_end_matches('/');
+        let segments: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty() && *s != ".").collect();
+
+        let target = if negated { &mut excluded } else { &mut included };
+        expand_segments(root, "", &segments, target);
+    }
+
+    included.retain(|p| !p.is_empty() && !excluded.contains(p));
+    included
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+}
+
+/// Find Nx `project.json` files and return (directory, project.json)
+fn find_nx_projects(root: &Path) -> Vec<(String, serde_json::Value)> {
+    let mut projects = Vec::new();
+    for entry in walkdir::WalkDir::new(root)
+        .max_depth(6)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !should_skip(&e.file_name().to_string_lossy()))
+        .flatten()
+    {
+        if entry.file_name() != "project.json" || entry.depth() < 2 {
+            continue;
+        }
+        let Some(dir) = entry.path().parent().and_then(|p| p.strip_prefix(root).ok()) else {
+            continue;
+        };
+        if let Some(project) = read_json(entry.path()) {
+            projects.push((dir.to_string_lossy().replace('\\', "/"), project));
+        }
+    }
+    projects
+}
+
+/// Raw member patterns declared by the workspace's package manager or Lerna
+pub fn workspace_patterns(root: &Path) -> Vec<String> {
+    let as_strings = |arr: &Vec<serde_json::Value>| -> Vec<String> {
+        arr.iter().filter_map(|v| v.as_str()).map(|s| s.to_string()).collect()
+    };
+
+    let mut patterns = Vec::new();
+
+    if let Some(workspaces) = read_json(&root.join("package.json")).and_then(|p| p.get("workspaces").cloned()) {
+        if let Some(arr) = workspaces.as_array() {
+            patterns.extend(as_strings(arr));
+        } else if let Some(arr) = workspaces.get("packages").and_then(|p| p.as_array()) {
+            // Yarn workspace format: { "packages": [...] }
+            patterns.extend(as_strings(arr));
+        }
+    }
+
+    if let Some(workspace) = fs::read_to_string(root.join("pnpm-workspace.yaml"))
+        .ok()
+        .and_then(|c| serde_yaml::from_str::<serde_yaml::Value>(&c).ok())
+    {
+        if let Some(packages) = workspace.get("packages").and_then(|p| p.as_sequence()) {
+            patterns.extend(packages.iter().filter_map(|v| v.as_str()).map(|s| s.to_string()));
+        }
+    }
+
+    if let Some(lerna) = read_json(&root.join("lerna.json")) {
+        match lerna.get("packages").and_then(|p| p.as_array()) {
+            Some(arr) => patterns.extend(as_strings(arr)),
+            None if patterns.is_empty() => patterns.push(LERNA_DEFAULT_PACKAGES.to_string()),
+            None => {}
+        }
+    }
+
+    patterns
+}
+
+/// Discover workspace packages and the dependencies between them
+pub fn discover_packages(root: &Path, patterns: &[String]) -> Vec<WorkspacePackage> {
+    // path -> (name, version, declared dependency names)
+    let mut found: BTreeMap<String, (String, Option<String>, BTreeSet<String>)> = BTreeMap::new();
+
+    for path in expand_workspace_globs(root, patterns) {
+        let Some(pkg) = read_json(&root.join(&path).join("package.json")) else {
+            continue;
+        };
+        let name = pkg
+            .get("name")
+            .and_then(|n| n.as_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| path.rsplit('/').next().unwrap_or(&path).to_string());
+        let version = pkg.get("version").and_then(|v| v.as_str()).map(|s| s.to_string());
+        let mut deps = BTreeSet::new();
+        for field in ["dependencies", "devDependencies", "optionalDependencies", "peerDependencies"] {
+            if let Some(map) = pkg.get(field).and_then(|d| d.as_object()) {
+                deps.extend(map.keys().cloned());
+            }
+        }
+        found.insert(path, (name, version, deps));
+    }
+
+    if root.join("nx.json").exists() {
+        for (path, project) in find_nx_projects(root) {
+            let entry = found.entry(path.clone()).or_insert_with(|| {
+                let fallback = path.rsplit('/').next().unwrap_or(&path).to_string();
+                (fallback, None, BTreeSet::new())
+            });
+            if let Some(name) = project.get("name").and_then(|n| n.as_str()) {
+                entry.0 = name.to_string();
+            }
+            if let Some(implicit) = project.get("implicitDependencies").and_then(|d| d.as_array()) {
+                entry.2.extend(
+                    implicit
+                        .iter()
+                        .filter_map(|d| d.as_str())
+                        .filter(|d| !d.starts_with('!') && !d.contains('*'))
+                        .map(|d| d.to_string()),
+                );
+            }
+        }
+    }
+
+    let names: BTreeSet<String> = found.values().map(|(name, _, _)| name.clone()).collect();
+    found
+        .into_iter()
+        .map(|(path, (name, version, deps))| WorkspacePackage {
+            dependencies: deps
+                .into_iter()
+                .filter(|d| names.contains(d) && *d != name)
+                .collect(),
+            name,
+            path,
+            version,
+        })
+        .collect()
+}
+
+/// Run package-scoped checks in every workspace member
+pub fn run_workspace_checks(
+    workspace: &WorkspaceInfo,
+    category: Option<CheckCategory>,
+    config: &Option<ZenvoConfig>,
+) -> Result<Vec<PackageReport>> {
+    let original_dir = env::current_dir()?;
+    let mut reports = Vec::new();
+
+    for package in &workspace.packages {
+        env::set_current_dir(original_dir.join(&package.path))?;
+        let results = super::run_package_checks(category, config);
+        // Restore before propagating any error
+        env::set_current_dir(&original_dir)?;
+
+        reports.push(PackageReport {
+            package: package.clone(),
+            results: results?,
+        });
+    }
+
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_package(root: &Path, dir: &str, json: serde_json::Value) {
+        fs::create_dir_all(root.join(dir)).unwrap();
+        fs::write(root.join(dir).join("package.json"), json.to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_segment_matches() {
+        assert!(segment_matches("*", "web"));
+        assert!(segment_matches("app-*", "app-web"));
+        assert!(segment_matches("v?", "v1"));
+        assert!(!segment_matches("app-*", "lib-web"));
+    }
+
+    #[test]
+    fn test_discover_packages() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write_package(root, ".", serde_json::json!({ "workspaces": ["packages/*", "tools/**"] }));
+        write_package(root, "packages/a", serde_json::json!({ "name": "@x/a", "version": "1.0.0" }));
+        write_package(
+            root,
+            "packages/b",
+            serde_json::json!({ "name": "@x/b", "dependencies": { "@x/a": "^1.0.0", "react": "^18" } }),
+        );
+        write_package(root, "tools/deep/c", serde_json::json!({ "name": "c" }));
+        write_package(root, "packages/a/node_modules/dep", serde_json::json!({ "name": "dep" }));
+        fs::create_dir_all(root.join("packages/empty")).unwrap();
+
+        let patterns = workspace_patterns(root);
+        let packages = discover_packages(root, &patterns);
+        let summary: Vec<(&str, &str, Vec<String>)> = packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.path.as_str(), p.dependencies.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("@x/a", "packages/a", vec![]),
+                ("@x/b", "packages/b", vec!["@x/a".to_string()]),
+                ("c", "tools/deep/c", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pnpm_negation_and_nx_projects() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("pnpm-workspace.yaml"),
+            "packages:\n  - 'packages/*'\n  - '!packages/legacy'\n",
+        )
+        .unwrap();
+        fs::write(root.join("nx.json"), "{}").unwrap();
+        write_package(root, "packages/core", serde_json::json!({ "name": "core" }));
+        write_package(root, "packages/legacy", serde_json::json!({ "name": "legacy" }));
+        fs::create_dir_all(root.join("apps/web")).unwrap();
+        fs::write(
+            root.join("apps/web/project.json"),
+            r#"{ "name": "web", "implicitDependencies": ["core"] }"#,
+        )
+        .unwrap();
+
+        let packages = discover_packages(root, &workspace_patterns(root));
+        let names: Vec<(&str, Vec<String>)> = packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.dependencies.clone()))
+            .collect();
+        assert_eq!(names, vec![("web", vec!["core".to_string()]), ("core", vec![])]);
+    }
+}
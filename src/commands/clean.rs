@@ -2,8 +2,11 @@ use anyhow::Result;
 use colored::Colorize;
 use std::fs;
 use std::path::Path;
+use std::time::Instant;
 
+use crate::journal::{EntryStatus, JournalRecorder, JournalStep};
 use crate::output::{CleanOutput, CleanTarget, OutputFormat, ZenvoOutput};
+use crate::repair::StepStatus;
 
 pub fn run(target: String, force: bool, format: OutputFormat) -> Result<()> {
     let targets: Vec<&str> = match target.as_str() {
@@ -41,6 +44,7 @@ pub fn run(target: String, force: bool, format: OutputFormat) -> Result<()> {
     let mut clean_targets: Vec<CleanTarget> = Vec::new();
     let mut cleaned: Vec<String> = Vec::new();
     let mut failed: Vec<serde_json::Value> = Vec::new();
+    let mut journal = force.then(|| JournalRecorder::start(Path::new("."), "clean", &[]));
 
     for t in &targets {
         let path = Path::new(t);
@@ -56,7 +60,22 @@ pub fn run(target: String, force: bool, format: OutputFormat) -> Result<()> {
             });
 
             if force {
-                match fs::remove_dir_all(path) {
+                let started = Instant::now();
+                let result = fs::remove_dir_all(path);
+                if let Some(journal) = &mut journal {
+                    journal.step(JournalStep {
+                        action: format!("Clean {}", t),
+                        step: format!("rm -rf {}", t),
+                        status: if result.is_ok() {
+                            StepStatus::Succeeded
+                        } else {
+                            StepStatus::Failed
+                        },
+                        exit_code: None,
+                        duration_ms: started.elapsed().as_millis() as u64,
+                    });
+                }
+                match result {
                     Ok(_) => {
                         if format == OutputFormat::Text {
                             println!(
@@ -118,6 +137,20 @@ pub fn run(target: String, force: bool, format: OutputFormat) -> Result<()> {
         }
     }
 
+    // Nothing was there to delete, so nothing changed
+    if let Some(journal) = journal.filter(|_| !cleaned.is_empty() || !failed.is_empty()) {
+        let (status, error) = match failed.first() {
+            None => (EntryStatus::Succeeded, None),
+            Some(f) => (
+                EntryStatus::Failed,
+                f["error"]
+                    .as_str()
+                    .map(|e| format!("{}: {}", f["path"].as_str().unwrap_or_default(), e)),
+            ),
+        };
+        journal.finish(status, error)?;
+    }
+
     if format == OutputFormat::Json {
         let clean_output = CleanOutput {
             targets: clean_targets,
@@ -1,10 +1,18 @@
 //! Configuration commands for Zenvo
-//! Provides `zenvo config init` and `zenvo config validate` subcommands.
+//! Provides `zenvo config init`, `zenvo config validate`, `zenvo config show` and
+//! `zenvo config schema` subcommands.
 
 use anyhow::Result;
 use colored::Colorize;
 use std::path::Path;
 
+use crate::config::discovery::{
+    discover_sources, display_path, load_discovered, load_source, package_json_config,
+    ConfigSource, PACKAGE_JSON_KEY,
+};
+use crate::config::extends::Sources;
+use crate::config::profiles::{apply_profile, selected_profile};
+use crate::config::schema::{custom_check_names, json_schema, validate_document, ConfigDiagnostic};
 use crate::config::{ZenvoConfig, CONFIG_FILE};
 use crate::output::{OutputFormat, ZenvoOutput};
 
@@ -13,6 +21,8 @@ use crate::output::{OutputFormat, ZenvoOutput};
 pub enum ConfigAction {
     Init { force: bool },
     Validate,
+    Show { effective: bool },
+    Schema,
 }
 
 /// Run the config command
@@ -20,6 +30,8 @@ pub fn run(action: ConfigAction, format: OutputFormat) -> Result<()> {
     match action {
         ConfigAction::Init { force } => run_init(force, format),
         ConfigAction::Validate => run_validate(format),
+        ConfigAction::Show { effective } => run_show(effective, format),
+        ConfigAction::Schema => run_schema(),
     }
 }
 
@@ -83,12 +95,48 @@ fn run_init(force: bool, format: OutputFormat) -> Result<()> {
     Ok(())
 }
 
+/// Strict diagnostics for one loaded file
+///
+/// A package.json `zenvo` key is validated as TOML, with every problem reported at the key.
+/// `custom_checks` are the custom check names of the merged configuration.
+fn file_diagnostics(file: &Path, custom_checks: &[String]) -> Result<Vec<ConfigDiagnostic>> {
+    let content = std::fs::read_to_string(file)?;
+    if file.file_name().map_or(true, |name| name != "package.json") {
+        return Ok(validate_document(&content, custom_checks));
+    }
+
+    let Some(value) = package_json_config(file) else {
+        return Ok(Vec::new());
+    };
+    let (line, column) = content
+        .find(&format!("\"{}\"", PACKAGE_JSON_KEY))
+        .map(|offset| {
+            let before = &content[..offset];
+            let line = before.matches('\n').count() + 1;
+            let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+            (line, column)
+        })
+        .unwrap_or((1, 1));
+    let document = toml::Value::try_from(&value)
+        .ok()
+        .and_then(|v| toml::to_string(&v).ok())
+        .unwrap_or_default();
+    Ok(validate_document(&document, custom_checks)
+        .into_iter()
+        .map(|d| ConfigDiagnostic {
+            line,
+            column,
+            ..d
+        })
+        .collect())
+}
+
 /// Validate the configuration file
 fn run_validate(format: OutputFormat) -> Result<()> {
-    let path = Path::new(CONFIG_FILE);
+    let sources = discover_sources(Path::new("."))?;
 
     // Check if config exists
-    if !path.exists() {
+    let Some(nearest) = sources.last() else {
         if format == OutputFormat::Json {
             let output = ZenvoOutput::new("config validate")
                 .with_success(false)
@@ -107,6 +155,64 @@ fn run_validate(format: OutputFormat) -> Result<()> {
             println!("Run {} to create one.", "zenvo config init".cyan());
         }
         return Ok(());
+    };
+    let label = display_path(nearest.path());
+
+    // Strict validation of every discovered file and everything it extends. Custom
+    // checks may be defined in one file (or profile) and disabled in another.
+    let mut files = Vec::new();
+    let mut custom_checks = Vec::new();
+    for source in &sources {
+        match load_source(source) {
+            Ok(layered) => {
+                custom_checks.extend(custom_check_names(&layered.table));
+                custom_checks.extend(layered.preset_commands);
+                files.extend(layered.files);
+            }
+            Err(_) => files.push(source.path().to_path_buf()),
+        }
+    }
+    let mut problems = Vec::new();
+    for file in &files {
+        let display = display_path(file);
+        for diagnostic in file_diagnostics(file, &custom_checks)? {
+            problems.push((display.clone(), diagnostic));
+        }
+    }
+
+    if !problems.is_empty() {
+        if format == OutputFormat::Json {
+            let errors: Vec<serde_json::Value> = problems
+                .iter()
+                .map(|(file, d)| {
+                    serde_json::json!({
+                        "file": file,
+                        "line": d.line,
+                        "column": d.column,
+                        "key": d.key,
+                        "message": d.message
+                    })
+                })
+                .collect();
+            let output = ZenvoOutput::new("config validate")
+                .with_success(false)
+                .with_data(serde_json::json!({
+                    "path": label,
+                    "valid": false,
+                    "errors": errors
+                }));
+            println!("{}", output.to_json()?);
+        } else {
+            println!(
+                "{} {} has validation errors",
+                "✗".red().bold(),
+                label.cyan()
+            );
+            for (file, diagnostic) in &problems {
+                println!("  {}:{}", file.dimmed(), diagnostic);
+            }
+        }
+        return Ok(());
     }
 
     // Load and validate config
@@ -119,14 +225,14 @@ fn run_validate(format: OutputFormat) -> Result<()> {
                         let output = ZenvoOutput::new("config validate")
                             .with_success(true)
                             .with_data(serde_json::json!({
-                                "path": CONFIG_FILE,
+                                "path": label,
                                 "valid": true,
                                 "disabled_checks": config.checks.disabled.len(),
                                 "severity_overrides": config.checks.severity_overrides.len()
                             }));
                         println!("{}", output.to_json()?);
                     } else {
-                        println!("{} {} is valid", "✓".green().bold(), CONFIG_FILE.cyan());
+                        println!("{} {} is valid", "✓".green().bold(), label.cyan());
                         println!();
 
                         // Show summary
@@ -159,7 +265,7 @@ fn run_validate(format: OutputFormat) -> Result<()> {
                         let output = ZenvoOutput::new("config validate")
                             .with_success(false)
                             .with_data(serde_json::json!({
-                                "path": CONFIG_FILE,
+                                "path": label,
                                 "valid": false,
                                 "error": e.to_string()
                             }));
@@ -168,7 +274,7 @@ fn run_validate(format: OutputFormat) -> Result<()> {
                         println!(
                             "{} {} has validation errors",
                             "✗".red().bold(),
-                            CONFIG_FILE.cyan()
+                            label.cyan()
                         );
                         println!("  {}", e);
                     }
@@ -180,7 +286,7 @@ fn run_validate(format: OutputFormat) -> Result<()> {
                 let output = ZenvoOutput::new("config validate")
                     .with_success(false)
                     .with_data(serde_json::json!({
-                        "path": CONFIG_FILE,
+                        "path": label,
                         "valid": false,
                         "error": e.to_string()
                     }));
@@ -189,7 +295,7 @@ fn run_validate(format: OutputFormat) -> Result<()> {
                 println!(
                     "{} Failed to parse {}",
                     "✗".red().bold(),
-                    CONFIG_FILE.cyan()
+                    label.cyan()
                 );
                 println!("  {}", e);
             }
@@ -198,3 +304,145 @@ fn run_validate(format: OutputFormat) -> Result<()> {
 
     Ok(())
 }
+
+/// Print the configuration file, or the merged result of every discovered file
+fn run_show(effective: bool, format: OutputFormat) -> Result<()> {
+    let sources = discover_sources(Path::new("."))?;
+
+    let Some(nearest) = sources.last() else {
+        if format == OutputFormat::Json {
+            let output = ZenvoOutput::new("config show")
+                .with_success(false)
+                .with_data(serde_json::json!({
+                    "error": "Config file not found",
+                    "path": CONFIG_FILE,
+                    "hint": "Run `zenvo config init` to create one"
+                }));
+            println!("{}", output.to_json()?);
+        } else {
+            println!(
+                "{} {} not found",
+                "Error:".red().bold(),
+                CONFIG_FILE.cyan()
+            );
+            println!("Run {} to create one.", "zenvo config init".cyan());
+        }
+        return Ok(());
+    };
+    let label = display_path(nearest.path());
+
+    if !effective {
+        let raw: toml::Value = match nearest {
+            ConfigSource::File(path) => toml::from_str(&std::fs::read_to_string(path)?)?,
+            ConfigSource::PackageJson(path) => {
+                toml::Value::try_from(package_json_config(path).unwrap_or_default())?
+            }
+        };
+        if format == OutputFormat::Json {
+            let output = ZenvoOutput::new("config show")
+                .with_success(true)
+                .with_data(serde_json::json!({
+                    "path": label,
+                    "effective": false,
+                    "config": raw
+                }));
+            println!("{}", output.to_json()?);
+        } else if let ConfigSource::File(path) = nearest {
+            print!("{}", std::fs::read_to_string(path)?);
+        } else {
+            println!("{}", format!("# \"{}\" in {}", PACKAGE_JSON_KEY, label).dimmed());
+            print!("{}", toml::to_string(&raw)?);
+        }
+        return Ok(());
+    }
+
+    let Some(mut layered) = load_discovered(Path::new("."))? else {
+        return Ok(());
+    };
+    let profile = apply_profile(&mut layered, selected_profile().as_ref())?;
+    let config = ZenvoConfig::from_table(layered.table)?;
+
+    // Discovered files and presets resolve to absolute paths; show them relative to here
+    for files in layered.sources.values_mut() {
+        for file in files.iter_mut() {
+            *file = display_path(file).into();
+        }
+    }
+    let merged = toml::Value::try_from(&config)?;
+
+    if format == OutputFormat::Json {
+        let sources: serde_json::Map<String, serde_json::Value> = layered
+            .sources
+            .iter()
+            .map(|(key, files)| {
+                let files: Vec<String> = files.iter().map(|f| f.display().to_string()).collect();
+                (key.clone(), serde_json::json!(files))
+            })
+            .collect();
+        let output = ZenvoOutput::new("config show")
+            .with_success(true)
+            .with_data(serde_json::json!({
+                "path": label,
+                "effective": true,
+                "profile": profile,
+                "config": config,
+                "sources": sources
+            }));
+        println!("{}", output.to_json()?);
+    } else {
+        println!(
+            "{}",
+            format!("# Effective configuration for {}", label).dimmed()
+        );
+        if let Some(ref name) = profile {
+            println!("{}", format!("# Profile: {}", name).dimmed());
+        }
+        if let Some(table) = merged.as_table() {
+            print_effective(table, "", &layered.sources);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print a merged table as TOML, annotating each value with the file it came from
+fn print_effective(table: &toml::value::Table, prefix: &str, sources: &Sources) {
+    let key_path = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        }
+    };
+
+    let leaves: Vec<(&String, &toml::Value)> = table.iter().filter(|(_, v)| !v.is_table()).collect();
+    if !leaves.is_empty() {
+        if !prefix.is_empty() {
+            println!();
+            println!("[{}]", prefix.cyan());
+        }
+        for (key, value) in leaves {
+            let source = match sources.get(&key_path(key)) {
+                Some(files) => files
+                    .iter()
+                    .map(|f| f.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                None => "(default)".to_string(),
+            };
+            println!("{} = {}  {}", key, value, format!("# {}", source).dimmed());
+        }
+    }
+
+    for (key, value) in table {
+        if let Some(inner) = value.as_table() {
+            print_effective(inner, &key_path(key), sources);
+        }
+    }
+}
+
+/// Print the JSON Schema for the configuration file
+fn run_schema() -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&json_schema())?);
+    Ok(())
+}
@@ -1,12 +1,25 @@
 use anyhow::Result;
 use colored::Colorize;
 
-use crate::checks::{CheckCategory, CheckResult, CheckSeverity, run_all_checks};
+use crate::checks::baseline::{Baseline, BASELINE_FILE};
+use crate::checks::workspace::{run_workspace_checks, PackageReport};
+use crate::checks::{detect_workspace_root, CheckCategory, CheckResult, CheckSeverity, run_all_checks};
 use crate::config::ZenvoConfig;
 use crate::lockfile::EnvLock;
 use crate::output::{Issue, OutputFormat, ZenvoOutput};
 
-pub fn run(category: Option<CheckCategory>, format: OutputFormat) -> Result<()> {
+/// `--write-baseline` settings
+pub struct BaselineOptions {
+    pub reason: Option<String>,
+    pub expires: Option<chrono::NaiveDate>,
+}
+
+pub fn run(
+    category: Option<CheckCategory>,
+    workspaces: bool,
+    baseline: Option<BaselineOptions>,
+    format: OutputFormat,
+) -> Result<()> {
     if format == OutputFormat::Text {
         println!("{}", "Running environment checks...".cyan());
         println!();
@@ -17,18 +30,56 @@ pub fn run(category: Option<CheckCategory>, format: OutputFormat) -> Result<()>
 
     // Load config if it exists
     let config = ZenvoConfig::load_if_exists()?;
+    let profile = config.as_ref().and_then(|c| c.active_profile.clone());
+    if format == OutputFormat::Text {
+        if let Some(ref name) = profile {
+            println!("{} {}", "Profile:".dimmed(), name.cyan());
+            println!();
+        }
+    }
 
     // Run checks
     let results = run_all_checks(&env_lock, category, &config)?;
 
-    // Count issues
-    let has_errors = results.iter().any(|r| r.severity == CheckSeverity::Error);
-    let has_warnings = results.iter().any(|r| r.severity == CheckSeverity::Warning);
+    // Per-package checks for workspace members
+    let packages = if workspaces {
+        match detect_workspace_root() {
+            Some(workspace) => run_workspace_checks(&workspace, category, &config)?,
+            None => Vec::new(),
+        }
+    } else {
+        Vec::new()
+    };
+
+    // Count issues across the root and every package
+    let all_results = || results.iter().chain(packages.iter().flat_map(|p| p.results.iter()));
+    let has_errors = all_results().any(|r| r.severity == CheckSeverity::Error);
+    let has_warnings = all_results().any(|r| r.severity == CheckSeverity::Warning);
+
+    // Record current findings as accepted
+    let baseline_written = match baseline {
+        Some(options) => Some(write_baseline(&results, &packages, category, workspaces, options)?),
+        None => None,
+    };
 
     // Output results
     match format {
-        OutputFormat::Json => output_json(&results, has_errors, has_warnings)?,
-        OutputFormat::Text => output_text(&results),
+        OutputFormat::Json => {
+            output_json(&results, &packages, profile.as_deref(), has_errors, has_warnings)?
+        }
+        OutputFormat::Text => output_text(&results, &packages, workspaces),
+    }
+
+    if let Some((ref path, count)) = baseline_written {
+        if format == OutputFormat::Text {
+            println!(
+                "{} Recorded {} finding{} in {}",
+                "✓".green().bold(),
+                count,
+                if count == 1 { "" } else { "s" },
+                path.cyan()
+            );
+        }
     }
 
     // Exit with error if any critical issues
@@ -39,7 +90,8 @@ pub fn run(category: Option<CheckCategory>, format: OutputFormat) -> Result<()>
     Ok(())
 }
 
-fn output_text(results: &[CheckResult]) {
+/// Print results and return (passed, warnings, errors)
+fn print_results(results: &[CheckResult]) -> (usize, usize, usize) {
     let mut errors = 0;
     let mut warnings = 0;
     let mut passed = 0;
@@ -79,6 +131,31 @@ fn output_text(results: &[CheckResult]) {
         }
     }
 
+    (passed, warnings, errors)
+}
+
+fn output_text(results: &[CheckResult], packages: &[PackageReport], workspaces: bool) {
+    let (mut passed, mut warnings, mut errors) = print_results(results);
+
+    if workspaces && packages.is_empty() {
+        println!();
+        println!("{}", "No workspace packages found.".dimmed());
+    }
+
+    for report in packages {
+        println!();
+        println!(
+            "{} {} {}",
+            "📦".cyan(),
+            report.package.name.bold(),
+            format!("({})", report.package.path).dimmed()
+        );
+        let (p, w, e) = print_results(&report.results);
+        passed += p;
+        warnings += w;
+        errors += e;
+    }
+
     println!();
     println!(
         "{}: {} passed, {} warnings, {} errors",
@@ -101,26 +178,110 @@ fn output_text(results: &[CheckResult]) {
     }
 }
 
-fn output_json(results: &[CheckResult], has_errors: bool, has_warnings: bool) -> Result<()> {
+fn summarize(results: &[CheckResult]) -> serde_json::Value {
+    let count = |severity: CheckSeverity| results.iter().filter(|r| r.severity == severity).count();
+    serde_json::json!({
+        "total": results.len(),
+        "passed": count(CheckSeverity::Pass),
+        "warnings": count(CheckSeverity::Warning),
+        "errors": count(CheckSeverity::Error)
+    })
+}
+
+fn output_json(
+    results: &[CheckResult],
+    packages: &[PackageReport],
+    profile: Option<&str>,
+    has_errors: bool,
+    has_warnings: bool,
+) -> Result<()> {
     let issues: Vec<Issue> = results.iter().map(Issue::from).collect();
 
-    let errors = results.iter().filter(|r| r.severity == CheckSeverity::Error).count();
-    let warnings = results.iter().filter(|r| r.severity == CheckSeverity::Warning).count();
-    let passed = results.iter().filter(|r| r.severity == CheckSeverity::Pass).count();
+    let all: Vec<CheckResult> = results
+        .iter()
+        .chain(packages.iter().flat_map(|p| p.results.iter()))
+        .cloned()
+        .collect();
+
+    let package_data: Vec<serde_json::Value> = packages
+        .iter()
+        .map(|report| {
+            serde_json::json!({
+                "name": report.package.name,
+                "path": report.package.path,
+                "dependencies": report.package.dependencies,
+                "summary": summarize(&report.results),
+                "issues": report.results.iter().map(Issue::from).collect::<Vec<_>>()
+            })
+        })
+        .collect();
 
     let output = ZenvoOutput::new("doctor")
         .with_success(!has_errors)
         .with_drift(has_errors || has_warnings)
         .with_issues(issues)
-        .with_data(serde_json::json!({
-            "summary": {
-                "total": results.len(),
-                "passed": passed,
-                "warnings": warnings,
-                "errors": errors
-            }
-        }));
+        .with_data(if packages.is_empty() {
+            serde_json::json!({ "summary": summarize(&all), "profile": profile })
+        } else {
+            serde_json::json!({
+                "summary": summarize(&all),
+                "profile": profile,
+                "packages": package_data
+            })
+        });
 
     println!("{}", output.to_json()?);
     Ok(())
 }
+
+/// Write the baseline next to an existing one (or in the current directory) and
+/// return its path and the number of findings recorded
+///
+/// Entries for checks that didn't run (other categories, or workspace packages without
+/// `--workspaces`) are kept from the previous baseline.
+fn write_baseline(
+    results: &[CheckResult],
+    packages: &[PackageReport],
+    category: Option<CheckCategory>,
+    workspaces: bool,
+    options: BaselineOptions,
+) -> Result<(String, usize)> {
+    let path = Baseline::find().unwrap_or_else(|| BASELINE_FILE.into());
+    let previous = if path.exists() {
+        Some(Baseline::load_from(&path)?)
+    } else {
+        None
+    };
+    let today = chrono::Local::now().date_naive();
+
+    let mut baseline = Baseline::from_results(
+        results,
+        previous.as_ref(),
+        options.reason.as_deref(),
+        options.expires,
+        today,
+    );
+    for report in packages {
+        baseline.record(
+            Some(&report.package.path),
+            &report.results,
+            previous.as_ref(),
+            options.reason.as_deref(),
+            options.expires,
+            today,
+        );
+    }
+    if let Some(ref previous) = previous {
+        baseline.carry_over(previous, |entry| {
+            let other_category =
+                category.is_some_and(|c| CheckCategory::from_name(&entry.category) != Some(c));
+            other_category || (entry.package.is_some() && !workspaces)
+        });
+    }
+    baseline.save(&path)?;
+
+    Ok((
+        crate::config::discovery::display_path(&path),
+        baseline.findings.len(),
+    ))
+}
@@ -0,0 +1,134 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+
+use crate::journal::{self, EntryStatus, JournalEntry, JournalFilter, JOURNAL_FILE};
+use crate::output::{OutputFormat, ZenvoOutput};
+use crate::repair::StepStatus;
+
+/// List journal entries matching `filter`, newest first
+pub fn run(filter: JournalFilter, limit: Option<usize>, format: OutputFormat) -> Result<()> {
+    let entries = journal::read(Path::new("."))?;
+    let total = entries.len();
+    let mut matching: Vec<JournalEntry> = entries
+        .into_iter()
+        .rev()
+        .filter(|e| filter.matches(e))
+        .collect();
+    let matched = matching.len();
+    if let Some(limit) = limit {
+        matching.truncate(limit);
+    }
+
+    if format == OutputFormat::Json {
+        let output = ZenvoOutput::new("journal")
+            .with_success(true)
+            .with_data(serde_json::json!({
+                "path": JOURNAL_FILE,
+                "total": total,
+                "matched": matched,
+                "entries": matching
+            }));
+        println!("{}", output.to_json()?);
+        return Ok(());
+    }
+
+    if matching.is_empty() {
+        if total == 0 {
+            println!("No changes recorded in {} yet.", JOURNAL_FILE.cyan());
+        } else {
+            println!("No journal entries match the filters.");
+        }
+        return Ok(());
+    }
+
+    println!("{}", "Journal".bold().cyan());
+    println!("{}", "═".repeat(50).dimmed());
+    for entry in &matching {
+        println!();
+        print_entry(entry);
+    }
+
+    if matched > matching.len() {
+        println!();
+        println!(
+            "{}",
+            format!(
+                "Showing {} of {} entries; use --limit to see more.",
+                matching.len(),
+                matched
+            )
+            .dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+fn print_entry(entry: &JournalEntry) {
+    let status = match entry.status {
+        EntryStatus::Succeeded => "✓ succeeded".green(),
+        EntryStatus::Failed => "✗ failed".red(),
+        EntryStatus::RolledBack => "↺ rolled back".yellow(),
+    };
+    let who = match &entry.host {
+        Some(host) => format!("{}@{}", entry.user, host),
+        None => entry.user.clone(),
+    };
+    println!(
+        "{}  {}  {}  {}",
+        entry
+            .timestamp
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S"),
+        entry.command.bold(),
+        who.dimmed(),
+        status
+    );
+
+    if !entry.triggered_by.is_empty() {
+        println!("  {} {}", "Checks:".dimmed(), entry.triggered_by.join(", "));
+    }
+    for step in &entry.steps {
+        let (icon, detail) = match step.status {
+            StepStatus::Succeeded => ("✓".green(), String::new()),
+            StepStatus::Failed => ("✗".red(), "failed".to_string()),
+            StepStatus::TimedOut => ("✗".red(), "timed out".to_string()),
+            StepStatus::Cancelled => ("✗".red(), "cancelled".to_string()),
+            StepStatus::Skipped => ("-".dimmed(), "skipped".to_string()),
+        };
+        let mut details: Vec<String> = Vec::new();
+        if !detail.is_empty() {
+            details.push(detail);
+        }
+        if let Some(code) = step.exit_code {
+            details.push(format!("exit {}", code));
+        }
+        if step.duration_ms > 0 {
+            details.push(format!("{:.1}s", step.duration_ms as f64 / 1000.0));
+        }
+        let details = if details.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", details.join(", "))
+        };
+        println!("  {} {}{}", icon, step.step, details.dimmed());
+    }
+    if let Some(error) = &entry.error {
+        println!(
+            "  {} {}",
+            "Error:".red(),
+            error.lines().next().unwrap_or_default()
+        );
+    }
+    let changed: Vec<String> = entry
+        .changed_files()
+        .map(|f| f.path.display().to_string())
+        .collect();
+    if !changed.is_empty() {
+        println!("  {} {}", "Changed:".dimmed(), changed.join(", "));
+    }
+    if let Some(snapshot) = &entry.snapshot {
+        println!("  {} {}", "Snapshot:".dimmed(), snapshot);
+    }
+}
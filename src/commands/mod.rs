@@ -3,6 +3,7 @@ pub mod config;
 pub mod diff;
 pub mod doctor;
 pub mod init;
+pub mod journal;
 pub mod lock;
 pub mod repair;
 pub mod resolve;
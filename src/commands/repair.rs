@@ -1,15 +1,31 @@
 use anyhow::Result;
 use colored::Colorize;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use crate::checks::{run_all_checks, CheckSeverity};
 use crate::config::ZenvoConfig;
+use crate::journal::{EntryStatus, JournalRecorder};
 use crate::lockfile::EnvLock;
 use crate::output::{OutputFormat, RepairActionJson, RepairPlanOutput, ZenvoOutput};
-use crate::repair::{execute_repair, generate_repair_plan_with_context, RepairContext};
+use crate::repair::backup::BACKUP_DIR;
+use crate::repair::emit::{self, EmitFormat};
+use crate::repair::verify::{recheck, verify_repair};
+use crate::repair::{
+    execute_repair, generate_repair_plan_with_context, ActionRun, IssueVerdict, RepairContext,
+    RunOptions, Snapshot, StepStatus, VerifiedIssue,
+};
 
-pub fn run(plan: bool, apply: bool, auto_yes: bool, format: OutputFormat) -> Result<()> {
-    if !plan && !apply {
+pub fn run(
+    plan: bool,
+    apply: bool,
+    auto_yes: bool,
+    backup_node_modules: bool,
+    emit: Option<EmitFormat>,
+    format: OutputFormat,
+) -> Result<()> {
+    if !plan && !apply && emit.is_none() {
         if format == OutputFormat::Json {
             let output = ZenvoOutput::new("repair")
                 .with_success(false)
@@ -27,6 +43,11 @@ pub fn run(plan: bool, apply: bool, auto_yes: bool, format: OutputFormat) -> Res
             println!("  {} Show what would be fixed", "--plan".cyan());
             println!("  {} Execute the repair plan", "--apply".cyan());
             println!("  {} Auto-approve safe repairs", "-y".cyan());
+            println!(
+                "  {} Print the plan as sh, powershell, makefile, github-actions or json",
+                "--emit <FORMAT>".cyan()
+            );
+            println!("  {} Restore the snapshot taken before a repair", "--rollback [ID]".cyan());
         }
         return Ok(());
     }
@@ -44,7 +65,8 @@ pub fn run(plan: bool, apply: bool, auto_yes: bool, format: OutputFormat) -> Res
         .filter(|r| r.severity == CheckSeverity::Error || r.severity == CheckSeverity::Warning)
         .collect();
 
-    if issues.is_empty() {
+    // An emitted plan is still written when there's nothing to do, so pipelines get a file
+    if issues.is_empty() && emit.is_none() {
         if format == OutputFormat::Json {
             let output = ZenvoOutput::new("repair")
                 .with_success(true)
@@ -64,22 +86,21 @@ pub fn run(plan: bool, apply: bool, auto_yes: bool, format: OutputFormat) -> Res
     }
 
     // Create repair context from env.lock
-    let repair_context = RepairContext::new(&env_lock.toolchain.package_manager)
-        .with_node_version_manager(env_lock.toolchain.node_version_source.clone())
-        .with_target_node_version(Some(env_lock.toolchain.node.clone()));
+    let repair_context = RepairContext::from_env(&env_lock, &config);
 
     // Generate repair plan with context
     let repair_plan = generate_repair_plan_with_context(&issues, &repair_context)?;
 
+    if let Some(emit_format) = emit {
+        print!("{}", emit::render(&repair_plan, &repair_context, emit_format)?);
+        return Ok(());
+    }
+
     if plan {
         // Convert to JSON-friendly format
         let actions_json: Vec<RepairActionJson> = repair_plan
             .iter()
-            .map(|a| RepairActionJson {
-                description: a.description.clone(),
-                command: a.command.clone(),
-                is_safe: a.is_safe,
-            })
+            .map(|a| RepairActionJson::from_action(a, None))
             .collect();
 
         let safe_count = actions_json.iter().filter(|a| a.is_safe).count();
@@ -91,6 +112,7 @@ pub fn run(plan: bool, apply: bool, auto_yes: bool, format: OutputFormat) -> Res
                 total_issues: issues.len(),
                 safe_actions: safe_count,
                 review_actions: review_count,
+                verification: None,
             };
 
             let output = ZenvoOutput::new("repair")
@@ -118,7 +140,21 @@ pub fn run(plan: bool, apply: bool, auto_yes: bool, format: OutputFormat) -> Res
                     action.description,
                     safety_badge
                 );
-                println!("   {} {}", "Command:".dimmed(), action.command.cyan());
+                for reason in &action.reasons {
+                    println!("   {} {}", "Why:".dimmed(), reason);
+                }
+                if !action.after.is_empty() {
+                    let after: Vec<String> =
+                        action.after.iter().map(|i| (i + 1).to_string()).collect();
+                    println!("   {} {}", "After:".dimmed(), after.join(", "));
+                }
+                for step in &action.steps {
+                    if step.is_manual() {
+                        println!("   {} {}", "Manual:".dimmed(), step.preview().yellow());
+                    } else {
+                        println!("   {} {}", "Command:".dimmed(), step.preview().cyan());
+                    }
+                }
                 println!();
             }
 
@@ -141,14 +177,61 @@ pub fn run(plan: bool, apply: bool, auto_yes: bool, format: OutputFormat) -> Res
             println!();
         }
 
-        for action in &repair_plan {
+        // Files the plan writes or deletes besides the usual ones
+        let touched_paths: Vec<PathBuf> = repair_plan
+            .iter()
+            .flat_map(|a| a.steps.iter())
+            .filter_map(|s| s.touched_path())
+            .map(Path::to_path_buf)
+            .collect();
+
+        // Save everything the plan may touch so a failed repair can be undone
+        let snapshot = if repair_plan.iter().any(|a| !a.is_manual()) {
+            let snapshot = Snapshot::for_actions(&repair_plan, backup_node_modules)?;
+            if format == OutputFormat::Text {
+                println!(
+                    "{} Snapshot saved to {}/{}",
+                    "✓".green(),
+                    BACKUP_DIR,
+                    snapshot.id
+                );
+                println!();
+            }
+            Some(snapshot)
+        } else {
+            None
+        };
+        let mut rolled_back = false;
+        let mut verified: Vec<Option<bool>> = vec![None; repair_plan.len()];
+        let mut runs: Vec<Option<ActionRun>> = vec![None; repair_plan.len()];
+
+        let mut journal = JournalRecorder::start(Path::new("."), "repair", &touched_paths);
+        journal.triggered_by(issues.iter().copied());
+        if let Some(snapshot) = &snapshot {
+            journal.snapshot(&snapshot.id);
+        }
+
+        for (i, action) in repair_plan.iter().enumerate() {
             if format == OutputFormat::Text {
                 println!("{} {}", "→".cyan(), action.description);
+                for reason in &action.reasons {
+                    println!("  {} {}", "Why:".dimmed(), reason);
+                }
+            }
+
+            // Nothing to run; leave it to the user
+            if action.is_manual() {
+                if format == OutputFormat::Text {
+                    println!("  {} {}", "Manual:".yellow(), action.command());
+                }
+                journal.skipped_action(action);
+                skipped.push(action.description.clone());
+                continue;
             }
 
             // Confirm if not safe and not auto-yes (only in text mode)
             if !action.is_safe && !auto_yes && format == OutputFormat::Text {
-                print!("  Execute {}? [y/N] ", action.command.cyan());
+                print!("  Execute {}? [y/N] ", action.command().cyan());
                 io::stdout().flush()?;
 
                 let mut input = String::new();
@@ -158,6 +241,7 @@ pub fn run(plan: bool, apply: bool, auto_yes: bool, format: OutputFormat) -> Res
                     if format == OutputFormat::Text {
                         println!("  {}", "Skipped".yellow());
                     }
+                    journal.skipped_action(action);
                     skipped.push(action.description.clone());
                     continue;
                 }
@@ -165,46 +249,252 @@ pub fn run(plan: bool, apply: bool, auto_yes: bool, format: OutputFormat) -> Res
 
             // In JSON mode with auto_yes=false, skip non-safe actions
             if !action.is_safe && !auto_yes && format == OutputFormat::Json {
+                journal.skipped_action(action);
                 skipped.push(action.description.clone());
                 continue;
             }
 
-            // Execute
-            match execute_repair(action) {
-                Ok(_) => {
+            // Execute, streaming the output in text mode
+            let options = RunOptions::for_action(action, &config, format == OutputFormat::Text);
+            let run = execute_repair(action, &options);
+            let took = format_duration(run.duration());
+            let error = run.error.clone();
+            journal.action_run(&action.description, &run);
+            runs[i] = Some(run);
+            match error {
+                None => {
+                    // Exiting 0 isn't enough; the issue itself has to be gone
+                    verified[i] = recheck(action, &config)?;
                     if format == OutputFormat::Text {
-                        println!("  {}", "Done".green());
+                        if verified[i] == Some(false) {
+                            println!(
+                                "  {} {}",
+                                "Done, but the issue is still reported".yellow(),
+                                took.dimmed()
+                            );
+                        } else {
+                            println!("  {} {}", "Done".green(), took.dimmed());
+                        }
                     }
                     executed.push(action.description.clone());
                 }
-                Err(e) => {
+                Some(e) => {
                     if format == OutputFormat::Text {
-                        println!("  {} {}", "Failed:".red(), e);
+                        println!("  {} {} {}", "Failed:".red(), e, took.dimmed());
                     }
                     failed.push(serde_json::json!({
                         "action": action.description,
-                        "error": e.to_string()
+                        "error": e
                     }));
+
+                    // Stop here and put the project back the way it was
+                    if let Some(snapshot) = &snapshot {
+                        snapshot.restore(Path::new("."))?;
+                        rolled_back = true;
+                        if format == OutputFormat::Text {
+                            println!();
+                            println!(
+                                "{} Rolled back to snapshot {}",
+                                "↺".yellow(),
+                                snapshot.id
+                            );
+                        }
+                    }
+                    break;
                 }
             }
         }
 
+        // Run everything again to see what the repair actually changed
+        let verification = if rolled_back {
+            None
+        } else {
+            Some(verify_repair(&issues, &config)?)
+        };
+        let resolved = failed.is_empty()
+            && verification.as_ref().is_some_and(|v| {
+                v.iter().all(|issue| issue.verdict == IssueVerdict::Fixed)
+            });
+
+        let status = if rolled_back {
+            EntryStatus::RolledBack
+        } else if failed.is_empty() {
+            EntryStatus::Succeeded
+        } else {
+            EntryStatus::Failed
+        };
+        let error = failed
+            .first()
+            .and_then(|f| f["error"].as_str())
+            .map(str::to_string);
+        journal.finish(status, error)?;
+
         if format == OutputFormat::Json {
+            let actions: Vec<RepairActionJson> = repair_plan
+                .iter()
+                .zip(&verified)
+                .zip(runs)
+                .map(|((a, v), run)| RepairActionJson::from_action(a, *v).with_run(run))
+                .collect();
+            let safe_count = actions.iter().filter(|a| a.is_safe).count();
+            let review_count = actions.len() - safe_count;
+            let plan_output = RepairPlanOutput {
+                actions,
+                total_issues: issues.len(),
+                safe_actions: safe_count,
+                review_actions: review_count,
+                verification,
+            };
+
+            let mut data = serde_json::to_value(&plan_output)?;
+            if let Some(object) = data.as_object_mut() {
+                object.insert("executed".to_string(), serde_json::json!(executed));
+                object.insert("skipped".to_string(), serde_json::json!(skipped));
+                object.insert("failed".to_string(), serde_json::json!(failed));
+                object.insert("total".to_string(), serde_json::json!(repair_plan.len()));
+                object.insert(
+                    "snapshot".to_string(),
+                    serde_json::json!(snapshot.as_ref().map(|s| &s.id)),
+                );
+                object.insert("rolled_back".to_string(), serde_json::json!(rolled_back));
+            }
+
             let output = ZenvoOutput::new("repair")
-                .with_success(failed.is_empty())
-                .with_data(serde_json::json!({
-                    "executed": executed,
-                    "skipped": skipped,
-                    "failed": failed,
-                    "total": repair_plan.len()
-                }));
+                .with_success(resolved)
+                .with_drift(!resolved)
+                .with_data(data);
 
             println!("{}", output.to_json()?);
+        } else if rolled_back {
+            if snapshot.as_ref().is_some_and(|s| s.node_modules) {
+                println!(
+                    "{}",
+                    "Repair failed; no changes were kept. Fix the error above and try again."
+                        .yellow()
+                );
+            } else {
+                // node_modules isn't in the snapshot, so whatever the steps did to it stays
+                println!(
+                    "{}",
+                    "Repair failed; the saved files were restored. Fix the error above and try again."
+                        .yellow()
+                );
+                println!(
+                    "{} node_modules was not saved; reinstall if it no longer matches the lockfile.",
+                    "→".cyan()
+                );
+            }
+        } else {
+            if let Some(verification) = &verification {
+                print_verification(verification);
+            }
+            println!();
+            if resolved {
+                println!("{}", "Repair complete. All issues are fixed. ✨".green());
+            } else {
+                println!(
+                    "{}",
+                    "Repair finished, but some issues remain. Run `zenvo doctor` for details."
+                        .yellow()
+                );
+            }
+            if let Some(snapshot) = &snapshot {
+                println!(
+                    "{} Run {} to undo it.",
+                    "→".cyan(),
+                    format!("zenvo repair --rollback {}", snapshot.id).cyan()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Elapsed time for an action, e.g. `(1m 05s)` or `(3.2s)`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 60 {
+        format!("({}m {:02}s)", secs / 60, secs % 60)
+    } else {
+        format!("({:.1}s)", duration.as_secs_f64())
+    }
+}
+
+/// Print each issue with its state after the repair
+fn print_verification(verification: &[VerifiedIssue]) {
+    println!();
+    println!("{}", "Verification".bold().cyan());
+    println!("{}", "═".repeat(50).dimmed());
+    for issue in verification {
+        match issue.verdict {
+            IssueVerdict::Fixed => println!("  {} {}", "✓".green(), issue.name),
+            IssueVerdict::StillFailing => println!(
+                "  {} {} {}",
+                "✗".red(),
+                issue.name,
+                format!("- still failing: {}", issue.message).dimmed()
+            ),
+            IssueVerdict::Introduced => println!(
+                "  {} {} {}",
+                "!".yellow(),
+                issue.name,
+                format!("- new: {}", issue.message).dimmed()
+            ),
+        }
+    }
+}
+
+/// Restore the snapshot `id`, or the latest one
+pub fn rollback(id: Option<&str>, format: OutputFormat) -> Result<()> {
+    let root = Path::new(".");
+    let snapshot = Snapshot::find(root, id)?;
+    let extra: Vec<PathBuf> = snapshot.files.iter().map(|f| f.path.clone()).collect();
+    let mut journal = JournalRecorder::start(root, "repair --rollback", &extra);
+    journal.snapshot(&snapshot.id);
+    let restore = snapshot.restore(root);
+    let (status, step_status, error) = match &restore {
+        Ok(()) => (EntryStatus::Succeeded, StepStatus::Succeeded, None),
+        Err(e) => (EntryStatus::Failed, StepStatus::Failed, Some(e.to_string())),
+    };
+    let step = format!("restore snapshot {}", snapshot.id);
+    journal.simple_step("Roll back", &step, step_status, None);
+    journal.finish(status, error)?;
+    restore?;
+
+    let restored: Vec<String> = snapshot
+        .files
+        .iter()
+        .map(|f| f.path.display().to_string())
+        .collect();
+
+    if format == OutputFormat::Json {
+        let output = ZenvoOutput::new("repair")
+            .with_success(true)
+            .with_data(serde_json::json!({
+                "snapshot": snapshot.id,
+                "created": snapshot.created.to_rfc3339(),
+                "restored": restored,
+                "node_modules": snapshot.node_modules
+            }));
+        println!("{}", output.to_json()?);
+    } else {
+        println!(
+            "{} Restored snapshot {} ({})",
+            "✓".green(),
+            snapshot.id.bold(),
+            snapshot.created.format("%Y-%m-%d %H:%M:%S")
+        );
+        for path in &restored {
+            println!("  {}", path.dimmed());
+        }
+        if snapshot.node_modules {
+            println!("  {}", "node_modules".dimmed());
         } else {
             println!();
             println!(
-                "{}",
-                "Repair complete. Run `zenvo doctor` to verify.".green()
+                "{} node_modules was not saved; reinstall if it no longer matches the lockfile.",
+                "→".cyan()
             );
         }
     }
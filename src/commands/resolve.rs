@@ -4,9 +4,12 @@ use anyhow::{Context, Result};
 use colored::Colorize;
 use serde::Serialize;
 use std::io::{self, Write};
+use std::path::Path;
 use std::process::Command;
 
+use crate::journal::{EntryStatus, JournalRecorder};
 use crate::output::OutputFormat;
+use crate::repair::StepStatus;
 
 /// A detected dependency conflict
 #[derive(Debug, Clone, Serialize)]
@@ -73,7 +76,7 @@ pub fn run(dry_run: bool, format: OutputFormat) -> Result<()> {
 
     let mut resolutions = Vec::new();
     for conflict in &conflicts {
-        if let Some(resolution) = find_resolution(&conflict)? {
+        if let Some(resolution) = find_resolution(conflict)? {
             resolutions.push(resolution);
         }
     }
@@ -135,7 +138,22 @@ pub fn run(dry_run: bool, format: OutputFormat) -> Result<()> {
     }
 
     // Step 5: Apply changes
-    apply_resolutions(&resolutions)?;
+    let mut journal = JournalRecorder::start(Path::new("."), "resolve", &[]);
+    let applied = apply_resolutions(&resolutions);
+    let (status, step_status, error) = match &applied {
+        Ok(()) => (EntryStatus::Succeeded, StepStatus::Succeeded, None),
+        Err(e) => (EntryStatus::Failed, StepStatus::Failed, Some(e.to_string())),
+    };
+    for res in &resolutions {
+        journal.simple_step(
+            &format!("Resolve {}", res.package),
+            &format!("set {} to ^{} in package.json", res.package, res.suggested_version),
+            step_status,
+            None,
+        );
+    }
+    journal.finish(status, error)?;
+    applied?;
 
     println!();
     println!("{} Changes applied. Run {} to install.", "✓".green(), "npm install".cyan());
@@ -244,22 +262,23 @@ fn parse_npm_conflicts(output: &str) -> Result<Vec<DependencyConflict>> {
         }
 
         // "Could not resolve dependency:" signals end of conflict block
-        if line.contains("Could not resolve dependency") {
-            if !conflicting_dep.is_empty() && !actual_version.is_empty() {
-                conflicts.push(DependencyConflict {
-                    package: conflicting_dep.clone(),
-                    current_version: actual_version.clone(),
-                    conflicting_dep: current_package.clone(),
-                    required_range: required_range.clone(),
-                    actual_version: if !suggested_version.is_empty() {
-                        format!("{} (suggested: {})", actual_version.clone(), suggested_version.clone())
-                    } else {
-                        actual_version.clone()
-                    },
-                });
-                // Reset for next conflict
-                suggested_version.clear();
-            }
+        if line.contains("Could not resolve dependency")
+            && !conflicting_dep.is_empty()
+            && !actual_version.is_empty()
+        {
+            conflicts.push(DependencyConflict {
+                package: conflicting_dep.clone(),
+                current_version: actual_version.clone(),
+                conflicting_dep: current_package.clone(),
+                required_range: required_range.clone(),
+                actual_version: if !suggested_version.is_empty() {
+                    format!("{} (suggested: {})", actual_version.clone(), suggested_version.clone())
+                } else {
+                    actual_version.clone()
+                },
+            });
+            // Reset for next conflict
+            suggested_version.clear();
         }
     }
 
@@ -1,10 +1,14 @@
 use anyhow::Result;
 use colored::Colorize;
 use std::io::{self, Write};
+use std::path::Path;
 use std::process::Command;
+use std::time::Instant;
 
+use crate::journal::{EntryStatus, JournalRecorder, JournalStep};
 use crate::lockfile::EnvLock;
 use crate::output::{OutputFormat, ZenvoOutput};
+use crate::repair::{RepairStep, StepStatus};
 
 /// Upgrade result for a package
 #[derive(Debug, Clone, serde::Serialize)]
@@ -218,12 +222,35 @@ pub fn run(interactive: bool, major: bool, dry_run: bool, format: OutputFormat)
         cmd.arg(pkg);
     }
 
+    let mut argv = vec![pkg_manager.clone()];
+    argv.extend(install_args.iter().map(|a| a.to_string()));
+    argv.extend(packages_to_upgrade.iter().cloned());
+    let mut journal = JournalRecorder::start(Path::new("."), "upgrade", &[]);
+
+    let started = Instant::now();
     let output = cmd.output()?;
+    journal.step(JournalStep {
+        action: format!("Upgrade {} packages", packages_to_upgrade.len()),
+        step: RepairStep::exec(&argv).preview(),
+        status: if output.status.success() {
+            StepStatus::Succeeded
+        } else {
+            StepStatus::Failed
+        },
+        exit_code: output.status.code(),
+        duration_ms: started.elapsed().as_millis() as u64,
+    });
 
     if output.status.success() {
         // Regenerate env.lock
-        let env_lock = EnvLock::generate()?;
-        env_lock.save(std::path::Path::new("env.lock"))?;
+        let relock = EnvLock::generate().and_then(|env_lock| env_lock.save(Path::new("env.lock")));
+        let (status, step_status, error) = match &relock {
+            Ok(()) => (EntryStatus::Succeeded, StepStatus::Succeeded, None),
+            Err(e) => (EntryStatus::Failed, StepStatus::Failed, Some(e.to_string())),
+        };
+        journal.simple_step("Update env.lock", "zenvo lock", step_status, None);
+        journal.finish(status, error)?;
+        relock?;
 
         if format == OutputFormat::Json {
             let output = ZenvoOutput::new("upgrade")
@@ -250,6 +277,7 @@ pub fn run(interactive: bool, major: bool, dry_run: bool, format: OutputFormat)
         }
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr);
+        journal.finish(EntryStatus::Failed, Some(stderr.trim().to_string()))?;
         if format == OutputFormat::Json {
             let output = ZenvoOutput::new("upgrade")
                 .with_success(false)
@@ -350,9 +378,7 @@ fn determine_upgrade_type(current: &str, wanted: &str, latest: &str) -> String {
     let (w_major, w_minor, _) = parse_version(wanted);
     let (l_major, _, _) = parse_version(latest);
 
-    if l_major > c_major {
-        "major".to_string()
-    } else if w_major > c_major {
+    if l_major > c_major || w_major > c_major {
         "major".to_string()
     } else if w_minor > c_minor {
         "minor".to_string()
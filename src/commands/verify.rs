@@ -1,6 +1,7 @@
 use anyhow::Result;
 use colored::Colorize;
 
+use crate::checks::baseline::{Baseline, Triage};
 use crate::checks::{run_all_checks, CheckSeverity};
 use crate::config::ZenvoConfig;
 use crate::lockfile::EnvLock;
@@ -12,22 +13,42 @@ use crate::output::{Issue, OutputFormat, ZenvoOutput};
 /// - Default: Exit 1 on errors, exit 0 on warnings only
 /// - --strict: Exit 1 on errors OR warnings
 /// - --warn: Exit 0 always, but print warnings/errors (don't fail CI)
+///
+/// Findings accepted in `.zenvo/baseline.toml` are ignored until their entry expires.
 pub fn run(strict: bool, warn_only: bool, format: OutputFormat) -> Result<()> {
     // Load env.lock (required for verify)
     let env_lock = EnvLock::load()?;
 
     // Load config if it exists
     let config = ZenvoConfig::load_if_exists()?;
+    let profile = config.as_ref().and_then(|c| c.active_profile.clone());
 
     // Run all checks
     let results = run_all_checks(&Some(env_lock), None, &config)?;
 
-    let errors: Vec<_> = results
+    // Split findings by the baseline
+    let baseline = Baseline::load_if_exists()?;
+    let today = chrono::Local::now().date_naive();
+    let triage = baseline.as_ref().map(|b| b.triage(&results, today));
+    let findings: Vec<_> = match triage {
+        Some(ref triage) => triage
+            .new
+            .iter()
+            .copied()
+            .chain(triage.expired.iter().map(|(result, _)| *result))
+            .collect(),
+        None => results
+            .iter()
+            .filter(|r| r.severity == CheckSeverity::Error || r.severity == CheckSeverity::Warning)
+            .collect(),
+    };
+
+    let errors: Vec<_> = findings
         .iter()
         .filter(|r| r.severity == CheckSeverity::Error)
         .collect();
 
-    let warnings: Vec<_> = results
+    let warnings: Vec<_> = findings
         .iter()
         .filter(|r| r.severity == CheckSeverity::Warning)
         .collect();
@@ -49,11 +70,23 @@ pub fn run(strict: bool, warn_only: bool, format: OutputFormat) -> Result<()> {
     let passed = !should_fail;
 
     if format == OutputFormat::Json {
-        let issues: Vec<Issue> = results
-            .iter()
-            .filter(|r| r.severity == CheckSeverity::Error || r.severity == CheckSeverity::Warning)
-            .map(Issue::from)
-            .collect();
+        let issues: Vec<Issue> = findings.iter().map(|r| Issue::from(*r)).collect();
+        let baseline_data = triage.as_ref().map(|triage| {
+            serde_json::json!({
+                "accepted": triage.accepted.len(),
+                "expired": triage
+                    .expired
+                    .iter()
+                    .map(|(result, entry)| serde_json::json!({
+                        "check": result.name,
+                        "fingerprint": entry.fingerprint,
+                        "expires": entry.expires,
+                        "reason": entry.reason
+                    }))
+                    .collect::<Vec<_>>(),
+                "stale": triage.stale.len()
+            })
+        });
 
         let output = ZenvoOutput::new("verify")
             .with_success(passed)
@@ -62,6 +95,8 @@ pub fn run(strict: bool, warn_only: bool, format: OutputFormat) -> Result<()> {
             .with_data(serde_json::json!({
                 "strict": strict,
                 "warn_only": warn_only,
+                "profile": profile,
+                "baseline": baseline_data,
                 "errors": errors.len(),
                 "warnings": warnings.len(),
                 "passed": results.iter().filter(|r| r.severity == CheckSeverity::Pass).count()
@@ -74,6 +109,12 @@ pub fn run(strict: bool, warn_only: bool, format: OutputFormat) -> Result<()> {
         }
     } else {
         // CI-friendly output
+        if let Some(ref name) = profile {
+            println!("{} {}", "Profile:".dimmed(), name.cyan());
+        }
+        if let Some(ref triage) = triage {
+            print_baseline_summary(triage);
+        }
         if !has_drift {
             println!("{} Environment matches env.lock", "✓".green().bold());
             std::process::exit(0);
@@ -131,3 +172,33 @@ pub fn run(strict: bool, warn_only: bool, format: OutputFormat) -> Result<()> {
 
     Ok(())
 }
+
+/// Report accepted, expired and stale baseline entries
+fn print_baseline_summary(triage: &Triage) {
+    if !triage.accepted.is_empty() {
+        println!(
+            "{}",
+            format!("{} accepted finding(s) in the baseline", triage.accepted.len()).dimmed()
+        );
+    }
+    for (result, entry) in &triage.expired {
+        println!(
+            "{} Baseline entry for {} expired on {} ({})",
+            "⚠".yellow().bold(),
+            result.name.bold(),
+            entry.expires.map(|d| d.to_string()).unwrap_or_default(),
+            entry.reason
+        );
+    }
+    if !triage.stale.is_empty() {
+        println!(
+            "{}",
+            format!(
+                "{} baseline entr{} no longer match; run `zenvo doctor --write-baseline` to prune them",
+                triage.stale.len(),
+                if triage.stale.len() == 1 { "y" } else { "ies" }
+            )
+            .dimmed()
+        );
+    }
+}
@@ -262,20 +262,20 @@ fn filter_by_constraint(versions: Vec<VersionInfo>, constraint: &str) -> Vec<Ver
     let constraint = constraint.trim();
 
     // Parse constraint
-    let (operator, version_str) = if constraint.starts_with(">=") {
-        (">=", &constraint[2..])
-    } else if constraint.starts_with("<=") {
-        ("<=", &constraint[2..])
-    } else if constraint.starts_with('^') {
-        ("^", &constraint[1..])
-    } else if constraint.starts_with('~') {
-        ("~", &constraint[1..])
-    } else if constraint.starts_with('>') {
-        (">", &constraint[1..])
-    } else if constraint.starts_with('<') {
-        ("<", &constraint[1..])
-    } else if constraint.starts_with('=') {
-        ("=", &constraint[1..])
+    let (operator, version_str) = if let Some(rest) = constraint.strip_prefix(">=") {
+        (">=", rest)
+    } else if let Some(rest) = constraint.strip_prefix("<=") {
+        ("<=", rest)
+    } else if let Some(rest) = constraint.strip_prefix('^') {
+        ("^", rest)
+    } else if let Some(rest) = constraint.strip_prefix('~') {
+        ("~", rest)
+    } else if let Some(rest) = constraint.strip_prefix('>') {
+        (">", rest)
+    } else if let Some(rest) = constraint.strip_prefix('<') {
+        ("<", rest)
+    } else if let Some(rest) = constraint.strip_prefix('=') {
+        ("=", rest)
     } else {
         ("=", constraint)
     };
@@ -0,0 +1,235 @@
+//! Locating configuration and env.lock files
+//!
+//! Discovery starts in the current directory and walks up to the repository root
+//! (the nearest directory containing `.git`). Every `.env.doctor.toml` and package.json
+//! `"zenvo"` key found on the way is merged, nearer over farther; in the same
+//! directory the TOML file wins over package.json. A `--config <path>` given on the
+//! command line replaces discovery entirely.
+
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::extends::{load_layered, load_layered_table, LayeredConfig};
+use super::CONFIG_FILE;
+
+/// Key in package.json that can hold the configuration
+pub const PACKAGE_JSON_KEY: &str = "zenvo";
+
+/// Config file passed with `--config`
+static CONFIG_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `path` instead of discovering configuration files
+pub fn set_config_override(path: PathBuf) {
+    let _ = CONFIG_OVERRIDE.set(path);
+}
+
+/// The `--config` path, if one was given
+pub fn config_override() -> Option<&'static Path> {
+    CONFIG_OVERRIDE.get().map(|p| p.as_path())
+}
+
+/// Directories searched for configuration, nearest first
+///
+/// Without a `.git` directory in any parent only `start` itself is searched, so
+/// unrelated files further up (such as in the home directory) are never picked up.
+pub fn search_dirs(start: &Path) -> Vec<PathBuf> {
+    let start = start.canonicalize().unwrap_or_else(|_| start.to_path_buf());
+    let mut dirs = Vec::new();
+    for dir in start.ancestors() {
+        dirs.push(dir.to_path_buf());
+        if dir.join(".git").exists() {
+            return dirs;
+        }
+    }
+    vec![start]
+}
+
+/// Where a piece of configuration was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// A `.env.doctor.toml` (or `--config`) file
+    File(PathBuf),
+    /// The `"zenvo"` key of a package.json
+    PackageJson(PathBuf),
+}
+
+impl ConfigSource {
+    pub fn path(&self) -> &Path {
+        match self {
+            ConfigSource::File(p) | ConfigSource::PackageJson(p) => p,
+        }
+    }
+}
+
+/// The `"zenvo"` value of a package.json, if it has one
+pub fn package_json_config(path: &Path) -> Option<serde_json::Value> {
+    let content = fs::read_to_string(path).ok()?;
+    let pkg: serde_json::Value = serde_json::from_str(&content).ok()?;
+    pkg.get(PACKAGE_JSON_KEY).cloned()
+}
+
+/// Configuration sources that apply in `start`, farthest first (merge order)
+pub fn discover_sources(start: &Path) -> Result<Vec<ConfigSource>> {
+    if let Some(path) = config_override() {
+        if !path.is_file() {
+            bail!("Config file not found: {}", path.display());
+        }
+        return Ok(vec![ConfigSource::File(path.to_path_buf())]);
+    }
+
+    let mut sources = Vec::new();
+    for dir in search_dirs(start).into_iter().rev() {
+        let package_json = dir.join("package.json");
+        if package_json_config(&package_json).is_some() {
+            sources.push(ConfigSource::PackageJson(package_json));
+        }
+        let file = dir.join(CONFIG_FILE);
+        if file.is_file() {
+            sources.push(ConfigSource::File(file));
+        }
+    }
+    Ok(sources)
+}
+
+/// Load one source with its `extends` resolved
+pub fn load_source(source: &ConfigSource) -> Result<LayeredConfig> {
+    match source {
+        ConfigSource::File(path) => load_layered(path),
+        ConfigSource::PackageJson(path) => {
+            let value = package_json_config(path).unwrap_or_default();
+            let table = match toml::Value::try_from(&value) {
+                Ok(toml::Value::Table(table)) => table,
+                Ok(_) => bail!(
+                    "\"{}\" in {} must be an object",
+                    PACKAGE_JSON_KEY,
+                    path.display()
+                ),
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!(
+                            "Invalid \"{}\" config in {}",
+                            PACKAGE_JSON_KEY,
+                            path.display()
+                        )
+                    })
+                }
+            };
+            load_layered_table(table, path)
+        }
+    }
+}
+
+/// Discover and merge all configuration that applies in `start`
+pub fn load_discovered(start: &Path) -> Result<Option<LayeredConfig>> {
+    let sources = discover_sources(start)?;
+    let mut merged: Option<LayeredConfig> = None;
+    for source in &sources {
+        let layer = load_source(source)?;
+        match merged.as_mut() {
+            Some(config) => config.merge(layer),
+            None => merged = Some(layer),
+        }
+    }
+    Ok(merged)
+}
+
+/// Nearest `name` between `start` and the repository root
+pub fn find_nearest(start: &Path, name: &str) -> Option<PathBuf> {
+    search_dirs(start)
+        .into_iter()
+        .map(|dir| dir.join(name))
+        .find(|p| p.is_file())
+}
+
+/// Render `path` relative to the current directory (`../../.env.doctor.toml`)
+pub fn display_path(path: &Path) -> String {
+    let Ok(cwd) = std::env::current_dir().and_then(|c| c.canonicalize()) else {
+        return path.display().to_string();
+    };
+    if !path.is_absolute() {
+        return path.display().to_string();
+    }
+
+    let target: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = cwd.components().collect();
+    let common = target.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return path.display().to_string();
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &target[common..] {
+        relative.push(component);
+    }
+    relative.display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_discovery_merges_nearer_over_farther() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let web = root.join("packages/web");
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(&web).unwrap();
+
+        fs::write(
+            root.join(CONFIG_FILE),
+            "[policies]\nenforce_corepack = true\nmin_node_version = \"18.0.0\"\n",
+        )
+        .unwrap();
+        fs::write(
+            web.join("package.json"),
+            r#"{ "name": "web", "zenvo": { "policies": { "min_node_version": "20.0.0" }, "checks": { "disabled": ["A"] } } }"#,
+        )
+        .unwrap();
+        fs::write(web.join(CONFIG_FILE), "[checks]\ndisabled = [\"B\"]\n").unwrap();
+
+        let sources = discover_sources(&web).unwrap();
+        assert_eq!(
+            sources,
+            vec![
+                ConfigSource::File(root.join(CONFIG_FILE)),
+                ConfigSource::PackageJson(web.join("package.json")),
+                ConfigSource::File(web.join(CONFIG_FILE)),
+            ]
+        );
+
+        let merged = load_discovered(&web).unwrap().unwrap();
+        let policies = merged.table["policies"].as_table().unwrap();
+        assert_eq!(policies["enforce_corepack"].as_bool(), Some(true));
+        assert_eq!(policies["min_node_version"].as_str(), Some("20.0.0"));
+        assert_eq!(
+            merged.table["checks"]["disabled"].as_array().unwrap().len(),
+            2
+        );
+        assert_eq!(
+            merged.sources["policies.min_node_version"],
+            vec![web.join("package.json")]
+        );
+    }
+
+    #[test]
+    fn test_search_stops_without_repo_root() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(search_dirs(&nested), vec![nested.canonicalize().unwrap()]);
+
+        fs::create_dir_all(dir.path().join("a/.git")).unwrap();
+        fs::write(dir.path().join("a/env.lock"), "").unwrap();
+        assert_eq!(
+            find_nearest(&nested, "env.lock"),
+            Some(dir.path().join("a/env.lock").canonicalize().unwrap())
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! `extends` support for configuration files
+//!
+//! Each entry is either a path relative to the extending file (`./base.toml`) or a
+//! package installed in node_modules (`@acme/zenvo-config`, optionally with a
+//! subpath such as `@acme/zenvo-config/strict.toml`).
+//!
+//! Merge semantics, applied in order (each `extends` entry, then the file itself):
+//! - tables merge key by key, recursively
+//! - arrays are concatenated, skipping values already present
+//! - any other value replaces the earlier one
+//!
+//! `command` custom checks run shell commands, so only the project's own config files may
+//! declare them. Those coming from an extended file are dropped and listed in
+//! `LayeredConfig::preset_commands` instead.
+
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Config files looked up in the root of a preset package, after its package.json `zenvo` field
+const PRESET_FILES: [&str; 2] = [".env.doctor.toml", "zenvo.toml"];
+
+/// Dotted key path -> files that contributed the value
+pub type Sources = BTreeMap<String, Vec<PathBuf>>;
+
+/// A configuration table with `extends` resolved
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    pub table: toml::value::Table,
+    pub sources: Sources,
+    /// Every file that was loaded, in merge order
+    pub files: Vec<PathBuf>,
+    /// Names of `command` checks dropped from extended files
+    pub preset_commands: Vec<String>,
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+/// Record `file` as the source of every leaf value in `table`
+fn collect_sources(table: &toml::value::Table, prefix: &str, file: &Path, sources: &mut Sources) {
+    for (key, value) in table {
+        let path = join_key(prefix, key);
+        match value {
+            toml::Value::Table(inner) => collect_sources(inner, &path, file, sources),
+            _ => {
+                sources.insert(path, vec![file.to_path_buf()]);
+            }
+        }
+    }
+}
+
+/// Move the sources for `path` (and anything below it) from `from` into `to`
+fn replace_sources(path: &str, to: &mut Sources, from: &Sources) {
+    let nested = format!("{}.", path);
+    to.retain(|k, _| k != path && !k.starts_with(&nested));
+    for (k, v) in from {
+        if k == path || k.starts_with(&nested) {
+            to.insert(k.clone(), v.clone());
+        }
+    }
+}
+
+fn merge_tables(
+    base: &mut toml::value::Table,
+    overlay: toml::value::Table,
+    prefix: &str,
+    base_sources: &mut Sources,
+    overlay_sources: &Sources,
+) {
+    for (key, value) in overlay {
+        let path = join_key(prefix, &key);
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(incoming)) => {
+                merge_tables(existing, incoming, &path, base_sources, overlay_sources);
+            }
+            (Some(toml::Value::Array(existing)), toml::Value::Array(incoming)) => {
+                for item in incoming {
+                    if !existing.contains(&item) {
+                        existing.push(item);
+                    }
+                }
+                let files = base_sources.entry(path.clone()).or_default();
+                for file in overlay_sources.get(&path).into_iter().flatten() {
+                    if !files.contains(file) {
+                        files.push(file.clone());
+                    }
+                }
+            }
+            (_, value) => {
+                base.insert(key, value);
+                replace_sources(&path, base_sources, overlay_sources);
+            }
+        }
+    }
+}
+
+impl LayeredConfig {
+    fn empty() -> Self {
+        Self {
+            table: toml::value::Table::new(),
+            sources: Sources::new(),
+            files: Vec::new(),
+            preset_commands: Vec::new(),
+        }
+    }
+
+    /// Merge `overlay` on top of this config
+    pub fn merge(&mut self, overlay: LayeredConfig) {
+        merge_tables(&mut self.table, overlay.table, "", &mut self.sources, &overlay.sources);
+        self.files.extend(overlay.files);
+        for name in overlay.preset_commands {
+            if !self.preset_commands.contains(&name) {
+                self.preset_commands.push(name);
+            }
+        }
+    }
+}
+
+/// Remove `kind = "command"` entries from `checks.custom` in `table`, returning their names
+fn take_command_checks(table: &mut toml::value::Table) -> Vec<String> {
+    let Some(toml::Value::Array(custom)) = table
+        .get_mut("checks")
+        .and_then(|c| c.as_table_mut())
+        .and_then(|c| c.get_mut("custom"))
+    else {
+        return Vec::new();
+    };
+
+    let mut names = Vec::new();
+    custom.retain(|check| {
+        if check.get("kind").and_then(|k| k.as_str()) != Some("command") {
+            return true;
+        }
+        let name = check.get("name").and_then(|n| n.as_str()).unwrap_or("unnamed");
+        names.push(name.to_string());
+        false
+    });
+    names
+}
+
+/// Drop the `command` checks an extended file declares, in its base table and its profiles
+fn strip_command_checks(layer: &mut LayeredConfig) {
+    let mut names = take_command_checks(&mut layer.table);
+    if let Some(toml::Value::Table(profiles)) = layer.table.get_mut("profiles") {
+        for (_, profile) in profiles.iter_mut() {
+            if let toml::Value::Table(profile) = profile {
+                names.extend(take_command_checks(profile));
+            }
+        }
+    }
+    for name in names {
+        if !layer.preset_commands.contains(&name) {
+            layer.preset_commands.push(name);
+        }
+    }
+}
+
+/// Find `node_modules/<package>` in `dir` or any parent directory
+fn find_package_dir(dir: &Path, package: &str) -> Option<PathBuf> {
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    dir.ancestors()
+        .map(|d| d.join("node_modules").join(package))
+        .find(|p| p.is_dir())
+}
+
+/// Resolve one `extends` entry relative to the directory of the extending file
+pub fn resolve_extends(spec: &str, base_dir: &Path) -> Result<PathBuf> {
+    if spec.starts_with('.') || Path::new(spec).is_absolute() {
+        let path = base_dir.join(spec);
+        if !path.is_file() {
+            bail!("extends \"{}\": {} not found", spec, path.display());
+        }
+        return Ok(path);
+    }
+
+    // Package name (scoped names span two segments), then an optional subpath
+    let segments: Vec<&str> = spec.split('/').collect();
+    let name_len = if spec.starts_with('@') { 2 } else { 1 };
+    if segments.len() < name_len {
+        bail!("extends \"{}\": invalid package name", spec);
+    }
+    let package = segments[..name_len].join("/");
+    let subpath = segments[name_len..].join("/");
+
+    let package_dir = find_package_dir(base_dir, &package).with_context(|| {
+        format!(
+            "extends \"{}\": package {} is not installed in node_modules",
+            spec, package
+        )
+    })?;
+
+    if !subpath.is_empty() {
+        let path = package_dir.join(&subpath);
+        if !path.is_file() {
+            bail!("extends \"{}\": {} not found", spec, path.display());
+        }
+        return Ok(path);
+    }
+
+    let declared = fs::read_to_string(package_dir.join("package.json"))
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .and_then(|p| p.get("zenvo").and_then(|z| z.as_str()).map(|s| s.to_string()));
+    let found = declared
+        .iter()
+        .map(|s| s.as_str())
+        .chain(PRESET_FILES)
+        .map(|file| package_dir.join(file))
+        .find(|p| p.is_file());
+    found.with_context(|| {
+        format!(
+            "extends \"{}\": no config found in {} (expected {} or a \"zenvo\" field in package.json)",
+            spec,
+            package_dir.display(),
+            PRESET_FILES.join(" or ")
+        )
+    })
+}
+
+fn load_recursive(path: &Path, stack: &mut Vec<PathBuf>) -> Result<LayeredConfig> {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if stack.contains(&canonical) {
+        bail!("Circular extends: {} is already being loaded", path.display());
+    }
+
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    let table: toml::value::Table = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+
+    layer_table(table, path, canonical, stack)
+}
+
+/// Resolve the `extends` of an already parsed table that was read from `path`
+fn layer_table(
+    mut table: toml::value::Table,
+    path: &Path,
+    canonical: PathBuf,
+    stack: &mut Vec<PathBuf>,
+) -> Result<LayeredConfig> {
+    let extends: Vec<String> = match table.remove("extends") {
+        None => Vec::new(),
+        Some(toml::Value::String(s)) => vec![s],
+        Some(toml::Value::Array(items)) => items
+            .into_iter()
+            .map(|v| match v {
+                toml::Value::String(s) => Ok(s),
+                other => bail!("extends entries must be strings, found {}", other.type_str()),
+            })
+            .collect::<Result<_>>()
+            .with_context(|| format!("Invalid extends in {}", path.display()))?,
+        Some(other) => bail!(
+            "extends in {} must be a string or an array, found {}",
+            path.display(),
+            other.type_str()
+        ),
+    };
+
+    stack.push(canonical);
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let mut layered = LayeredConfig::empty();
+    for spec in &extends {
+        let resolved = resolve_extends(spec, base_dir)
+            .with_context(|| format!("Failed to resolve extends in {}", path.display()))?;
+        let mut layer = load_recursive(&resolved, stack)?;
+        strip_command_checks(&mut layer);
+        layered.merge(layer);
+    }
+    stack.pop();
+
+    let mut own_sources = Sources::new();
+    collect_sources(&table, "", path, &mut own_sources);
+    layered.merge(LayeredConfig {
+        table,
+        sources: own_sources,
+        files: vec![path.to_path_buf()],
+        preset_commands: Vec::new(),
+    });
+
+    Ok(layered)
+}
+
+/// Load a config file with its `extends` chain merged in
+pub fn load_layered(path: &Path) -> Result<LayeredConfig> {
+    load_recursive(path, &mut Vec::new())
+}
+
+/// Layer a table that didn't come from a TOML file (the package.json `zenvo` key)
+pub fn load_layered_table(table: toml::value::Table, path: &Path) -> Result<LayeredConfig> {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    layer_table(table, path, canonical, &mut Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_extends_merge_semantics() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("node_modules/@acme/zenvo-config")).unwrap();
+        fs::write(
+            root.join("node_modules/@acme/zenvo-config/package.json"),
+            r#"{ "name": "@acme/zenvo-config", "zenvo": "preset.toml" }"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("node_modules/@acme/zenvo-config/preset.toml"),
+            "[policies]\nenforce_corepack = true\nallowed_package_managers = [\"pnpm\"]\n\n[checks]\ndisabled = [\"A\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("base.toml"),
+            "[policies]\nenforce_corepack = false\n\n[checks]\ndisabled = [\"B\", \"A\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join(".env.doctor.toml"),
+            "extends = [\"@acme/zenvo-config\", \"./base.toml\"]\n\n[checks.severity_overrides]\nC = \"info\"\n",
+        )
+        .unwrap();
+
+        let layered = load_layered(&root.join(".env.doctor.toml")).unwrap();
+        let policies = layered.table["policies"].as_table().unwrap();
+        assert_eq!(policies["enforce_corepack"].as_bool(), Some(false));
+        assert_eq!(policies["allowed_package_managers"].as_array().unwrap().len(), 1);
+
+        let disabled: Vec<&str> = layered.table["checks"]["disabled"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|v| v.as_str())
+            .collect();
+        assert_eq!(disabled, vec!["A", "B"]);
+        assert!(!layered.table.contains_key("extends"));
+
+        assert_eq!(layered.sources["policies.enforce_corepack"], vec![root.join("base.toml")]);
+        assert_eq!(layered.sources["checks.disabled"].len(), 2);
+        assert_eq!(
+            layered.sources["checks.severity_overrides.C"],
+            vec![root.join(".env.doctor.toml")]
+        );
+    }
+
+    #[test]
+    fn test_extends_cycle_and_missing() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.toml"), "extends = \"./b.toml\"\n").unwrap();
+        fs::write(dir.path().join("b.toml"), "extends = [\"./a.toml\"]\n").unwrap();
+        let err = load_layered(&dir.path().join("a.toml")).unwrap_err();
+        assert!(format!("{:#}", err).contains("Circular extends"));
+
+        fs::write(dir.path().join("c.toml"), "extends = [\"missing-preset\"]\n").unwrap();
+        let err = load_layered(&dir.path().join("c.toml")).unwrap_err();
+        assert!(format!("{:#}", err).contains("not installed"));
+    }
+
+    #[test]
+    fn test_extends_drops_preset_command_checks() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("preset.toml"),
+            "[[checks.custom]]\nname = \"Preset cmd\"\nkind = \"command\"\ncommand = \"true\"\n\n\
+             [[checks.custom]]\nname = \"Has .nvmrc\"\nkind = \"file_exists\"\npath = \".nvmrc\"\n\n\
+             [[profiles.ci.checks.custom]]\nname = \"CI cmd\"\nkind = \"command\"\ncommand = \"true\"\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join(".env.doctor.toml"),
+            "extends = \"./preset.toml\"\n\n[[checks.custom]]\nname = \"Own cmd\"\nkind = \"command\"\ncommand = \"true\"\n",
+        )
+        .unwrap();
+
+        let layered = load_layered(&root.join(".env.doctor.toml")).unwrap();
+        let names: Vec<&str> = layered.table["checks"]["custom"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.get("name").and_then(|n| n.as_str()))
+            .collect();
+        assert_eq!(names, vec!["Has .nvmrc", "Own cmd"]);
+        assert_eq!(layered.preset_commands, vec!["Preset cmd", "CI cmd"]);
+    }
+}
@@ -1,6 +1,11 @@
 //! Configuration module for Zenvo
 //! Handles loading and parsing of `.env.doctor.toml` configuration files.
 
+pub mod discovery;
+pub mod extends;
+pub mod profiles;
+pub mod schema;
+
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -15,6 +20,10 @@ pub const CONFIG_FILE: &str = ".env.doctor.toml";
 /// Main configuration structure for Zenvo
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ZenvoConfig {
+    /// Config files or preset packages this one builds on (resolved when loading)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>,
+
     /// Policy settings for environment management
     #[serde(default)]
     pub policies: Policies,
@@ -26,6 +35,14 @@ pub struct ZenvoConfig {
     /// Framework-specific settings
     #[serde(default)]
     pub frameworks: FrameworksConfig,
+
+    /// Repair execution settings
+    #[serde(default)]
+    pub repair: RepairConfig,
+
+    /// Profile applied when loading (`--profile`, ZENVO_PROFILE or `ci` on CI)
+    #[serde(skip)]
+    pub active_profile: Option<String>,
 }
 
 /// Policy settings that control Zenvo behavior
@@ -58,6 +75,10 @@ pub struct Policies {
     /// Maximum Node.js version allowed
     #[serde(default)]
     pub max_node_version: Option<String>,
+
+    /// Packages that must be declared and locked at a single version across workspace packages
+    #[serde(default)]
+    pub single_version_packages: Vec<String>,
 }
 
 impl Default for Policies {
@@ -70,6 +91,7 @@ impl Default for Policies {
             allowed_package_managers: Vec::new(),
             min_node_version: None,
             max_node_version: None,
+            single_version_packages: Vec::new(),
         }
     }
 }
@@ -88,6 +110,128 @@ pub struct ChecksConfig {
     /// Custom check timeout in seconds
     #[serde(default)]
     pub timeout_seconds: Option<u64>,
+
+    /// Repo-specific checks declared as `[[checks.custom]]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom: Vec<CustomCheck>,
+
+    /// Packages never reported as unused, besides `@types/*`; `*` matches anything
+    /// (`eslint-plugin-*`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unused_ignore: Vec<String>,
+
+    /// Where the phantom and unused dependency checks look for imports
+    #[serde(default)]
+    pub imports: ImportsConfig,
+
+    /// `command` checks declared by `extends` presets, reported but never run
+    #[serde(skip)]
+    pub preset_commands: Vec<String>,
+
+    /// Skip every `command` check (the MCP server, unless a tool call sets `run_commands`)
+    #[serde(skip)]
+    pub commands_disabled: bool,
+}
+
+/// Where and how to look for imports, as `[checks.imports]`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportsConfig {
+    /// Directories or files to scan, relative to the package (default: the whole package)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<String>,
+
+    /// Paths never scanned; `*` matches within a path segment, `**` across them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+
+    /// Import prefixes resolved by the bundler rather than node_modules (`~/`, `@/*`),
+    /// on top of tsconfig `paths`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+/// A repo-specific check declared in the config file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomCheck {
+    /// Name shown in results and matched by `disabled` / `severity_overrides`
+    pub name: String,
+
+    /// What to check
+    #[serde(flatten)]
+    pub kind: CustomCheckKind,
+
+    /// Category the result is reported under (and filtered by with `--category`)
+    #[serde(default = "default_custom_category")]
+    pub category: CustomCheckCategory,
+
+    /// Severity when the check fails
+    #[serde(default = "default_custom_severity")]
+    pub severity: SeverityOverride,
+
+    /// Suggested fix shown when the check fails
+    #[serde(default)]
+    pub fix: Option<String>,
+}
+
+/// The kinds of custom checks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CustomCheckKind {
+    /// A file or directory must exist
+    FileExists { path: String },
+
+    /// A file must contain a match for `pattern` (a regex), or must not when `negate` is set
+    FileContains {
+        path: String,
+        pattern: String,
+        #[serde(default)]
+        negate: bool,
+    },
+
+    /// The value at a dotted path in a JSON file (`engines.node`, `workspaces[0]`) must equal `value`
+    JsonPathEquals {
+        path: String,
+        json_path: String,
+        value: toml::Value,
+    },
+
+    /// A shell command must exit 0 within the timeout
+    Command {
+        command: String,
+        #[serde(default)]
+        timeout_seconds: Option<u64>,
+    },
+}
+
+/// Categories a custom check can report under
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomCheckCategory {
+    Toolchain,
+    Lockfile,
+    Deps,
+    Frameworks,
+    Project,
+}
+
+impl CustomCheckCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CustomCheckCategory::Toolchain => "toolchain",
+            CustomCheckCategory::Lockfile => "lockfile",
+            CustomCheckCategory::Deps => "deps",
+            CustomCheckCategory::Frameworks => "frameworks",
+            CustomCheckCategory::Project => "project",
+        }
+    }
+}
+
+fn default_custom_category() -> CustomCheckCategory {
+    CustomCheckCategory::Project
+}
+
+fn default_custom_severity() -> SeverityOverride {
+    SeverityOverride::Error
 }
 
 /// Severity override configuration
@@ -111,6 +255,72 @@ impl From<SeverityOverride> for CheckSeverity {
     }
 }
 
+/// Settings for `zenvo repair --apply`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepairConfig {
+    /// Seconds a repair action may run before it's killed (default: 600)
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+
+    /// Timeouts for actions running a given program, e.g. `npm = 1800`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub timeouts: HashMap<String, u64>,
+
+    /// Files the Node version repair writes the locked version into
+    ///
+    /// Unset means the pins the project already has, or `.nvmrc` if it has none; an
+    /// empty list turns pinning off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_pin_files: Option<Vec<NodePinFile>>,
+}
+
+/// A place the project's Node version is pinned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodePinFile {
+    #[serde(rename = ".nvmrc")]
+    Nvmrc,
+    #[serde(rename = ".node-version")]
+    NodeVersion,
+    /// `volta.node` in package.json
+    #[serde(rename = "volta.node")]
+    Volta,
+    /// `engines.node` in package.json
+    #[serde(rename = "engines.node")]
+    Engines,
+    /// The `nodejs` line of asdf/mise's `.tool-versions`
+    #[serde(rename = ".tool-versions")]
+    ToolVersions,
+}
+
+impl NodePinFile {
+    pub const ALL: [NodePinFile; 5] = [
+        NodePinFile::Nvmrc,
+        NodePinFile::NodeVersion,
+        NodePinFile::Volta,
+        NodePinFile::Engines,
+        NodePinFile::ToolVersions,
+    ];
+
+    /// File the pin lives in, relative to the project root
+    pub fn file(self) -> &'static str {
+        match self {
+            NodePinFile::Nvmrc => ".nvmrc",
+            NodePinFile::NodeVersion => ".node-version",
+            NodePinFile::Volta | NodePinFile::Engines => "package.json",
+            NodePinFile::ToolVersions => ".tool-versions",
+        }
+    }
+
+    /// Name used in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            NodePinFile::Volta => "volta.node",
+            NodePinFile::Engines => "engines.node",
+            other => other.file(),
+        }
+    }
+}
+
 /// Framework-specific configuration
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FrameworksConfig {
@@ -164,33 +374,44 @@ fn default_true() -> bool {
 }
 
 impl ZenvoConfig {
-    /// Load configuration from the default location
+    /// Load configuration discovered from the current directory up to the repo root
     pub fn load() -> Result<Self> {
-        Self::load_from(Path::new(CONFIG_FILE))
+        Ok(Self::load_if_exists()?.unwrap_or_default())
     }
 
-    /// Load configuration from a specific path
-    pub fn load_from(path: &Path) -> Result<Self> {
-        if !path.exists() {
-            return Ok(Self::default());
-        }
-
-        let content = fs::read_to_string(path)
-            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
-
-        let config: ZenvoConfig = toml::from_str(&content)
-            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
-
-        Ok(config)
+    /// Build a configuration from a merged TOML table
+    pub fn from_table(table: toml::value::Table) -> Result<Self> {
+        Ok(toml::Value::Table(table).try_into()?)
     }
 
-    /// Load configuration if file exists, otherwise return None
+    /// Load discovered configuration if there is any, otherwise return None
     pub fn load_if_exists() -> Result<Option<Self>> {
-        let path = Path::new(CONFIG_FILE);
-        if !path.exists() {
+        Self::load_with_profile(None)
+    }
+
+    /// Like `load_if_exists`, with `profile` taking precedence over the selected profile
+    pub fn load_with_profile(profile: Option<&str>) -> Result<Option<Self>> {
+        let Some(mut layered) = discovery::load_discovered(Path::new("."))? else {
             return Ok(None);
-        }
-        Ok(Some(Self::load()?))
+        };
+
+        let selection = match profile {
+            Some(name) => Some(profiles::ProfileSelection::explicit(name)),
+            None => profiles::selected_profile(),
+        };
+        let active_profile = profiles::apply_profile(&mut layered, selection.as_ref())?;
+
+        let sources: Vec<String> = layered
+            .files
+            .iter()
+            .map(|f| discovery::display_path(f))
+            .collect();
+        let mut config = Self::from_table(layered.table).with_context(|| {
+            format!("Failed to parse configuration from {}", sources.join(", "))
+        })?;
+        config.active_profile = active_profile;
+        config.checks.preset_commands = layered.preset_commands;
+        Ok(Some(config))
     }
 
     /// Save configuration to file
@@ -247,6 +468,17 @@ impl ZenvoConfig {
             }
         }
 
+        for check in &self.checks.custom {
+            if check.name.trim().is_empty() {
+                anyhow::bail!("Custom checks need a name");
+            }
+            if let CustomCheckKind::FileContains { pattern, .. } = &check.kind {
+                regex::Regex::new(pattern).with_context(|| {
+                    format!("Invalid pattern in custom check \"{}\"", check.name)
+                })?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -282,4 +514,36 @@ disabled = ["deprecated_packages"]
         assert!(config.policies.enforce_corepack);
         assert!(config.is_check_disabled("deprecated_packages"));
     }
+
+    #[test]
+    fn test_parse_custom_checks() {
+        let toml_content = r#"
+[[checks.custom]]
+name = "Has .nvmrc"
+kind = "file_exists"
+path = ".nvmrc"
+
+[[checks.custom]]
+name = "Lint passes"
+kind = "command"
+command = "npm run lint"
+timeout_seconds = 120
+category = "deps"
+severity = "warning"
+fix = "Run `npm run lint -- --fix`"
+"#;
+
+        let config: ZenvoConfig = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.checks.custom.len(), 2);
+        assert_eq!(config.checks.custom[0].category, CustomCheckCategory::Project);
+        assert!(matches!(
+            config.checks.custom[1].kind,
+            CustomCheckKind::Command { timeout_seconds: Some(120), .. }
+        ));
+        assert!(matches!(config.checks.custom[1].severity, SeverityOverride::Warning));
+
+        let invalid: ZenvoConfig =
+            toml::from_str("[[checks.custom]]\nname = \"x\"\nkind = \"file_contains\"\npath = \"a\"\npattern = \"(\"\n").unwrap();
+        assert!(invalid.validate().is_err());
+    }
 }
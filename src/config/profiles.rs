@@ -0,0 +1,232 @@
+//! Named configuration profiles
+//!
+//! A `[profiles.<name>]` section holds `policies`, `checks`, `repair` and `frameworks`
+//! settings that replace the base values when the profile is active. The profile is
+//! chosen with `--profile`, then the `ZENVO_PROFILE` environment variable, and falls
+//! back to `ci` when the `CI` environment variable is set.
+
+use anyhow::{bail, Result};
+use std::sync::OnceLock;
+
+use super::extends::LayeredConfig;
+
+/// Environment variable that selects a profile
+pub const PROFILE_ENV: &str = "ZENVO_PROFILE";
+
+/// Profile used automatically on CI
+pub const CI_PROFILE: &str = "ci";
+
+/// Profile passed with `--profile`
+static PROFILE_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Use `name` instead of `ZENVO_PROFILE` or CI detection
+pub fn set_profile_override(name: String) {
+    let _ = PROFILE_OVERRIDE.set(name);
+}
+
+/// The profile to apply and whether it was asked for explicitly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSelection {
+    pub name: String,
+    /// Explicit selections must exist; the automatic `ci` profile is optional
+    pub explicit: bool,
+}
+
+impl ProfileSelection {
+    pub fn explicit(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            explicit: true,
+        }
+    }
+}
+
+/// Whether we're running on a CI provider (`CI=true`, `CI=1`, ...)
+fn is_ci() -> bool {
+    std::env::var("CI").is_ok_and(|v| {
+        let v = v.trim();
+        !v.is_empty() && v != "0" && !v.eq_ignore_ascii_case("false")
+    })
+}
+
+/// Resolve `--profile`, `ZENVO_PROFILE` and `CI` into the profile to apply
+pub fn selected_profile() -> Option<ProfileSelection> {
+    if let Some(name) = PROFILE_OVERRIDE.get() {
+        return Some(ProfileSelection::explicit(name));
+    }
+    if let Some(name) = std::env::var(PROFILE_ENV)
+        .ok()
+        .filter(|n| !n.trim().is_empty())
+    {
+        return Some(ProfileSelection::explicit(name.trim()));
+    }
+    is_ci().then(|| ProfileSelection {
+        name: CI_PROFILE.to_string(),
+        explicit: false,
+    })
+}
+
+/// Replace values in `base` with those in `overlay`, merging nested tables
+fn override_table(base: &mut toml::value::Table, overlay: toml::value::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(incoming)) => {
+                override_table(existing, incoming);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Strip `[profiles]` from a merged config and apply the selected profile
+///
+/// Returns the name of the profile that was applied, if any.
+pub fn apply_profile(
+    layered: &mut LayeredConfig,
+    selection: Option<&ProfileSelection>,
+) -> Result<Option<String>> {
+    let mut profiles = match layered.table.remove("profiles") {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(other) => bail!("profiles must be a table, found {}", other.type_str()),
+        None => toml::value::Table::new(),
+    };
+
+    let Some(selection) = selection else {
+        layered
+            .sources
+            .retain(|key, _| !key.starts_with("profiles."));
+        return Ok(None);
+    };
+
+    let profile = match profiles.remove(&selection.name) {
+        Some(toml::Value::Table(profile)) => profile,
+        Some(other) => bail!(
+            "profiles.{} must be a table, found {}",
+            selection.name,
+            other.type_str()
+        ),
+        None if selection.explicit => {
+            let available: Vec<&str> = profiles.keys().map(|k| k.as_str()).collect();
+            bail!(
+                "Profile `{}` is not defined (available: {})",
+                selection.name,
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            );
+        }
+        None => {
+            layered
+                .sources
+                .retain(|key, _| !key.starts_with("profiles."));
+            return Ok(None);
+        }
+    };
+
+    override_table(&mut layered.table, profile);
+
+    // Values from the profile now live at their plain key path
+    let prefix = format!("profiles.{}.", selection.name);
+    let moved: Vec<(String, Vec<std::path::PathBuf>)> = layered
+        .sources
+        .iter()
+        .filter_map(|(key, files)| {
+            key.strip_prefix(&prefix)
+                .map(|k| (k.to_string(), files.clone()))
+        })
+        .collect();
+    layered
+        .sources
+        .retain(|key, _| !key.starts_with("profiles."));
+    for (key, files) in moved {
+        let nested = format!("{}.", key);
+        layered.sources.retain(|k, _| !k.starts_with(&nested));
+        layered.sources.insert(key, files);
+    }
+
+    Ok(Some(selection.name.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::extends::load_layered;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_apply_profile_overrides_base() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(".env.doctor.toml");
+        fs::write(
+            &path,
+            r#"
+[policies]
+allow_node_upgrade_minor = true
+require_lockfile_frozen = false
+
+[checks]
+disabled = ["Node version", "Engines field"]
+
+[profiles.ci.policies]
+allow_node_upgrade_minor = false
+require_lockfile_frozen = true
+
+[profiles.ci.checks]
+disabled = ["Engines field"]
+
+[profiles.ci.checks.severity_overrides]
+"Node version" = "error"
+"#,
+        )
+        .unwrap();
+
+        let mut layered = load_layered(&path).unwrap();
+        let applied = apply_profile(&mut layered, Some(&ProfileSelection::explicit("ci"))).unwrap();
+        assert_eq!(applied.as_deref(), Some("ci"));
+        assert!(!layered.table.contains_key("profiles"));
+
+        let policies = layered.table["policies"].as_table().unwrap();
+        assert_eq!(policies["allow_node_upgrade_minor"].as_bool(), Some(false));
+        assert_eq!(policies["require_lockfile_frozen"].as_bool(), Some(true));
+        assert_eq!(
+            layered.table["checks"]["disabled"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            layered.table["checks"]["severity_overrides"]["Node version"].as_str(),
+            Some("error")
+        );
+        assert!(layered
+            .sources
+            .contains_key("policies.allow_node_upgrade_minor"));
+        assert!(!layered.sources.keys().any(|k| k.starts_with("profiles.")));
+
+        // Unknown profiles are an error only when asked for explicitly
+        let mut layered = load_layered(&path).unwrap();
+        let err =
+            apply_profile(&mut layered, Some(&ProfileSelection::explicit("release"))).unwrap_err();
+        assert!(err.to_string().contains("available: ci"));
+
+        let mut layered = load_layered(&path).unwrap();
+        let auto = ProfileSelection {
+            name: "nightly".to_string(),
+            explicit: false,
+        };
+        assert_eq!(apply_profile(&mut layered, Some(&auto)).unwrap(), None);
+        assert_eq!(
+            layered.table["checks"]["disabled"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,528 @@
+//! JSON Schema for `.env.doctor.toml` and strict validation against it
+//!
+//! The schema is the single description of the file: `zenvo config schema` prints it
+//! for editors, and `zenvo config validate` walks the TOML document against it to
+//! report unknown keys, wrong types and invalid versions with their line and column.
+
+use serde_json::{json, Value};
+use std::ops::Range;
+use toml_edit::{ImDocument, Item, TableLike};
+
+use crate::checks::is_known_check;
+
+/// A problem found in a configuration file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiagnostic {
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column
+    pub column: usize,
+    /// Dotted key path (`policies.min_node_version`)
+    pub key: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// JSON Schema (draft-07) describing the configuration file
+pub fn json_schema() -> Value {
+    let boolean = |description: &str| json!({ "type": "boolean", "description": description });
+    let semver = |description: &str| {
+        json!({
+            "type": "string",
+            "format": "semver",
+            "pattern": "^v?\\d+\\.\\d+\\.\\d+",
+            "description": description
+        })
+    };
+    let strings = |description: &str| {
+        json!({ "type": "array", "items": { "type": "string" }, "description": description })
+    };
+
+    let mut schema = json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Zenvo configuration (.env.doctor.toml)",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "extends": {
+                "type": ["string", "array"],
+                "items": { "type": "string" },
+                "description": "Config files (./base.toml) or preset packages (@acme/zenvo-config) to build on"
+            },
+            "policies": {
+                "type": "object",
+                "description": "Policy settings that control Zenvo behavior",
+                "additionalProperties": false,
+                "properties": {
+                    "allow_node_upgrade_minor": boolean("Allow minor version upgrades of Node.js"),
+                    "allow_node_upgrade_major": boolean("Allow major version upgrades of Node.js"),
+                    "require_lockfile_frozen": boolean("Require lockfile to be frozen (no modifications allowed)"),
+                    "enforce_corepack": boolean("Enforce corepack usage"),
+                    "allowed_package_managers": {
+                        "type": "array",
+                        "items": { "type": "string", "enum": ["npm", "pnpm", "yarn", "bun"] },
+                        "description": "Allowed package managers (empty = all allowed)"
+                    },
+                    "min_node_version": semver("Minimum Node.js version required"),
+                    "max_node_version": semver("Maximum Node.js version allowed"),
+                    "single_version_packages": strings(
+                        "Packages that must be declared and locked at a single version across workspace packages"
+                    )
+                }
+            },
+            "checks": {
+                "type": "object",
+                "description": "Check configuration (disabled checks, severity overrides)",
+                "additionalProperties": false,
+                "properties": {
+                    "disabled": {
+                        "type": "array",
+                        "items": { "type": "string", "format": "check-name" },
+                        "description": "List of check names to disable"
+                    },
+                    "severity_overrides": {
+                        "type": "object",
+                        "description": "Override severity for specific checks",
+                        "propertyNames": { "format": "check-name" },
+                        "additionalProperties": {
+                            "type": "string",
+                            "enum": ["pass", "info", "warning", "error"]
+                        }
+                    },
+                    "timeout_seconds": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Custom check timeout in seconds"
+                    },
+                    "unused_ignore": strings(
+                        "Packages never reported as unused, besides @types/* (`*` matches anything)"
+                    ),
+                    "custom": {
+                        "type": "array",
+                        "description": "Repo-specific checks",
+                        "items": {
+                            "type": "object",
+                            "additionalProperties": false,
+                            "required": ["name", "kind"],
+                            "properties": {
+                                "name": { "type": "string", "description": "Name shown in results" },
+                                "kind": {
+                                    "type": "string",
+                                    "enum": ["file_exists", "file_contains", "json_path_equals", "command"]
+                                },
+                                "category": {
+                                    "type": "string",
+                                    "enum": ["toolchain", "lockfile", "deps", "frameworks", "project"],
+                                    "description": "Category the result is reported under (default: project)"
+                                },
+                                "severity": {
+                                    "type": "string",
+                                    "enum": ["info", "warning", "error"],
+                                    "description": "Severity when the check fails (default: error)"
+                                },
+                                "fix": { "type": "string", "description": "Suggested fix shown when the check fails" },
+                                "path": { "type": "string", "description": "File to check (file_exists, file_contains, json_path_equals)" },
+                                "pattern": { "type": "string", "description": "Regex to look for (file_contains)" },
+                                "negate": boolean("Fail when the pattern is found instead (file_contains)"),
+                                "json_path": { "type": "string", "description": "Dotted path such as engines.node (json_path_equals)" },
+                                "value": { "description": "Expected value (json_path_equals)" },
+                                "command": { "type": "string", "description": "Shell command that must exit 0 (command)" },
+                                "timeout_seconds": {
+                                    "type": "integer",
+                                    "minimum": 1,
+                                    "description": "Command timeout in seconds (default: checks.timeout_seconds or 30)"
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "repair": {
+                "type": "object",
+                "description": "Repair execution settings",
+                "additionalProperties": false,
+                "properties": {
+                    "timeout_seconds": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Seconds a repair action may run before it's killed (default: 600)"
+                    },
+                    "timeouts": {
+                        "type": "object",
+                        "description": "Timeouts for actions running a given program, e.g. npm = 1800",
+                        "additionalProperties": { "type": "integer", "minimum": 1 }
+                    },
+                    "node_pin_files": {
+                        "type": "array",
+                        "items": {
+                            "type": "string",
+                            "enum": [".nvmrc", ".node-version", "volta.node", "engines.node", ".tool-versions"]
+                        },
+                        "description": "Files the Node version repair writes the locked version into (default: the existing pins, or .nvmrc)"
+                    }
+                }
+            },
+            "frameworks": {
+                "type": "object",
+                "description": "Framework-specific settings",
+                "additionalProperties": false,
+                "properties": {
+                    "nextjs": {
+                        "type": "object",
+                        "additionalProperties": false,
+                        "properties": {
+                            "required_version": { "type": "string", "description": "Require specific Next.js version" },
+                            "check_cache_integrity": boolean("Check .next cache integrity")
+                        }
+                    },
+                    "react": {
+                        "type": "object",
+                        "additionalProperties": false,
+                        "properties": {
+                            "enforce_version_match": boolean("Enforce React/ReactDOM version match")
+                        }
+                    },
+                    "typescript": {
+                        "type": "object",
+                        "additionalProperties": false,
+                        "properties": {
+                            "require_tsconfig": boolean("Require tsconfig.json"),
+                            "enforce_strict": boolean("Check for strict mode")
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    // Nested too deep for a single json! invocation
+    schema["properties"]["checks"]["properties"]["imports"] = json!({
+        "type": "object",
+        "description": "Where the phantom and unused dependency checks look for imports",
+        "additionalProperties": false,
+        "properties": {
+            "roots": strings("Directories or files to scan, relative to the package (default: the whole package)"),
+            "ignore": strings("Paths never scanned (`*` within a segment, `**` across segments)"),
+            "aliases": strings("Import prefixes resolved by the bundler, on top of tsconfig paths (`~/`, `@/*`)")
+        }
+    });
+
+    // A profile overrides any of the sections above
+    let sections: serde_json::Map<String, Value> = ["policies", "checks", "repair", "frameworks"]
+        .iter()
+        .map(|key| (key.to_string(), schema["properties"][*key].clone()))
+        .collect();
+    schema["properties"]["profiles"] = json!({
+        "type": "object",
+        "description": "Named profiles (selected with --profile, ZENVO_PROFILE, or `ci` when CI is set)",
+        "additionalProperties": {
+            "type": "object",
+            "additionalProperties": false,
+            "properties": sections
+        }
+    });
+    schema
+}
+
+/// Levenshtein distance, for "did you mean" suggestions
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push((prev[j] + cost).min(prev[j + 1] + 1).min(current[j] + 1));
+        }
+        prev = current;
+    }
+    prev[b.len()]
+}
+
+fn suggest<'a>(key: &str, candidates: impl Iterator<Item = &'a String>) -> Option<&'a str> {
+    candidates
+        .map(|c| (edit_distance(key, c), c))
+        .filter(|(d, _)| *d <= (key.len() / 2).max(1))
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c.as_str())
+}
+
+fn type_name(item: &Item) -> &'static str {
+    match item {
+        Item::None => "nothing",
+        Item::Table(_) | Item::Value(toml_edit::Value::InlineTable(_)) => "table",
+        Item::ArrayOfTables(_) | Item::Value(toml_edit::Value::Array(_)) => "array",
+        Item::Value(toml_edit::Value::String(_)) => "string",
+        Item::Value(toml_edit::Value::Integer(_)) => "integer",
+        Item::Value(toml_edit::Value::Float(_)) => "float",
+        Item::Value(toml_edit::Value::Boolean(_)) => "boolean",
+        Item::Value(toml_edit::Value::Datetime(_)) => "datetime",
+    }
+}
+
+fn schema_types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(|t| t.as_str()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+struct Validator<'a> {
+    source: &'a str,
+    diagnostics: Vec<ConfigDiagnostic>,
+    /// Names of `[[checks.custom]]` entries, which may be disabled or overridden too
+    custom_checks: Vec<String>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, span: Option<Range<usize>>, key: &str, message: String) {
+        let offset = span.map(|s| s.start).unwrap_or(0).min(self.source.len());
+        let before = &self.source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+        self.diagnostics.push(ConfigDiagnostic {
+            line,
+            column,
+            key: key.to_string(),
+            message,
+        });
+    }
+
+    fn check_format(&mut self, schema: &Value, text: &str, span: Option<Range<usize>>, key: &str) {
+        match schema.get("format").and_then(|f| f.as_str()) {
+            Some("semver") if semver::Version::parse(text.trim_start_matches('v')).is_err() => {
+                self.report(
+                    span,
+                    key,
+                    format!("`{}` is not a valid version for {} (expected e.g. 20.11.0)", text, key),
+                );
+            }
+            Some("check-name")
+                if !is_known_check(text)
+                    && !self.custom_checks.iter().any(|c| c.eq_ignore_ascii_case(text)) =>
+            {
+                self.report(span, key, format!("`{}` in {} is not a known check name", text, key));
+            }
+            _ => {}
+        }
+    }
+
+    fn check_table(&mut self, table: &dyn TableLike, schema: &Value, path: &str) {
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+        let additional = schema.get("additionalProperties");
+        let names = schema.get("propertyNames");
+
+        for (key, item) in table.iter() {
+            let key_path = if path.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            let key_span = table.get_key_value(key).and_then(|(k, _)| k.span());
+
+            if let Some(names) = names {
+                self.check_format(names, key, key_span.clone(), path);
+            }
+
+            if let Some(child) = properties.and_then(|p| p.get(key)) {
+                self.check_item(item, child, &key_path, key_span);
+            } else if let Some(Value::Object(_)) = additional {
+                self.check_item(item, additional.unwrap_or(&Value::Null), &key_path, key_span);
+            } else {
+                let hint = properties
+                    .and_then(|p| suggest(key, p.keys()))
+                    .map(|s| format!(" (did you mean `{}`?)", s))
+                    .unwrap_or_default();
+                let location = if path.is_empty() {
+                    "at the top level".to_string()
+                } else {
+                    format!("in [{}]", path)
+                };
+                self.report(key_span, &key_path, format!("unknown key `{}` {}{}", key, location, hint));
+            }
+        }
+    }
+
+    fn check_item(&mut self, item: &Item, schema: &Value, path: &str, key_span: Option<Range<usize>>) {
+        let span = item.span().or(key_span);
+        let types = schema_types(schema);
+        let found = type_name(item);
+        let matches_type = |t: &str| match t {
+            "object" => found == "table",
+            "number" => found == "integer" || found == "float",
+            other => other == found,
+        };
+
+        if !types.is_empty() && !types.iter().any(|t| matches_type(t)) {
+            let expected: Vec<&str> = types
+                .iter()
+                .map(|t| if *t == "object" { "table" } else { t })
+                .collect();
+            self.report(
+                span,
+                path,
+                format!("{} must be {}, found {}", path, expected.join(" or "), found),
+            );
+            return;
+        }
+
+        match item {
+            Item::Table(table) => self.check_table(table, schema, path),
+            Item::Value(toml_edit::Value::InlineTable(table)) => self.check_table(table, schema, path),
+            Item::ArrayOfTables(tables) => {
+                if let Some(items) = schema.get("items") {
+                    for table in tables.iter() {
+                        self.check_table(table, items, path);
+                    }
+                }
+            }
+            Item::Value(toml_edit::Value::Array(array)) => {
+                if let Some(items) = schema.get("items") {
+                    for value in array.iter() {
+                        let span = value.span();
+                        self.check_item(&Item::Value(value.clone()), items, path, span);
+                    }
+                }
+            }
+            Item::Value(toml_edit::Value::String(s)) => {
+                let text = s.value();
+                if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+                    if !allowed.iter().any(|a| a.as_str() == Some(text)) {
+                        let options: Vec<&str> = allowed.iter().filter_map(|a| a.as_str()).collect();
+                        self.report(
+                            span.clone(),
+                            path,
+                            format!("`{}` is not valid for {} (expected one of: {})", text, path, options.join(", ")),
+                        );
+                    }
+                }
+                self.check_format(schema, text, span, path);
+            }
+            Item::Value(toml_edit::Value::Integer(n)) => {
+                if let Some(min) = schema.get("minimum").and_then(|m| m.as_i64()) {
+                    if *n.value() < min {
+                        self.report(span, path, format!("{} must be at least {}", path, min));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Names of the `[[checks.custom]]` entries in a config table and its profiles
+pub fn custom_check_names(table: &toml::value::Table) -> Vec<String> {
+    let names_in = |table: &toml::value::Table| -> Vec<String> {
+        table
+            .get("checks")
+            .and_then(|c| c.get("custom"))
+            .and_then(|c| c.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|check| check.get("name").and_then(|n| n.as_str()).map(String::from))
+            .collect()
+    };
+
+    let mut names = names_in(table);
+    if let Some(profiles) = table.get("profiles").and_then(|p| p.as_table()) {
+        for profile in profiles.values().filter_map(|p| p.as_table()) {
+            names.extend(names_in(profile));
+        }
+    }
+    names
+}
+
+/// Validate configuration file contents against the schema
+///
+/// Check names in `checks.disabled` and `checks.severity_overrides` may also refer to
+/// `custom_checks`, the custom checks of the files this one is merged with.
+pub fn validate_document(source: &str, custom_checks: &[String]) -> Vec<ConfigDiagnostic> {
+    let mut validator = Validator {
+        source,
+        diagnostics: Vec::new(),
+        custom_checks: custom_checks.to_vec(),
+    };
+
+    match ImDocument::parse(source) {
+        Ok(doc) => {
+            if let Ok(table) = toml::from_str::<toml::value::Table>(source) {
+                validator.custom_checks.extend(custom_check_names(&table));
+            }
+            validator.check_table(doc.as_table(), &json_schema(), "")
+        }
+        Err(e) => validator.report(e.span(), "", format!("invalid TOML: {}", e.message().trim())),
+    }
+
+    validator.diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_document_reports_positions() {
+        let source = "[policy]\nenforce_corepack = true\n\n[policies]\nalowed_package_managers = [\"npm\"]\nenforce_corepack = \"yes\"\nmin_node_version = \"20\"\nallowed_package_managers = [\"npm\", \"deno\"]\n\n[checks]\ndisabled = [\"Lockfile exists\", \"Deprecated: request\", \"Lockfile exist\"]\n\n[checks.severity_overrides]\n\"Peer dependencies\" = \"info\"\n\"Peer deps\" = \"loud\"\n";
+        let diagnostics = validate_document(source, &[]);
+        let summary: Vec<(usize, usize, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.column, d.key.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, 2, "policy"),
+                (5, 1, "policies.alowed_package_managers"),
+                (6, 20, "policies.enforce_corepack"),
+                (7, 20, "policies.min_node_version"),
+                (8, 36, "policies.allowed_package_managers"),
+                (11, 55, "checks.disabled"),
+                (15, 1, "checks.severity_overrides"),
+                (15, 15, "checks.severity_overrides.Peer deps"),
+            ]
+        );
+        assert!(diagnostics[0].message.contains("did you mean `policies`"));
+        assert!(diagnostics[1].message.contains("did you mean `allowed_package_managers`"));
+    }
+
+    #[test]
+    fn test_valid_document_and_syntax_error() {
+        assert!(validate_document("extends = \"./base.toml\"\n[policies]\nmin_node_version = \"18.0.0\"\n", &[]).is_empty());
+        assert!(validate_document("[profiles.ci.policies]\nrequire_lockfile_frozen = true\n", &[]).is_empty());
+        assert!(validate_document(
+            "[checks]\ndisabled = [\"Has .nvmrc\"]\n\n[[checks.custom]]\nname = \"Has .nvmrc\"\nkind = \"file_exists\"\npath = \".nvmrc\"\n",
+            &[]
+        )
+        .is_empty());
+        assert_eq!(validate_document("[profiles.ci]\nstrict = true\n", &[])[0].key, "profiles.ci.strict");
+
+        let diagnostics = validate_document("[policies\n", &[]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 1);
+        assert!(diagnostics[0].message.starts_with("invalid TOML"));
+    }
+
+    #[test]
+    fn test_schema_covers_default_config() {
+        let config = toml::to_string(&crate::config::ZenvoConfig::default()).unwrap();
+        assert!(validate_document(&config, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_custom_checks_from_profiles_and_other_files() {
+        // Defined in a profile of the same file
+        let source = "[checks]\ndisabled = [\"Has .nvmrc\"]\n\n[[profiles.ci.checks.custom]]\nname = \"Has .nvmrc\"\nkind = \"file_exists\"\npath = \".nvmrc\"\n";
+        assert!(validate_document(source, &[]).is_empty());
+
+        // Defined in a preset or another discovered file
+        let source = "[profiles.ci.checks]\ndisabled = [\"License header\"]\n\n[checks.severity_overrides]\n\"License header\" = \"info\"\n";
+        assert_eq!(validate_document(source, &[]).len(), 2);
+        assert!(validate_document(source, &["License header".to_string()]).is_empty());
+    }
+}
@@ -0,0 +1,378 @@
+//! Journal of changes made by zenvo
+//!
+//! Every command that changes a project (`repair --apply`, `resolve`, `upgrade` and
+//! `clean --force`) appends one JSON line to `.zenvo/journal.jsonl`: when it ran, who
+//! ran it, which checks asked for it, each step with its exit status and duration, and
+//! the hashes of the project files before and after. `zenvo journal` reads it back.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::checks::CheckResult;
+use crate::repair::backup::SNAPSHOT_FILES;
+use crate::repair::{ActionRun, RepairAction, StepStatus};
+
+/// Journal location, relative to the project root
+pub const JOURNAL_FILE: &str = ".zenvo/journal.jsonl";
+
+/// How a journaled command ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryStatus {
+    Succeeded,
+    Failed,
+    /// A step failed and the snapshot taken before the repair was restored
+    RolledBack,
+}
+
+/// One step a command ran (or skipped)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalStep {
+    /// Repair action or other unit of work the step belongs to
+    pub action: String,
+    /// Shell-like rendering of the step
+    pub step: String,
+    pub status: StepStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub duration_ms: u64,
+}
+
+/// Hashes of a file before and after a command (`None` when it didn't exist)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl FileChange {
+    pub fn changed(&self) -> bool {
+        self.before != self.after
+    }
+}
+
+/// One journal line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Utc>,
+    pub user: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    pub zenvo_version: String,
+    /// Command that made the changes (`repair`, `resolve`, `upgrade`, `clean`, ...)
+    pub command: String,
+    /// Checks whose findings the command was fixing
+    #[serde(default)]
+    pub triggered_by: Vec<String>,
+    #[serde(default)]
+    pub steps: Vec<JournalStep>,
+    pub status: EntryStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Snapshot taken before a repair
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+    /// Tracked project files, with their hashes before and after
+    #[serde(default)]
+    pub files: Vec<FileChange>,
+}
+
+impl JournalEntry {
+    /// Files whose contents changed
+    pub fn changed_files(&self) -> impl Iterator<Item = &FileChange> {
+        self.files.iter().filter(|f| f.changed())
+    }
+}
+
+/// `sha256:<hex>` of a file, or `None` if it doesn't exist or isn't a file
+fn hash_file(path: &Path) -> Option<String> {
+    let content = fs::read(path).ok()?;
+    Some(format!("sha256:{}", hex::encode(Sha256::digest(&content))))
+}
+
+fn hash_files(root: &Path, paths: &[PathBuf]) -> BTreeMap<PathBuf, Option<String>> {
+    paths
+        .iter()
+        .map(|path| (path.clone(), hash_file(&root.join(path))))
+        .collect()
+}
+
+/// The user running zenvo, from the environment
+fn current_user() -> String {
+    ["USER", "USERNAME", "LOGNAME"]
+        .iter()
+        .find_map(|key| std::env::var(key).ok().filter(|v| !v.trim().is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn current_host() -> Option<String> {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|key| std::env::var(key).ok().filter(|v| !v.trim().is_empty()))
+        .or_else(|| {
+            fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|h| h.trim().to_string())
+                .filter(|h| !h.is_empty())
+        })
+}
+
+/// Collects an entry while a command runs and appends it when the command is done
+///
+/// File hashes are taken when recording starts and again in `finish`.
+#[derive(Debug)]
+pub struct JournalRecorder {
+    root: PathBuf,
+    command: String,
+    started: DateTime<Utc>,
+    triggered_by: Vec<String>,
+    steps: Vec<JournalStep>,
+    snapshot: Option<String>,
+    paths: Vec<PathBuf>,
+    before: BTreeMap<PathBuf, Option<String>>,
+}
+
+impl JournalRecorder {
+    /// Start recording `command`, tracking the standard project files and `extra`
+    pub fn start(root: &Path, command: &str, extra: &[PathBuf]) -> Self {
+        let mut paths: Vec<PathBuf> = SNAPSHOT_FILES.iter().map(PathBuf::from).collect();
+        for path in extra {
+            if !paths.contains(path) {
+                paths.push(path.clone());
+            }
+        }
+        let before = hash_files(root, &paths);
+        Self {
+            root: root.to_path_buf(),
+            command: command.to_string(),
+            started: Utc::now(),
+            triggered_by: Vec::new(),
+            steps: Vec::new(),
+            snapshot: None,
+            paths,
+            before,
+        }
+    }
+
+    /// Record the checks the command is fixing
+    pub fn triggered_by<'a>(&mut self, issues: impl IntoIterator<Item = &'a CheckResult>) {
+        for issue in issues {
+            if !self.triggered_by.contains(&issue.name) {
+                self.triggered_by.push(issue.name.clone());
+            }
+        }
+    }
+
+    pub fn snapshot(&mut self, id: &str) {
+        self.snapshot = Some(id.to_string());
+    }
+
+    pub fn step(&mut self, step: JournalStep) {
+        self.steps.push(step);
+    }
+
+    /// Record a step that isn't a repair step, e.g. deleting a directory
+    pub fn simple_step(
+        &mut self,
+        action: &str,
+        step: &str,
+        status: StepStatus,
+        exit_code: Option<i32>,
+    ) {
+        self.step(JournalStep {
+            action: action.to_string(),
+            step: step.to_string(),
+            status,
+            exit_code,
+            duration_ms: 0,
+        });
+    }
+
+    /// Record every step of a repair action that ran
+    pub fn action_run(&mut self, action: &str, run: &ActionRun) {
+        for step in &run.steps {
+            self.step(JournalStep {
+                action: action.to_string(),
+                step: step.step.clone(),
+                status: step.status,
+                exit_code: step.exit_code,
+                duration_ms: step.duration_ms,
+            });
+        }
+    }
+
+    /// Record an action that didn't run (declined, or left to the user)
+    pub fn skipped_action(&mut self, action: &RepairAction) {
+        for step in &action.steps {
+            self.simple_step(
+                &action.description,
+                &step.preview(),
+                StepStatus::Skipped,
+                None,
+            );
+        }
+    }
+
+    /// Hash the tracked files again and append the entry
+    pub fn finish(self, status: EntryStatus, error: Option<String>) -> Result<JournalEntry> {
+        let after = hash_files(&self.root, &self.paths);
+        let files = self
+            .paths
+            .iter()
+            .filter_map(|path| {
+                let before = self.before.get(path).cloned().flatten();
+                let after = after.get(path).cloned().flatten();
+                // Files that never existed say nothing
+                (before.is_some() || after.is_some()).then(|| FileChange {
+                    path: path.clone(),
+                    before,
+                    after,
+                })
+            })
+            .collect();
+
+        let entry = JournalEntry {
+            timestamp: self.started,
+            user: current_user(),
+            host: current_host(),
+            zenvo_version: env!("CARGO_PKG_VERSION").to_string(),
+            command: self.command,
+            triggered_by: self.triggered_by,
+            steps: self.steps,
+            status,
+            error,
+            snapshot: self.snapshot,
+            files,
+        };
+        append(&self.root, &entry)?;
+        Ok(entry)
+    }
+}
+
+/// Append `entry` to the journal under `root`
+pub fn append(root: &Path, entry: &JournalEntry) -> Result<()> {
+    let path = root.join(JOURNAL_FILE);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    // One write per entry, so concurrent runs don't interleave lines
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Read every entry under `root`, oldest first
+///
+/// Lines that can't be parsed (e.g. a write cut short) are skipped.
+pub fn read(root: &Path) -> Result<Vec<JournalEntry>> {
+    let path = root.join(JOURNAL_FILE);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Which entries to show
+#[derive(Debug, Clone, Default)]
+pub struct JournalFilter {
+    pub command: Option<String>,
+    pub user: Option<String>,
+    /// Only entries triggered by this check
+    pub check: Option<String>,
+    /// First day to include (local time)
+    pub since: Option<NaiveDate>,
+    /// Only entries that didn't succeed
+    pub failed: bool,
+}
+
+impl JournalFilter {
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        self.command.as_ref().map_or(true, |c| entry.command == *c)
+            && self
+                .user
+                .as_ref()
+                .map_or(true, |u| entry.user.eq_ignore_ascii_case(u))
+            && self.check.as_ref().map_or(true, |c| {
+                entry.triggered_by.iter().any(|t| t.eq_ignore_ascii_case(c))
+            })
+            && self.since.map_or(true, |since| {
+                entry.timestamp.with_timezone(&chrono::Local).date_naive() >= since
+            })
+            && (!self.failed || entry.status != EntryStatus::Succeeded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_filter() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("package.json"), "{}").unwrap();
+
+        let mut recorder = JournalRecorder::start(root, "repair", &[]);
+        recorder.triggered_by(&[CheckResult::error(
+            "node_modules exists",
+            "deps",
+            "node_modules not found",
+        )]);
+        recorder.simple_step("Install", "npm ci", StepStatus::Succeeded, Some(0));
+        fs::write(root.join("package.json"), "{\"name\":\"app\"}").unwrap();
+        fs::write(root.join("package-lock.json"), "{}").unwrap();
+        recorder.finish(EntryStatus::Succeeded, None).unwrap();
+
+        let mut recorder = JournalRecorder::start(root, "clean", &[]);
+        recorder.simple_step("Clean", "rm -rf .next", StepStatus::Failed, None);
+        recorder
+            .finish(EntryStatus::Failed, Some("Permission denied".to_string()))
+            .unwrap();
+
+        let entries = read(root).unwrap();
+        assert_eq!(entries.len(), 2);
+        let changed: Vec<&Path> = entries[0]
+            .changed_files()
+            .map(|f| f.path.as_path())
+            .collect();
+        assert_eq!(
+            changed,
+            vec![Path::new("package.json"), Path::new("package-lock.json")]
+        );
+        assert_eq!(entries[0].files[1].before, None);
+        assert_eq!(entries[0].steps[0].exit_code, Some(0));
+
+        let filter = JournalFilter {
+            check: Some("node_modules exists".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&entries[0]) && !filter.matches(&entries[1]));
+        let filter = JournalFilter {
+            failed: true,
+            ..Default::default()
+        };
+        assert!(!filter.matches(&entries[0]) && filter.matches(&entries[1]));
+    }
+}
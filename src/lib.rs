@@ -4,6 +4,7 @@
 
 pub mod checks;
 pub mod config;
+pub mod journal;
 pub mod lockfile;
 pub mod mcp;
 pub mod output;
@@ -22,7 +23,8 @@ pub use output::{
     RepairActionJson, RepairPlanOutput, ZenvoOutput,
 };
 pub use repair::{
-    execute_repair, generate_repair_plan_with_context, RepairAction, RepairContext,
+    execute_repair, generate_repair_plan_with_context, ActionRun, RepairAction, RepairContext,
+    RepairStep, RepairTarget, RunOptions, Snapshot, VerifiedIssue,
 };
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
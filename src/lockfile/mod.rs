@@ -6,6 +6,7 @@ use std::fs;
 use std::path::Path;
 
 use crate::checks::{lockfile_checks, toolchain};
+use crate::config::discovery::find_nearest;
 use crate::utils::{run_command_with_timeout, CommandResult, SHORT_COMMAND_TIMEOUT};
 
 /// Current schema version for env.lock files
@@ -231,13 +232,14 @@ impl EnvLock {
     }
 
     /// Load from file with schema version validation
+    ///
+    /// The nearest env.lock between the current directory and the repository root is used.
     pub fn load() -> Result<Self> {
-        let path = Path::new("env.lock");
-        if !path.exists() {
+        let Some(path) = find_nearest(Path::new("."), "env.lock") else {
             anyhow::bail!("env.lock not found. Run `zenvo init` to create one.");
-        }
+        };
 
-        let content = fs::read_to_string(path)
+        let content = fs::read_to_string(&path)
             .context("Failed to read env.lock")?;
 
         let env_lock: EnvLock = toml::from_str(&content)
@@ -251,10 +253,9 @@ impl EnvLock {
 
     /// Load if exists with schema version validation, otherwise return None
     pub fn load_if_exists() -> Result<Option<Self>> {
-        let path = Path::new("env.lock");
-        if !path.exists() {
+        let Some(path) = find_nearest(Path::new("."), "env.lock") else {
             return Ok(None);
-        }
+        };
 
         let content = fs::read_to_string(path)?;
         let env_lock: EnvLock = toml::from_str(&content)?;
@@ -4,6 +4,7 @@ use colored::Colorize;
 mod checks;
 mod commands;
 mod config;
+mod journal;
 mod lockfile;
 mod output;
 mod repair;
@@ -24,6 +25,14 @@ struct Cli {
     #[arg(long, global = true, default_value = "text")]
     format: String,
 
+    /// Use this config file instead of discovering .env.doctor.toml files
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<std::path::PathBuf>,
+
+    /// Config profile to apply (default: $ZENVO_PROFILE, or `ci` when $CI is set)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -49,6 +58,22 @@ enum Commands {
         /// Only check specific category
         #[arg(short, long, value_enum)]
         category: Option<checks::CheckCategory>,
+
+        /// Also run package-level checks in every workspace member
+        #[arg(long)]
+        workspaces: bool,
+
+        /// Record current warnings and errors as accepted in .zenvo/baseline.toml
+        #[arg(long)]
+        write_baseline: bool,
+
+        /// Why the recorded findings are accepted (with --write-baseline)
+        #[arg(long, requires = "write_baseline")]
+        reason: Option<String>,
+
+        /// Date (YYYY-MM-DD) after which recorded findings count again (with --write-baseline)
+        #[arg(long, requires = "write_baseline")]
+        expires: Option<chrono::NaiveDate>,
     },
 
     /// Show repair plan or apply fixes
@@ -64,6 +89,18 @@ enum Commands {
         /// Auto-approve safe repairs
         #[arg(short, long)]
         yes: bool,
+
+        /// Include node_modules in the snapshot taken before applying
+        #[arg(long, requires = "apply")]
+        backup_node_modules: bool,
+
+        /// Print the plan as a script or CI workflow instead of running it
+        #[arg(long, value_enum, value_name = "FORMAT", conflicts_with_all = ["apply", "plan"])]
+        emit: Option<repair::emit::EmitFormat>,
+
+        /// Restore a snapshot taken before an earlier repair (latest if no ID is given)
+        #[arg(long, value_name = "ID", num_args = 0..=1, conflicts_with_all = ["plan", "apply", "emit"])]
+        rollback: Option<Option<String>>,
     },
 
     /// Verify environment matches env.lock
@@ -139,6 +176,37 @@ enum Commands {
         #[arg(long)]
         dry_run: bool,
     },
+
+    /// List the changes zenvo made to this project (.zenvo/journal.jsonl)
+    Journal {
+        /// Only entries from this command (repair, resolve, upgrade, clean, ...)
+        #[arg(long)]
+        command: Option<String>,
+
+        /// Only entries by this user
+        #[arg(long)]
+        user: Option<String>,
+
+        /// Only entries triggered by this check
+        #[arg(long)]
+        check: Option<String>,
+
+        /// Only entries from this date (YYYY-MM-DD) on
+        #[arg(long)]
+        since: Option<chrono::NaiveDate>,
+
+        /// Only entries that failed or were rolled back
+        #[arg(long)]
+        failed: bool,
+
+        /// Number of entries to show, newest first
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+
+        /// Show every matching entry
+        #[arg(long, conflicts_with = "limit")]
+        all: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -152,14 +220,39 @@ enum ConfigCommands {
 
     /// Validate configuration file
     Validate,
+
+    /// Print the configuration file
+    Show {
+        /// Show the merged result of `extends` and where each value came from
+        #[arg(long)]
+        effective: bool,
+    },
+
+    /// Print a JSON Schema for .env.doctor.toml (for editor autocompletion)
+    Schema,
 }
 
 fn main() {
     let cli = Cli::parse();
-    let format = OutputFormat::from_str(&cli.format);
+    let format: OutputFormat = cli.format.parse().unwrap_or_default();
     let is_json = format == OutputFormat::Json;
 
-    if !is_json {
+    if let Some(path) = cli.config.clone() {
+        config::discovery::set_config_override(path);
+    }
+    if let Some(name) = cli.profile.clone() {
+        config::profiles::set_profile_override(name);
+    }
+
+    // The schema and emitted repair plans are meant to be redirected to a file, so skip the banner
+    let prints_raw = matches!(
+        cli.command,
+        Commands::Config {
+            action: ConfigCommands::Schema
+        } | Commands::Repair { emit: Some(_), .. }
+    );
+
+    if !is_json && !prints_raw {
         println!("{}", "⚡ Zenvo".bold().cyan());
         println!("{}", "Node.js Environment Lock & Doctor".dimmed());
         println!();
@@ -168,8 +261,27 @@ fn main() {
     let result = match cli.command {
         Commands::Init { force } => commands::init::run(force, format),
         Commands::Lock { full } => commands::lock::run(full, format),
-        Commands::Doctor { category } => commands::doctor::run(category, format),
-        Commands::Repair { plan, apply, yes } => commands::repair::run(plan, apply, yes, format),
+        Commands::Doctor {
+            category,
+            workspaces,
+            write_baseline,
+            reason,
+            expires,
+        } => {
+            let baseline = write_baseline.then_some(commands::doctor::BaselineOptions { reason, expires });
+            commands::doctor::run(category, workspaces, baseline, format)
+        }
+        Commands::Repair {
+            plan,
+            apply,
+            yes,
+            backup_node_modules,
+            emit,
+            rollback,
+        } => match rollback {
+            Some(id) => commands::repair::rollback(id.as_deref(), format),
+            None => commands::repair::run(plan, apply, yes, backup_node_modules, emit, format),
+        },
         Commands::Verify { strict, warn } => commands::verify::run(strict, warn, format),
         Commands::Status => commands::status::run(format),
         Commands::Diff => commands::diff::run(format),
@@ -183,6 +295,8 @@ fn main() {
             let config_action = match action {
                 ConfigCommands::Init { force } => ConfigAction::Init { force },
                 ConfigCommands::Validate => ConfigAction::Validate,
+                ConfigCommands::Show { effective } => ConfigAction::Show { effective },
+                ConfigCommands::Schema => ConfigAction::Schema,
             };
             commands::config::run(config_action, format)
         }
@@ -193,6 +307,24 @@ fn main() {
             all,
         } => commands::versions::run(&package, constraint.as_deref(), limit, all, format),
         Commands::Resolve { dry_run } => commands::resolve::run(dry_run, format),
+        Commands::Journal {
+            command,
+            user,
+            check,
+            since,
+            failed,
+            limit,
+            all,
+        } => {
+            let filter = journal::JournalFilter {
+                command,
+                user,
+                check,
+                since,
+                failed,
+            };
+            commands::journal::run(filter, (!all).then_some(limit), format)
+        }
     };
 
     if let Err(e) = result {
@@ -7,9 +7,13 @@ use std::path::Path;
 
 use crate::checks::{detect_current_environment, run_all_checks, CheckCategory, CheckSeverity};
 use crate::config::ZenvoConfig;
+use crate::journal::{EntryStatus, JournalRecorder};
 use crate::lockfile::EnvLock;
 use crate::output::Issue;
-use crate::repair::{generate_repair_plan_with_context, RepairContext};
+use crate::repair::verify::{recheck, verify_repair};
+use crate::repair::{
+    generate_repair_plan_with_context, IssueVerdict, RepairContext, RunOptions, Snapshot,
+};
 
 /// Change to specified directory if path is provided
 fn with_directory<T, F>(args: &Value, f: F) -> Result<T>
@@ -30,6 +34,24 @@ where
     }
 }
 
+/// Profile requested by the tool call, overriding ZENVO_PROFILE and CI detection
+fn requested_profile(args: &Value) -> Option<&str> {
+    args.get("profile").and_then(|v| v.as_str())
+}
+
+/// Load config for a tool call
+///
+/// `command` custom checks run shell commands from the config, so they stay off unless
+/// the call sets `run_commands`.
+fn load_config(args: &Value) -> Result<Option<ZenvoConfig>> {
+    let mut config = ZenvoConfig::load_with_profile(requested_profile(args))?;
+    if let Some(config) = config.as_mut() {
+        let run_commands = args.get("run_commands").and_then(|v| v.as_bool());
+        config.checks.commands_disabled = !run_commands.unwrap_or(false);
+    }
+    Ok(config)
+}
+
 /// Detect Node.js subdirectories (containing package.json)
 pub fn detect_node_projects(_args: &Value) -> Result<Value> {
     let mut projects = Vec::new();
@@ -74,10 +96,10 @@ fn get_package_name(path: &str) -> Option<String> {
 
 /// Get the current environment status
 pub fn get_environment_status(args: &Value) -> Result<Value> {
-    with_directory(args, get_environment_status_impl)
+    with_directory(args, || get_environment_status_impl(args))
 }
 
-fn get_environment_status_impl() -> Result<Value> {
+fn get_environment_status_impl(args: &Value) -> Result<Value> {
     // Detect current environment
     let current = detect_current_environment()?;
 
@@ -85,7 +107,7 @@ fn get_environment_status_impl() -> Result<Value> {
     let locked = EnvLock::load_if_exists()?;
 
     // Load config if it exists
-    let config = ZenvoConfig::load_if_exists()?;
+    let config = load_config(args)?;
 
     // Run all checks
     let results = run_all_checks(&locked, None, &config)?;
@@ -188,7 +210,7 @@ fn fix_drift_impl(args: &Value) -> Result<Value> {
     let env_lock = EnvLock::load()?;
 
     // Load config if it exists
-    let config = ZenvoConfig::load_if_exists()?;
+    let config = load_config(args)?;
 
     // Run checks to find issues
     let results = run_all_checks(&Some(env_lock.clone()), None, &config)?;
@@ -206,9 +228,7 @@ fn fix_drift_impl(args: &Value) -> Result<Value> {
     }
 
     // Create repair context from env.lock
-    let repair_context = RepairContext::new(&env_lock.toolchain.package_manager)
-        .with_node_version_manager(env_lock.toolchain.node_version_source.clone())
-        .with_target_node_version(Some(env_lock.toolchain.node.clone()));
+    let repair_context = RepairContext::from_env(&env_lock, &config);
 
     // Generate repair plan with context
     let repair_plan = generate_repair_plan_with_context(&issues, &repair_context)?;
@@ -219,8 +239,11 @@ fn fix_drift_impl(args: &Value) -> Result<Value> {
         .map(|a| {
             serde_json::json!({
                 "description": a.description,
-                "command": a.command,
-                "is_safe": a.is_safe
+                "command": a.command(),
+                "steps": a.steps,
+                "is_safe": a.is_safe,
+                "after": a.after,
+                "issues": a.triggered_by.iter().map(|r| &r.name).collect::<Vec<_>>()
             })
         })
         .collect();
@@ -234,37 +257,100 @@ fn fix_drift_impl(args: &Value) -> Result<Value> {
         }));
     }
 
-    // Execute repairs
+    // Execute repairs, rolling back to the snapshot if one fails
+    let snapshot = Snapshot::for_actions(&repair_plan, false)?;
+    let mut rolled_back = false;
     let mut executed = Vec::new();
     let mut skipped = Vec::new();
     let mut failed = Vec::new();
+    let mut verified = Vec::new();
+    let mut runs = Vec::new();
+
+    let touched: Vec<std::path::PathBuf> = repair_plan
+        .iter()
+        .flat_map(|a| a.steps.iter())
+        .filter_map(|s| s.touched_path())
+        .map(Path::to_path_buf)
+        .collect();
+    let mut journal = JournalRecorder::start(Path::new("."), "mcp fix_drift", &touched);
+    journal.triggered_by(issues.iter().copied());
+    journal.snapshot(&snapshot.id);
 
     for action in &repair_plan {
-        // Skip non-safe actions if safe_only
-        if safe_only && !action.is_safe {
+        // Skip non-safe actions if safe_only, and anything that has to be done by hand
+        if action.is_manual() || (safe_only && !action.is_safe) {
+            journal.skipped_action(action);
             skipped.push(action.description.clone());
             continue;
         }
 
-        match crate::repair::execute_repair(action) {
-            Ok(_) => executed.push(action.description.clone()),
-            Err(e) => failed.push(serde_json::json!({
-                "action": action.description,
-                "error": e.to_string()
-            })),
+        // stdout carries the protocol, so nothing is streamed
+        let options = RunOptions::for_action(action, &config, false);
+        let run = crate::repair::execute_repair(action, &options);
+        let error = run.error.clone();
+        journal.action_run(&action.description, &run);
+        runs.push(serde_json::json!({
+            "action": action.description,
+            "run": run
+        }));
+        match error {
+            None => {
+                executed.push(action.description.clone());
+                if let Some(fixed) = recheck(action, &config)? {
+                    verified.push(serde_json::json!({
+                        "action": action.description,
+                        "verified": fixed
+                    }));
+                }
+            }
+            Some(e) => {
+                failed.push(serde_json::json!({
+                    "action": action.description,
+                    "error": e
+                }));
+                snapshot.restore(Path::new("."))?;
+                rolled_back = true;
+                break;
+            }
         }
     }
 
+    // Run everything again to see what the repair actually changed
+    let verification = if rolled_back {
+        Vec::new()
+    } else {
+        verify_repair(&issues, &config)?
+    };
+    let resolved = !rolled_back && verification.iter().all(|v| v.verdict == IssueVerdict::Fixed);
+
+    let error = failed
+        .first()
+        .and_then(|f| f["error"].as_str())
+        .map(str::to_string);
+    let status = if rolled_back {
+        EntryStatus::RolledBack
+    } else {
+        EntryStatus::Succeeded
+    };
+    journal.finish(status, error)?;
+
     Ok(serde_json::json!({
-        "success": failed.is_empty(),
-        "message": if failed.is_empty() {
-            "Repair completed successfully"
+        "success": resolved,
+        "message": if rolled_back {
+            "Repair failed and was rolled back"
+        } else if resolved {
+            "Repair completed and all issues are fixed"
         } else {
-            "Repair completed with some failures"
+            "Repair completed but some issues remain"
         },
+        "verification": verification,
+        "verified_actions": verified,
+        "snapshot": snapshot.id,
+        "rolled_back": rolled_back,
         "executed": executed,
         "skipped": skipped,
-        "failed": failed
+        "failed": failed,
+        "runs": runs
     }))
 }
 
@@ -278,19 +364,13 @@ fn run_doctor_impl(args: &Value) -> Result<Value> {
     let category = args
         .get("category")
         .and_then(|v| v.as_str())
-        .and_then(|s| match s.to_lowercase().as_str() {
-            "toolchain" => Some(CheckCategory::Toolchain),
-            "lockfile" => Some(CheckCategory::Lockfile),
-            "deps" => Some(CheckCategory::Deps),
-            "frameworks" => Some(CheckCategory::Frameworks),
-            _ => None,
-        });
+        .and_then(CheckCategory::from_name);
 
     // Load env.lock if it exists
     let env_lock = EnvLock::load_if_exists()?;
 
     // Load config if it exists
-    let config = ZenvoConfig::load_if_exists()?;
+    let config = load_config(args)?;
 
     // Run checks
     let results = run_all_checks(&env_lock, category, &config)?;
@@ -412,13 +492,10 @@ pub fn search_versions(args: &Value) -> Result<Value> {
 
     // Filter by constraint if provided
     if let Some(constraint_str) = constraint {
-        versions = versions
-            .into_iter()
-            .filter(|v| {
-                let version = v.get("version").and_then(|v| v.as_str()).unwrap_or("");
-                matches_version_constraint(version, constraint_str)
-            })
-            .collect();
+        versions.retain(|v| {
+            let version = v.get("version").and_then(|v| v.as_str()).unwrap_or("");
+            matches_version_constraint(version, constraint_str)
+        });
     }
 
     // Limit results
@@ -445,10 +522,11 @@ pub fn search_versions(args: &Value) -> Result<Value> {
         "dist_tags": dist_tags,
         "versions": display_versions,
         "suggestion": suggestion,
-        "hint": if display_versions.is_empty() && constraint.is_some() {
-            Some(format!("No versions match constraint '{}'. Try a different constraint.", constraint.unwrap()))
-        } else {
-            None
+        "hint": match constraint {
+            Some(c) if display_versions.is_empty() => {
+                Some(format!("No versions match constraint '{}'. Try a different constraint.", c))
+            }
+            _ => None,
         }
     }))
 }
@@ -482,18 +560,18 @@ fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
 fn matches_version_constraint(version: &str, constraint: &str) -> bool {
     let constraint = constraint.trim();
 
-    let (operator, target) = if constraint.starts_with(">=") {
-        (">=", &constraint[2..])
-    } else if constraint.starts_with("<=") {
-        ("<=", &constraint[2..])
-    } else if constraint.starts_with('^') {
-        ("^", &constraint[1..])
-    } else if constraint.starts_with('~') {
-        ("~", &constraint[1..])
-    } else if constraint.starts_with('>') {
-        (">", &constraint[1..])
-    } else if constraint.starts_with('<') {
-        ("<", &constraint[1..])
+    let (operator, target) = if let Some(rest) = constraint.strip_prefix(">=") {
+        (">=", rest)
+    } else if let Some(rest) = constraint.strip_prefix("<=") {
+        ("<=", rest)
+    } else if let Some(rest) = constraint.strip_prefix('^') {
+        ("^", rest)
+    } else if let Some(rest) = constraint.strip_prefix('~') {
+        ("~", rest)
+    } else if let Some(rest) = constraint.strip_prefix('>') {
+        (">", rest)
+    } else if let Some(rest) = constraint.strip_prefix('<') {
+        ("<", rest)
     } else {
         ("=", constraint)
     };
@@ -723,18 +801,19 @@ fn parse_conflicts(output: &str) -> Vec<serde_json::Value> {
         }
 
         // "Could not resolve dependency:" signals end of conflict block
-        if line.contains("Could not resolve dependency") {
-            if !conflicting_dep.is_empty() && !actual_version.is_empty() {
-                conflicts.push(serde_json::json!({
-                    "package": conflicting_dep.clone(),
-                    "current_version": actual_version.clone(),
-                    "conflicting_dep": current_package.clone(),
-                    "required_range": required_range.clone(),
-                    "actual_version": actual_version.clone(),
-                    "suggested_version": if !suggested_version.is_empty() { Some(suggested_version.clone()) } else { None::<String> }
-                }));
-                suggested_version.clear();
-            }
+        if line.contains("Could not resolve dependency")
+            && !conflicting_dep.is_empty()
+            && !actual_version.is_empty()
+        {
+            conflicts.push(serde_json::json!({
+                "package": conflicting_dep.clone(),
+                "current_version": actual_version.clone(),
+                "conflicting_dep": current_package.clone(),
+                "required_range": required_range.clone(),
+                "actual_version": actual_version.clone(),
+                "suggested_version": if !suggested_version.is_empty() { Some(suggested_version.clone()) } else { None::<String> }
+            }));
+            suggested_version.clear();
         }
     }
 
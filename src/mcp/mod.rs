@@ -167,6 +167,16 @@ impl McpServer {
             "description": "Path to the Node.js project directory (default: current directory). Use this when the project is in a subdirectory like 'frontend/' or 'client/'."
         });
 
+        // Config profile for tools that run checks
+        let profile_prop = serde_json::json!({
+            "type": "string",
+            "description": "Config profile to apply, e.g. 'ci' or 'release' (default: ZENVO_PROFILE, or 'ci' when CI is set)"
+        });
+        let run_commands_prop = serde_json::json!({
+            "type": "boolean",
+            "description": "Run `command` custom checks from the project's config, which execute shell commands (default: false, reported as not run)"
+        });
+
         let tools = vec![
             Tool {
                 name: "detect_node_projects".to_string(),
@@ -183,7 +193,9 @@ impl McpServer {
                 input_schema: serde_json::json!({
                     "type": "object",
                     "properties": {
-                        "path": path_prop
+                        "path": path_prop,
+                        "profile": profile_prop,
+                        "run_commands": run_commands_prop
                     },
                     "required": []
                 }),
@@ -210,6 +222,8 @@ impl McpServer {
                     "type": "object",
                     "properties": {
                         "path": path_prop,
+                        "profile": profile_prop,
+                        "run_commands": run_commands_prop,
                         "execute": {
                             "type": "boolean",
                             "description": "Whether to execute the repair plan (default: false, only shows plan)"
@@ -229,6 +243,8 @@ impl McpServer {
                     "type": "object",
                     "properties": {
                         "path": path_prop,
+                        "profile": profile_prop,
+                        "run_commands": run_commands_prop,
                         "category": {
                             "type": "string",
                             "enum": ["toolchain", "lockfile", "deps", "frameworks"],
@@ -2,6 +2,7 @@ use serde::Serialize;
 use chrono::Utc;
 
 use crate::checks::{CheckResult, CheckSeverity, CurrentEnvironment};
+use crate::repair::{ActionRun, RepairAction, RepairStep, RepairTarget, VerifiedIssue};
 
 /// Output format for CLI
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -11,12 +12,15 @@ pub enum OutputFormat {
     Json,
 }
 
-impl OutputFormat {
-    pub fn from_str(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
+/// Anything other than `json` is text
+impl std::str::FromStr for OutputFormat {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
             "json" => OutputFormat::Json,
             _ => OutputFormat::Text,
-        }
+        })
     }
 }
 
@@ -161,8 +165,50 @@ pub struct DiffOutput {
 #[derive(Debug, Clone, Serialize)]
 pub struct RepairActionJson {
     pub description: String,
+    /// Shell-like preview of the steps
     pub command: String,
+    pub steps: Vec<RepairStep>,
     pub is_safe: bool,
+    pub provides: Vec<RepairTarget>,
+    pub requires: Vec<RepairTarget>,
+    pub invalidates: Vec<RepairTarget>,
+    /// Indexes of the earlier actions this one has to wait for
+    pub after: Vec<usize>,
+    /// Names of the checks the action is meant to fix
+    pub issues: Vec<String>,
+    /// Why zenvo chose these steps
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+    /// Whether those checks pass after running it (only after `--apply`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+    /// Exit codes and durations of its steps (only for actions that ran)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run: Option<ActionRun>,
+}
+
+impl RepairActionJson {
+    pub fn from_action(action: &RepairAction, verified: Option<bool>) -> Self {
+        Self {
+            description: action.description.clone(),
+            command: action.command(),
+            steps: action.steps.clone(),
+            is_safe: action.is_safe,
+            provides: action.provides.clone(),
+            requires: action.requires.clone(),
+            invalidates: action.invalidates.clone(),
+            after: action.after.clone(),
+            issues: action.triggered_by.iter().map(|r| r.name.clone()).collect(),
+            reasons: action.reasons.clone(),
+            verified,
+            run: None,
+        }
+    }
+
+    pub fn with_run(mut self, run: Option<ActionRun>) -> Self {
+        self.run = run;
+        self
+    }
 }
 
 /// Repair plan output
@@ -172,6 +218,9 @@ pub struct RepairPlanOutput {
     pub total_issues: usize,
     pub safe_actions: usize,
     pub review_actions: usize,
+    /// State of each issue after `--apply`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Vec<VerifiedIssue>>,
 }
 
 /// Clean target info for JSON output
@@ -0,0 +1,360 @@
+//! Snapshots taken before a repair
+//!
+//! `zenvo repair --apply` copies package.json, lockfiles, env.lock, config files and
+//! everything its steps write or delete into `.zenvo/backups/<id>` before running. If
+//! a step fails the snapshot is restored straight away; `zenvo repair --rollback [id]`
+//! restores one later. node_modules is only included when asked for, since copying it
+//! can take a while.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use super::RepairAction;
+use crate::config::CONFIG_FILE;
+
+/// Where snapshots are kept, relative to the project root
+pub const BACKUP_DIR: &str = ".zenvo/backups";
+
+/// Snapshots kept before the oldest are deleted
+pub const MAX_SNAPSHOTS: usize = 10;
+
+const MANIFEST_FILE: &str = "snapshot.toml";
+const FILES_DIR: &str = "files";
+const NODE_MODULES: &str = "node_modules";
+
+/// Files that are always part of a snapshot
+pub const SNAPSHOT_FILES: &[&str] = &[
+    "package.json",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+    "bun.lock",
+    "env.lock",
+    CONFIG_FILE,
+    ".nvmrc",
+    ".node-version",
+    ".npmrc",
+    ".yarnrc.yml",
+];
+
+/// A saved copy of the project files a repair may change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub id: String,
+    pub created: DateTime<Local>,
+    /// Paths relative to the project root
+    #[serde(default, rename = "file")]
+    pub files: Vec<SnapshotFile>,
+    /// Whether node_modules was copied
+    #[serde(default)]
+    pub node_modules: bool,
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+/// One path in a snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    pub path: PathBuf,
+    /// Paths that didn't exist are deleted again on restore
+    pub existed: bool,
+}
+
+/// Whether `path` is a relative path that stays inside the project
+fn is_project_path(path: &Path) -> bool {
+    !path.as_os_str().is_empty()
+        && path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+fn remove_path(path: &Path) -> Result<()> {
+    let result = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(_) => return Ok(()),
+    };
+    result.with_context(|| format!("Failed to remove {}", path.display()))
+}
+
+/// Copy a file, symlink or directory tree from `from` to `to`
+fn copy_path(from: &Path, to: &Path) -> Result<()> {
+    for entry in walkdir::WalkDir::new(from).follow_links(false) {
+        let entry = entry.with_context(|| format!("Failed to read {}", from.display()))?;
+        let relative = entry.path().strip_prefix(from).unwrap_or(Path::new(""));
+        let target = if relative.as_os_str().is_empty() {
+            to.to_path_buf()
+        } else {
+            to.join(relative)
+        };
+
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            fs::create_dir_all(&target)
+                .with_context(|| format!("Failed to create {}", target.display()))?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        if file_type.is_symlink() {
+            copy_symlink(entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target).with_context(|| {
+                format!(
+                    "Failed to copy {} to {}",
+                    entry.path().display(),
+                    target.display()
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> Result<()> {
+    let link = fs::read_link(from)?;
+    std::os::unix::fs::symlink(&link, to)
+        .with_context(|| format!("Failed to create link {}", to.display()))
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, to: &Path) -> Result<()> {
+    // Junctions and links on Windows: copy what they point at
+    if from.is_dir() {
+        copy_path(&fs::canonicalize(from)?, to)
+    } else {
+        fs::copy(from, to)
+            .map(|_| ())
+            .with_context(|| format!("Failed to copy {}", from.display()))
+    }
+}
+
+impl Snapshot {
+    /// Directory holding all snapshots for `root`
+    pub fn backup_dir(root: &Path) -> PathBuf {
+        root.join(BACKUP_DIR)
+    }
+
+    /// Save the standard files, `extra` paths and optionally node_modules under `root`
+    pub fn create(root: &Path, extra: &[PathBuf], include_node_modules: bool) -> Result<Self> {
+        let created = Local::now();
+        let base = Self::backup_dir(root);
+        let stamp = created.format("%Y%m%d-%H%M%S").to_string();
+        let mut id = stamp.clone();
+        let mut n = 1;
+        while base.join(&id).exists() {
+            id = format!("{}-{}", stamp, n);
+            n += 1;
+        }
+        let dir = base.join(&id);
+        fs::create_dir_all(dir.join(FILES_DIR))
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let mut paths: Vec<PathBuf> = SNAPSHOT_FILES.iter().map(PathBuf::from).collect();
+        for path in extra {
+            let path = path.strip_prefix(".").unwrap_or(path);
+            if is_project_path(path)
+                && !path.starts_with(NODE_MODULES)
+                && !path.starts_with(BACKUP_DIR)
+                && !paths.iter().any(|p| p == path)
+            {
+                paths.push(path.to_path_buf());
+            }
+        }
+
+        let mut files = Vec::new();
+        for path in paths {
+            let source = root.join(&path);
+            let existed = fs::symlink_metadata(&source).is_ok();
+            if existed {
+                copy_path(&source, &dir.join(FILES_DIR).join(&path))?;
+            }
+            // Missing files are recorded too, so a lockfile created by a failed install goes away
+            files.push(SnapshotFile { path, existed });
+        }
+
+        let node_modules = include_node_modules && root.join(NODE_MODULES).is_dir();
+        if node_modules {
+            copy_path(&root.join(NODE_MODULES), &dir.join(NODE_MODULES))?;
+        }
+
+        let snapshot = Self {
+            id,
+            created,
+            files,
+            node_modules,
+            dir,
+        };
+        let manifest = toml::to_string_pretty(&snapshot).context("Failed to serialize snapshot")?;
+        fs::write(snapshot.dir.join(MANIFEST_FILE), manifest)
+            .with_context(|| format!("Failed to write snapshot {}", snapshot.id))?;
+
+        Self::prune(root, MAX_SNAPSHOTS)?;
+        Ok(snapshot)
+    }
+
+    /// Snapshot taken before applying `actions` in the current directory
+    pub fn for_actions(actions: &[RepairAction], include_node_modules: bool) -> Result<Self> {
+        let touched: Vec<PathBuf> = actions
+            .iter()
+            .flat_map(|a| a.steps.iter())
+            .filter_map(|s| s.touched_path())
+            .map(Path::to_path_buf)
+            .collect();
+        Self::create(Path::new("."), &touched, include_node_modules)
+    }
+
+    fn load_from(dir: &Path) -> Result<Self> {
+        let path = dir.join(MANIFEST_FILE);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut snapshot: Self = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        snapshot.dir = dir.to_path_buf();
+        Ok(snapshot)
+    }
+
+    /// All snapshots under `root`, oldest first
+    pub fn list(root: &Path) -> Result<Vec<Self>> {
+        let base = Self::backup_dir(root);
+        let Ok(entries) = fs::read_dir(&base) else {
+            return Ok(Vec::new());
+        };
+        let mut snapshots: Vec<Self> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().join(MANIFEST_FILE).is_file())
+            .filter_map(|e| Self::load_from(&e.path()).ok())
+            .collect();
+        snapshots.sort_by(|a, b| a.created.cmp(&b.created).then(a.id.cmp(&b.id)));
+        Ok(snapshots)
+    }
+
+    /// The snapshot called `id`, or the latest one
+    pub fn find(root: &Path, id: Option<&str>) -> Result<Self> {
+        let mut snapshots = Self::list(root)?;
+        match id {
+            None => match snapshots.pop() {
+                Some(snapshot) => Ok(snapshot),
+                None => bail!("No snapshots in {}", Self::backup_dir(root).display()),
+            },
+            Some(id) => match snapshots.iter().position(|s| s.id == id) {
+                Some(i) => Ok(snapshots.swap_remove(i)),
+                None => {
+                    let available: Vec<&str> = snapshots.iter().map(|s| s.id.as_str()).collect();
+                    bail!(
+                        "Snapshot `{}` not found (available: {})",
+                        id,
+                        if available.is_empty() {
+                            "none".to_string()
+                        } else {
+                            available.join(", ")
+                        }
+                    );
+                }
+            },
+        }
+    }
+
+    /// Delete all but the `keep` newest snapshots
+    fn prune(root: &Path, keep: usize) -> Result<()> {
+        let snapshots = Self::list(root)?;
+        let excess = snapshots.len().saturating_sub(keep);
+        for snapshot in &snapshots[..excess] {
+            remove_path(&snapshot.dir)?;
+        }
+        Ok(())
+    }
+
+    /// Put every saved path under `root` back the way it was
+    pub fn restore(&self, root: &Path) -> Result<()> {
+        for file in &self.files {
+            let target = root.join(&file.path);
+            remove_path(&target)?;
+            if file.existed {
+                copy_path(&self.dir.join(FILES_DIR).join(&file.path), &target)?;
+            }
+        }
+        if self.node_modules {
+            let target = root.join(NODE_MODULES);
+            remove_path(&target)?;
+            copy_path(&self.dir.join(NODE_MODULES), &target)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_snapshot_restore() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("package.json"), r#"{"name":"app"}"#).unwrap();
+        fs::write(root.join("package-lock.json"), "{}").unwrap();
+        fs::create_dir_all(root.join("node_modules/left-pad")).unwrap();
+        fs::write(
+            root.join("node_modules/left-pad/index.js"),
+            "module.exports = 1",
+        )
+        .unwrap();
+
+        let snapshot = Snapshot::create(root, &[PathBuf::from("./.prettierrc")], true).unwrap();
+        assert!(snapshot.node_modules);
+        assert!(snapshot
+            .files
+            .iter()
+            .any(|f| f.path == Path::new(".prettierrc") && !f.existed));
+        assert!(snapshot
+            .files
+            .iter()
+            .any(|f| f.path == Path::new("yarn.lock") && !f.existed));
+
+        // A repair that went wrong
+        fs::write(root.join("package.json"), r#"{"name":"broken"}"#).unwrap();
+        fs::remove_file(root.join("package-lock.json")).unwrap();
+        fs::remove_dir_all(root.join("node_modules")).unwrap();
+        fs::write(root.join(".prettierrc"), "{}").unwrap();
+        fs::write(root.join("yarn.lock"), "").unwrap();
+
+        Snapshot::find(root, None).unwrap().restore(root).unwrap();
+        assert_eq!(
+            fs::read_to_string(root.join("package.json")).unwrap(),
+            r#"{"name":"app"}"#
+        );
+        assert!(root.join("package-lock.json").exists());
+        assert!(root.join("node_modules/left-pad/index.js").exists());
+        assert!(!root.join(".prettierrc").exists());
+        assert!(!root.join("yarn.lock").exists());
+
+        let err = Snapshot::find(root, Some("nope")).unwrap_err();
+        assert!(err.to_string().contains(&snapshot.id));
+    }
+
+    #[test]
+    fn test_prune_keeps_newest() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("package.json"), "{}").unwrap();
+        let ids: Vec<String> = (0..3)
+            .map(|_| Snapshot::create(root, &[], false).unwrap().id)
+            .collect();
+        Snapshot::prune(root, 2).unwrap();
+        let left: Vec<String> = Snapshot::list(root)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(left, ids[1..].to_vec());
+    }
+}
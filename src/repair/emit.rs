@@ -0,0 +1,493 @@
+//! Rendering repair plans as scripts and CI steps
+//!
+//! `zenvo repair --emit <format>` prints the plan as something that can be reviewed,
+//! committed and run elsewhere. Unlike `--apply`, a script can switch Node with nvm or
+//! fnm because it runs in the shell whose Node it changes.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::step::shell_quote;
+use super::{RepairAction, RepairContext, RepairStep, RepairTarget};
+use crate::output::{RepairActionJson, RepairPlanOutput};
+
+/// Format for `zenvo repair --emit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EmitFormat {
+    /// POSIX shell script
+    Sh,
+    /// PowerShell script
+    Powershell,
+    /// Makefile with one target per action
+    Makefile,
+    /// GitHub Actions workflow
+    GithubActions,
+    /// The plan as JSON
+    Json,
+}
+
+impl EmitFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EmitFormat::Sh => "sh",
+            EmitFormat::Powershell => "powershell",
+            EmitFormat::Makefile => "makefile",
+            EmitFormat::GithubActions => "github-actions",
+            EmitFormat::Json => "json",
+        }
+    }
+}
+
+/// Sets a dotted key in a JSON file: `node -e <script> <file> <key> <hex JSON value>`
+///
+/// The script has no quotes and the value is hex-encoded, so both survive any shell's
+/// quoting, including PowerShell's handling of embedded double quotes.
+const EDIT_JSON_JS: &str = "const fs=require(`fs`);const [file,key,encoded]=process.argv.slice(1);const raw=fs.readFileSync(file,`utf8`);const json=JSON.parse(raw);const keys=key.split(/(?<!\\\\)\\./).map(k=>k.split(`\\\\.`).join(`.`));let obj=json;for(const k of keys.slice(0,-1)){obj=obj[k]=obj[k]||{};}const value=JSON.parse(Buffer.from(encoded,`hex`).toString());if(value===null){delete obj[keys[keys.length-1]];}else{obj[keys[keys.length-1]]=value;}fs.writeFileSync(file,JSON.stringify(json,null,/\\n {4}\\S/.test(raw)?4:2)+`\\n`);";
+
+fn header(format: EmitFormat) -> String {
+    format!(
+        "# Repair plan generated by `zenvo repair --emit {}`. Review before running.\n",
+        format.name()
+    )
+}
+
+/// Quote as a PowerShell literal string
+fn ps_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "''"))
+}
+
+/// Escape `contents` as a printf format string that prints it back unchanged
+fn printf_format(contents: &str) -> String {
+    contents
+        .replace('\\', "\\\\")
+        .replace('%', "%%")
+        .replace('\n', "\\n")
+}
+
+fn edit_json_argv(step: &RepairStep) -> Option<Vec<String>> {
+    let RepairStep::EditJson { path, key, value } = step else {
+        return None;
+    };
+    let encoded = hex::encode(value.to_string());
+    Some(vec![
+        "node".to_string(),
+        "-e".to_string(),
+        EDIT_JSON_JS.to_string(),
+        path.display().to_string(),
+        key.clone(),
+        encoded,
+    ])
+}
+
+/// Shell lines for one step; each line stands on its own (for make recipes)
+fn sh_lines(step: &RepairStep) -> Vec<String> {
+    match step {
+        RepairStep::Exec { argv, cwd, env } => {
+            let mut command: Vec<String> = env
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, shell_quote(v)))
+                .collect();
+            command.extend(argv.iter().map(|a| shell_quote(a)));
+            let command = command.join(" ");
+            vec![match cwd {
+                Some(cwd) => format!(
+                    "(cd {} && {})",
+                    shell_quote(&cwd.display().to_string()),
+                    command
+                ),
+                None => command,
+            }]
+        }
+        RepairStep::RemovePath { path } => {
+            vec![format!(
+                "rm -rf {}",
+                shell_quote(&path.display().to_string())
+            )]
+        }
+        RepairStep::WriteFile { path, contents } => vec![format!(
+            "printf {} > {}",
+            shell_quote(&printf_format(contents)),
+            shell_quote(&path.display().to_string())
+        )],
+        RepairStep::EditJson { .. } => {
+            let argv = edit_json_argv(step).unwrap_or_default();
+            vec![argv
+                .iter()
+                .map(|a| shell_quote(a))
+                .collect::<Vec<_>>()
+                .join(" ")]
+        }
+        RepairStep::UseNode { version, manager } if manager == "nvm" => vec![
+            ". \"${NVM_DIR:-$HOME/.nvm}/nvm.sh\"".to_string(),
+            format!("nvm install {}", shell_quote(version)),
+            format!("nvm use {}", shell_quote(version)),
+        ],
+        RepairStep::UseNode { version, manager } => vec![
+            format!("eval \"$({} env)\"", manager),
+            format!(
+                "{} use --install-if-missing {}",
+                manager,
+                shell_quote(version)
+            ),
+        ],
+        RepairStep::ManualInstruction { instructions } => instructions
+            .lines()
+            .map(|line| format!("# Manual: {}", line))
+            .collect(),
+    }
+}
+
+/// `# Why: ...` lines for the reasons behind an action
+fn reason_comments(action: &RepairAction) -> String {
+    action
+        .reasons
+        .iter()
+        .map(|reason| format!("# Why: {}\n", reason))
+        .collect()
+}
+
+fn render_sh(plan: &[RepairAction]) -> String {
+    let mut out = String::from("#!/bin/sh\n");
+    out.push_str(&header(EmitFormat::Sh));
+    out.push_str("set -eu\n");
+    for (i, action) in plan.iter().enumerate() {
+        out.push_str(&format!("\n# {}. {}\n", i + 1, action.description));
+        out.push_str(&reason_comments(action));
+        for step in &action.steps {
+            for line in sh_lines(step) {
+                out.push_str(&line);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// PowerShell lines for one step
+fn ps_lines(step: &RepairStep) -> Vec<String> {
+    const CHECK_EXIT: &str = "if ($LASTEXITCODE -ne 0) { exit $LASTEXITCODE }";
+    let native = |argv: &[String]| {
+        let command: Vec<String> = argv.iter().map(|a| ps_quote(a)).collect();
+        vec![format!("& {}", command.join(" ")), CHECK_EXIT.to_string()]
+    };
+
+    match step {
+        RepairStep::Exec { argv, cwd, env } => {
+            let mut lines: Vec<String> = env
+                .iter()
+                .map(|(k, v)| format!("$env:{} = {}", k, ps_quote(v)))
+                .collect();
+            match cwd {
+                Some(cwd) => {
+                    lines.push(format!(
+                        "Push-Location -LiteralPath {}",
+                        ps_quote(&cwd.display().to_string())
+                    ));
+                    lines.extend(native(argv));
+                    lines.push("Pop-Location".to_string());
+                }
+                None => lines.extend(native(argv)),
+            }
+            lines
+        }
+        RepairStep::RemovePath { path } => vec![format!(
+            "Remove-Item -Recurse -Force -ErrorAction SilentlyContinue -LiteralPath {}",
+            ps_quote(&path.display().to_string())
+        )],
+        RepairStep::WriteFile { path, contents } => vec![format!(
+            "[IO.File]::WriteAllText((Join-Path (Get-Location) {}), {})",
+            ps_quote(&path.display().to_string()),
+            ps_quote(contents)
+        )],
+        RepairStep::EditJson { .. } => native(&edit_json_argv(step).unwrap_or_default()),
+        RepairStep::UseNode { version, manager } if manager == "nvm" => vec![
+            // nvm-windows changes the system-wide symlink, so no environment to load
+            format!("nvm install {}", ps_quote(version)),
+            format!("nvm use {}", ps_quote(version)),
+        ],
+        RepairStep::UseNode { version, manager } => vec![
+            format!(
+                "{} env --use-on-cd | Out-String | Invoke-Expression",
+                manager
+            ),
+            format!("{} use --install-if-missing {}", manager, ps_quote(version)),
+            CHECK_EXIT.to_string(),
+        ],
+        RepairStep::ManualInstruction { instructions } => instructions
+            .lines()
+            .map(|line| format!("# Manual: {}", line))
+            .collect(),
+    }
+}
+
+fn render_powershell(plan: &[RepairAction]) -> String {
+    let mut out = header(EmitFormat::Powershell);
+    out.push_str("$ErrorActionPreference = 'Stop'\n");
+    for (i, action) in plan.iter().enumerate() {
+        out.push_str(&format!("\n# {}. {}\n", i + 1, action.description));
+        out.push_str(&reason_comments(action));
+        for step in &action.steps {
+            for line in ps_lines(step) {
+                out.push_str(&line);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+fn render_makefile(plan: &[RepairAction]) -> String {
+    // Every recipe line runs in its own shell, so the Node picked by nvm/fnm has to be
+    // activated again in each line that follows the switch
+    let node_env = plan
+        .iter()
+        .flat_map(|a| a.steps.iter())
+        .find_map(|step| match step {
+            RepairStep::UseNode { version, manager } if manager == "nvm" => Some(format!(
+                ". \"$${{NVM_DIR:-$$HOME/.nvm}}/nvm.sh\" && nvm use {} >/dev/null &&",
+                shell_quote(version)
+            )),
+            RepairStep::UseNode { version, manager } => Some(format!(
+                "eval \"$$({} env)\" && {} use {} >/dev/null &&",
+                manager,
+                manager,
+                shell_quote(version)
+            )),
+            _ => None,
+        });
+
+    let targets: Vec<String> = (1..=plan.len()).map(|i| format!("step-{}", i)).collect();
+    let mut out = header(EmitFormat::Makefile);
+    out.push_str(&format!(".PHONY: repair {}\n", targets.join(" ")));
+    if node_env.is_some() {
+        out.push_str("\nNODE_ENV_SETUP = ");
+        out.push_str(node_env.as_deref().unwrap_or_default());
+        out.push('\n');
+    }
+    out.push_str(&format!("\nrepair: {}\n", targets.join(" ")));
+
+    for (i, action) in plan.iter().enumerate() {
+        let prerequisites: Vec<&str> = action.after.iter().map(|&j| targets[j].as_str()).collect();
+        out.push_str(&format!("\n# {}\n", action.description));
+        out.push_str(&reason_comments(action));
+        if prerequisites.is_empty() {
+            out.push_str(&format!("{}:\n", targets[i]));
+        } else {
+            out.push_str(&format!("{}: {}\n", targets[i], prerequisites.join(" ")));
+        }
+
+        let switches_node = action
+            .steps
+            .iter()
+            .any(|s| matches!(s, RepairStep::UseNode { .. }));
+        for step in &action.steps {
+            for line in sh_lines(step) {
+                let line = line.replace('$', "$$");
+                if line.starts_with('#') {
+                    out.push_str(&format!("\t@echo {}\n", shell_quote(&line[2..])));
+                } else if node_env.is_some()
+                    && !switches_node
+                    && matches!(step, RepairStep::Exec { .. } | RepairStep::EditJson { .. })
+                {
+                    out.push_str(&format!("\t$(NODE_ENV_SETUP) {}\n", line));
+                } else {
+                    out.push_str(&format!("\t{}\n", line));
+                }
+            }
+        }
+    }
+    out
+}
+
+#[derive(Serialize)]
+struct Workflow {
+    name: &'static str,
+    on: &'static str,
+    jobs: BTreeMap<&'static str, Job>,
+}
+
+#[derive(Serialize)]
+struct Job {
+    #[serde(rename = "runs-on")]
+    runs_on: &'static str,
+    steps: Vec<WorkflowStep>,
+}
+
+#[derive(Serialize, Default)]
+struct WorkflowStep {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uses: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    with: Option<BTreeMap<&'static str, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run: Option<String>,
+}
+
+fn render_github_actions(plan: &[RepairAction], context: &RepairContext) -> Result<String> {
+    let mut steps = vec![WorkflowStep {
+        uses: Some("actions/checkout@v4"),
+        ..Default::default()
+    }];
+    let mut zenvo_installed = false;
+
+    for action in plan {
+        // Runners have no version manager; setup-node installs and activates Node
+        if action.provides.contains(&RepairTarget::Node) {
+            let version = context
+                .target_node_version
+                .clone()
+                .unwrap_or_else(|| "lts/*".to_string());
+            steps.push(WorkflowStep {
+                name: Some(action.description.clone()),
+                uses: Some("actions/setup-node@v4"),
+                with: Some(BTreeMap::from([("node-version", version)])),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        // zenvo itself isn't on the runner
+        let runs_zenvo = action
+            .steps
+            .iter()
+            .any(|s| matches!(s, RepairStep::Exec { argv, .. } if argv.first().is_some_and(|p| p == "zenvo")));
+        if runs_zenvo && !zenvo_installed {
+            steps.push(WorkflowStep {
+                name: Some("Install zenvo".to_string()),
+                run: Some("npm install -g zenvo".to_string()),
+                ..Default::default()
+            });
+            zenvo_installed = true;
+        }
+
+        let lines: Vec<String> = action
+            .steps
+            .iter()
+            .flat_map(|step| match step {
+                RepairStep::ManualInstruction { instructions } => instructions
+                    .lines()
+                    .map(|line| format!("echo {}", shell_quote(&format!("::warning::{}", line))))
+                    .collect(),
+                _ => sh_lines(step),
+            })
+            .collect();
+        steps.push(WorkflowStep {
+            name: Some(action.description.clone()),
+            run: Some(lines.join("\n")),
+            ..Default::default()
+        });
+    }
+
+    let workflow = Workflow {
+        name: "Zenvo repair",
+        on: "workflow_dispatch",
+        jobs: BTreeMap::from([(
+            "repair",
+            Job {
+                runs_on: "ubuntu-latest",
+                steps,
+            },
+        )]),
+    };
+    Ok(header(EmitFormat::GithubActions) + &serde_yaml::to_string(&workflow)?)
+}
+
+fn render_json(plan: &[RepairAction]) -> Result<String> {
+    let actions: Vec<RepairActionJson> = plan
+        .iter()
+        .map(|a| RepairActionJson::from_action(a, None))
+        .collect();
+    let safe_actions = actions.iter().filter(|a| a.is_safe).count();
+    let output = RepairPlanOutput {
+        total_issues: plan.iter().map(|a| a.triggered_by.len()).sum(),
+        review_actions: actions.len() - safe_actions,
+        safe_actions,
+        actions,
+        verification: None,
+    };
+    Ok(serde_json::to_string_pretty(&output)? + "\n")
+}
+
+/// Render `plan` in `format`
+pub fn render(
+    plan: &[RepairAction],
+    context: &RepairContext,
+    format: EmitFormat,
+) -> Result<String> {
+    match format {
+        EmitFormat::Sh => Ok(render_sh(plan)),
+        EmitFormat::Powershell => Ok(render_powershell(plan)),
+        EmitFormat::Makefile => Ok(render_makefile(plan)),
+        EmitFormat::GithubActions => render_github_actions(plan, context),
+        EmitFormat::Json => render_json(plan),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::CheckResult;
+    use crate::repair::generate_repair_plan_with_context;
+
+    fn plan(context: &RepairContext) -> Vec<RepairAction> {
+        let issues = [
+            CheckResult::error(
+                "Node version match",
+                "toolchain",
+                "Expected 20.11.0 but found 18.19.0",
+            ),
+            CheckResult::error("node_modules exists", "deps", "node_modules not found"),
+            CheckResult::warning("Prettier config", "frameworks", "No config; 100% defaults"),
+        ];
+        let issues: Vec<&CheckResult> = issues.iter().collect();
+        generate_repair_plan_with_context(&issues, context).unwrap()
+    }
+
+    #[test]
+    fn test_emit_scripts_use_version_manager() {
+        let context = RepairContext::new("pnpm")
+            .with_node_version_manager(Some("nvm".to_string()))
+            .with_target_node_version(Some("20.11.0".to_string()));
+        let plan = plan(&context);
+
+        let sh = render(&plan, &context, EmitFormat::Sh).unwrap();
+        assert!(sh.starts_with("#!/bin/sh\n"));
+        assert!(sh.contains("nvm use 20.11.0\n"));
+        assert!(sh.contains("pnpm install --frozen-lockfile\n"));
+        assert!(sh.find("nvm use").unwrap() < sh.find("pnpm install").unwrap());
+        assert!(sh.contains("printf '{}\\n' > .prettierrc\n"));
+
+        let ps = render(&plan, &context, EmitFormat::Powershell).unwrap();
+        assert!(ps.contains("& 'pnpm' 'install' '--frozen-lockfile'\n"));
+        assert!(ps.contains("if ($LASTEXITCODE -ne 0) { exit $LASTEXITCODE }"));
+
+        let make = render(&plan, &context, EmitFormat::Makefile).unwrap();
+        assert!(make.contains("NODE_ENV_SETUP = . \"$${NVM_DIR:-$$HOME/.nvm}/nvm.sh\""));
+        assert!(make.contains("\t$(NODE_ENV_SETUP) pnpm install --frozen-lockfile\n"));
+        assert!(make.contains("step-2: step-1\n"));
+    }
+
+    #[test]
+    fn test_emit_github_actions_uses_setup_node() {
+        let context = RepairContext::new("npm")
+            .with_node_version_manager(Some("volta".to_string()))
+            .with_target_node_version(Some("20.11.0".to_string()));
+        let plan = plan(&context);
+
+        let workflow = render(&plan, &context, EmitFormat::GithubActions).unwrap();
+        let yaml: serde_yaml::Value = serde_yaml::from_str(&workflow).unwrap();
+        let steps = yaml["jobs"]["repair"]["steps"].as_sequence().unwrap();
+        assert_eq!(steps[1]["uses"].as_str(), Some("actions/setup-node@v4"));
+        assert_eq!(steps[1]["with"]["node-version"].as_str(), Some("20.11.0"));
+        assert!(!workflow.contains("volta"));
+        assert!(steps.iter().any(|s| s["run"].as_str() == Some("npm ci")));
+
+        let json = render(&plan, &context, EmitFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["actions"][0]["steps"][0]["argv"][1], "pin");
+    }
+}
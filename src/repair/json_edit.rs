@@ -0,0 +1,347 @@
+//! Editing JSON files without reformatting them
+//!
+//! Round-tripping package.json through `serde_json::Value` sorts its keys and rewrites
+//! its whitespace. Instead, the member being changed is located in the source text and
+//! only its value is replaced (or the member inserted or removed), so key order,
+//! indentation and everything else stay as the user wrote them.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+/// Position of an object member in the source
+struct Member {
+    key: String,
+    /// Offset of the opening quote of the key
+    key_start: usize,
+    value_start: usize,
+    value_end: usize,
+}
+
+/// Position of an object in the source
+struct Object {
+    /// Offset of `{`
+    open: usize,
+    /// Offset of `}`
+    close: usize,
+    members: Vec<Member>,
+}
+
+struct Scanner<'a> {
+    src: &'a [u8],
+}
+
+impl Scanner<'_> {
+    fn skip_ws(&self, mut i: usize) -> usize {
+        while i < self.src.len() && self.src[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        i
+    }
+
+    /// End of the string starting at `i` (just past the closing quote)
+    fn string_end(&self, i: usize) -> Result<usize> {
+        let mut j = i + 1;
+        while j < self.src.len() {
+            match self.src[j] {
+                b'\\' => j += 2,
+                b'"' => return Ok(j + 1),
+                _ => j += 1,
+            }
+        }
+        bail!("Unterminated string")
+    }
+
+    /// End of the value starting at `i`
+    fn value_end(&self, i: usize) -> Result<usize> {
+        match self.src.get(i) {
+            Some(b'"') => self.string_end(i),
+            Some(b'{') | Some(b'[') => {
+                let mut depth = 0usize;
+                let mut j = i;
+                while j < self.src.len() {
+                    match self.src[j] {
+                        b'"' => {
+                            j = self.string_end(j)?;
+                            continue;
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return Ok(j + 1);
+                            }
+                        }
+                        _ => {}
+                    }
+                    j += 1;
+                }
+                bail!("Unterminated object or array")
+            }
+            Some(_) => {
+                let mut j = i;
+                while j < self.src.len()
+                    && !matches!(self.src[j], b',' | b'}' | b']')
+                    && !self.src[j].is_ascii_whitespace()
+                {
+                    j += 1;
+                }
+                Ok(j)
+            }
+            None => bail!("Unexpected end of input"),
+        }
+    }
+
+    /// Members of the object starting at `open`
+    fn object(&self, open: usize) -> Result<Object> {
+        let mut members = Vec::new();
+        let mut i = self.skip_ws(open + 1);
+        loop {
+            match self.src.get(i) {
+                Some(b'}') => {
+                    return Ok(Object {
+                        open,
+                        close: i,
+                        members,
+                    })
+                }
+                Some(b',') => i = self.skip_ws(i + 1),
+                Some(b'"') => {
+                    let key_end = self.string_end(i)?;
+                    let key: String = serde_json::from_slice(&self.src[i..key_end])?;
+                    let colon = self.skip_ws(key_end);
+                    if self.src.get(colon) != Some(&b':') {
+                        bail!("Expected ':' after \"{}\"", key);
+                    }
+                    let value_start = self.skip_ws(colon + 1);
+                    let value_end = self.value_end(value_start)?;
+                    members.push(Member {
+                        key,
+                        key_start: i,
+                        value_start,
+                        value_end,
+                    });
+                    i = self.skip_ws(value_end);
+                }
+                _ => bail!("Malformed object at offset {}", i),
+            }
+        }
+    }
+}
+
+/// Indentation of the line containing `offset`
+fn line_indent(src: &str, offset: usize) -> &str {
+    let line_start = src[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = &src[line_start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// One level of indentation used in the file
+fn indent_unit(src: &str) -> &'static str {
+    if src.contains("\n\t") {
+        "\t"
+    } else if src.contains("\n    \"") && !src.contains("\n  \"") {
+        "    "
+    } else {
+        "  "
+    }
+}
+
+/// `value` pretty-printed with `unit`, continuation lines indented by `indent`
+fn render(value: &serde_json::Value, unit: &str, indent: &str) -> Result<String> {
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(unit.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    value.serialize(&mut serializer)?;
+    let text = String::from_utf8(out)?;
+    Ok(text.replace('\n', &format!("\n{}", indent)))
+}
+
+/// Segments of a dotted key, where `\.` is a dot within a segment
+fn split_key(key: &str) -> Vec<String> {
+    let mut segments = vec![String::new()];
+    let mut chars = key.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'.') => {
+                chars.next();
+                segments.last_mut().unwrap().push('.');
+            }
+            '.' => segments.push(String::new()),
+            c => segments.last_mut().unwrap().push(c),
+        }
+    }
+    segments
+}
+
+/// `segment` escaped for use in a dotted key (`socket.io` -> `socket\.io`)
+pub fn escape_key(segment: &str) -> String {
+    segment.replace('.', "\\.")
+}
+
+/// Set the dotted `key` to `value` in the JSON text `src`, or remove it when `value` is
+/// null, changing nothing else
+pub fn set(src: &str, key: &str, value: &serde_json::Value) -> Result<String> {
+    // Only edit documents that parse
+    serde_json::from_str::<serde_json::Value>(src).context("Invalid JSON")?;
+
+    let scanner = Scanner {
+        src: src.as_bytes(),
+    };
+    let unit = indent_unit(src);
+    let segments = split_key(key);
+    if segments.iter().any(|s| s.is_empty()) {
+        bail!("Invalid JSON key '{}'", key);
+    }
+
+    let root = scanner.skip_ws(0);
+    if src.as_bytes().get(root) != Some(&b'{') {
+        bail!("Cannot set {}: the document is not an object", key);
+    }
+    let mut object = scanner.object(root)?;
+    for (depth, segment) in segments.iter().enumerate() {
+        let is_last = depth + 1 == segments.len();
+        let Some(index) = object.members.iter().position(|m| m.key == *segment) else {
+            if value.is_null() {
+                return Ok(src.to_string());
+            }
+            // Build whatever is missing below this point
+            let mut nested = value.clone();
+            for missing in segments[depth + 1..].iter().rev() {
+                nested = serde_json::json!({ missing.as_str(): nested });
+            }
+            return insert(src, &object, segment, &nested, unit);
+        };
+        let member = &object.members[index];
+
+        if is_last {
+            if value.is_null() {
+                return Ok(remove(src, &object, index));
+            }
+            let indent = line_indent(src, member.key_start);
+            let rendered = render(value, unit, indent)?;
+            return Ok(format!(
+                "{}{}{}",
+                &src[..member.value_start],
+                rendered,
+                &src[member.value_end..]
+            ));
+        }
+
+        if src.as_bytes()[member.value_start] != b'{' {
+            bail!("{} is not an object", segment);
+        }
+        object = scanner.object(member.value_start)?;
+    }
+    unreachable!("the last segment always returns")
+}
+
+fn insert(
+    src: &str,
+    object: &Object,
+    key: &str,
+    value: &serde_json::Value,
+    unit: &str,
+) -> Result<String> {
+    let key = serde_json::to_string(key)?;
+    match object.members.last() {
+        Some(last) => {
+            let indent = line_indent(src, last.key_start);
+            let rendered = render(value, unit, indent)?;
+            // Same line as the others for `{ "a": 1 }`, a new line otherwise
+            let separator = if src[object.open..last.key_start].contains('\n') {
+                format!(",\n{}", indent)
+            } else {
+                ", ".to_string()
+            };
+            Ok(format!(
+                "{}{}{}: {}{}",
+                &src[..last.value_end],
+                separator,
+                key,
+                rendered,
+                &src[last.value_end..]
+            ))
+        }
+        None => {
+            let outer = line_indent(src, object.open);
+            let indent = format!("{}{}", outer, unit);
+            let rendered = render(value, unit, &indent)?;
+            Ok(format!(
+                "{}\n{}{}: {}\n{}{}",
+                &src[..object.open + 1],
+                indent,
+                key,
+                rendered,
+                outer,
+                &src[object.close..]
+            ))
+        }
+    }
+}
+
+fn remove(src: &str, object: &Object, index: usize) -> String {
+    let members = &object.members;
+    let (start, end) = if index > 0 {
+        // From the end of the previous member, taking the comma with it
+        (members[index - 1].value_end, members[index].value_end)
+    } else if let Some(next) = members.get(1) {
+        (members[0].key_start, next.key_start)
+    } else {
+        // The only member; leave an empty object
+        (object.open + 1, object.close)
+    };
+    format!("{}{}", &src[..start], &src[end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PACKAGE: &str = r#"{
+  "name": "app",
+  "version": "1.0.0",
+  "engines": {
+    "node": ">=18"
+  },
+  "dependencies": { "react": "^18.2.0" }
+}
+"#;
+
+    #[test]
+    fn test_set_preserves_formatting() {
+        let out = set(PACKAGE, "engines.node", &json!(">=20.11.0")).unwrap();
+        assert_eq!(out, PACKAGE.replace(">=18", ">=20.11.0"));
+
+        let out = set(PACKAGE, "volta.node", &json!("20.11.0")).unwrap();
+        assert!(out.ends_with(
+            "\"dependencies\": { \"react\": \"^18.2.0\" },\n  \"volta\": {\n    \"node\": \"20.11.0\"\n  }\n}\n"
+        ));
+
+        let out = set(PACKAGE, "dependencies.zod", &json!("^3.0.0")).unwrap();
+        assert!(out.contains(r#"{ "react": "^18.2.0", "zod": "^3.0.0" }"#));
+
+        let key = format!("dependencies.{}", escape_key("socket.io"));
+        let out = set(PACKAGE, &key, &json!("^4.7.0")).unwrap();
+        assert!(out.contains(r#""socket.io": "^4.7.0""#));
+
+        let out = set("{}\n", "packageManager", &json!("pnpm@9.1.0")).unwrap();
+        assert_eq!(out, "{\n  \"packageManager\": \"pnpm@9.1.0\"\n}\n");
+    }
+
+    #[test]
+    fn test_remove_member() {
+        let out = set(PACKAGE, "version", &serde_json::Value::Null).unwrap();
+        assert_eq!(out, PACKAGE.replace("\n  \"version\": \"1.0.0\",", ""));
+
+        let out = set(PACKAGE, "engines.node", &serde_json::Value::Null).unwrap();
+        assert!(out.contains("\"engines\": {}"));
+
+        // Removing something that isn't there changes nothing
+        assert_eq!(
+            set(PACKAGE, "volta.node", &serde_json::Value::Null).unwrap(),
+            PACKAGE
+        );
+        assert!(set(PACKAGE, "name.first", &json!("x")).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! Choosing which lockfile to keep when a project has several
+//!
+//! Each signal votes for a package manager: the `packageManager` field, the lockfile type
+//! recorded in env.lock, the layout of the installed node_modules, the lockfile committed
+//! most recently and the one modified most recently. The lockfile with the most weight
+//! behind it is kept; a tie is left to the user. Every vote is kept as a reason so the
+//! plan can show why.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+/// Lockfiles and the package manager that writes each
+pub const LOCKFILES: &[(&str, &str)] = &[
+    ("package-lock.json", "npm"),
+    ("yarn.lock", "yarn"),
+    ("pnpm-lock.yaml", "pnpm"),
+    ("bun.lockb", "bun"),
+];
+
+/// Files a package manager leaves in node_modules after installing
+const NODE_MODULES_MARKERS: &[(&str, &str)] = &[
+    ("node_modules/.pnpm", "pnpm"),
+    ("node_modules/.yarn-state.yml", "yarn"),
+    ("node_modules/.package-lock.json", "npm"),
+];
+
+const PACKAGE_MANAGER_WEIGHT: u32 = 4;
+const NODE_MODULES_WEIGHT: u32 = 3;
+const ENV_LOCK_WEIGHT: u32 = 2;
+const GIT_WEIGHT: u32 = 2;
+const MTIME_WEIGHT: u32 = 1;
+
+/// The lockfile to keep, the ones to remove and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockfileChoice {
+    /// `None` when the signals don't agree on one
+    pub keep: Option<&'static str>,
+    pub remove: Vec<&'static str>,
+    pub reasons: Vec<String>,
+}
+
+/// One signal's vote
+struct Vote {
+    manager: &'static str,
+    weight: u32,
+    reason: String,
+}
+
+/// Lockfile written by `manager`
+pub fn lockfile_for(manager: &str) -> Option<&'static str> {
+    LOCKFILES
+        .iter()
+        .find(|(_, m)| *m == manager)
+        .map(|(f, _)| *f)
+}
+
+fn manager_of(name: &str) -> Option<&'static str> {
+    LOCKFILES.iter().find(|(_, m)| *m == name).map(|(_, m)| *m)
+}
+
+/// Manager named by package.json `packageManager` (`pnpm@9.1.0` -> `pnpm`)
+fn package_manager_field(root: &Path) -> Option<String> {
+    let content = fs::read_to_string(root.join("package.json")).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let spec = json.get("packageManager")?.as_str()?;
+    Some(spec.split('@').next().unwrap_or(spec).trim().to_string())
+}
+
+/// Unix time of the last commit touching `file`, if git knows it
+fn last_commit_time(root: &Path, file: &str) -> Option<i64> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%ct", "--", file])
+        .current_dir(root)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+fn modified(root: &Path, file: &str) -> Option<SystemTime> {
+    fs::metadata(root.join(file)).ok()?.modified().ok()
+}
+
+/// The single lockfile with the greatest `key`, if there is one
+fn latest<T: Ord + Copy>(
+    present: &[(&'static str, &'static str)],
+    key: impl Fn(&str) -> Option<T>,
+) -> Option<(&'static str, &'static str)> {
+    let mut keyed: Vec<(T, (&'static str, &'static str))> = present
+        .iter()
+        .filter_map(|&(file, manager)| key(file).map(|k| (k, (file, manager))))
+        .collect();
+    keyed.sort_by_key(|k| std::cmp::Reverse(k.0));
+    match keyed.as_slice() {
+        [(first, lockfile), rest @ ..] if rest.first().map_or(true, |(k, _)| k < first) => {
+            Some(*lockfile)
+        }
+        _ => None,
+    }
+}
+
+/// Decide which of the lockfiles in `root` to keep
+///
+/// `lockfile_type` is the type recorded in env.lock. Returns `None` unless there is more
+/// than one lockfile.
+pub fn choose(root: &Path, lockfile_type: Option<&str>) -> Option<LockfileChoice> {
+    let present: Vec<(&'static str, &'static str)> = LOCKFILES
+        .iter()
+        .copied()
+        .filter(|(file, _)| root.join(file).is_file())
+        .collect();
+    if present.len() < 2 {
+        return None;
+    }
+    let is_present = |manager: &str| present.iter().any(|(_, m)| *m == manager);
+
+    let mut votes = Vec::new();
+    let mut reasons = Vec::new();
+
+    match package_manager_field(root) {
+        Some(name) => match manager_of(&name) {
+            Some(manager) if is_present(manager) => votes.push(Vote {
+                manager,
+                weight: PACKAGE_MANAGER_WEIGHT,
+                reason: format!("package.json sets packageManager to {}", name),
+            }),
+            _ => reasons.push(format!(
+                "package.json sets packageManager to {}, which has no lockfile here",
+                name
+            )),
+        },
+        None => reasons.push("package.json has no packageManager field".to_string()),
+    }
+
+    if let Some(manager) = lockfile_type.and_then(manager_of).filter(|m| is_present(m)) {
+        votes.push(Vote {
+            manager,
+            weight: ENV_LOCK_WEIGHT,
+            reason: format!(
+                "env.lock was created from {}",
+                lockfile_for(manager).unwrap_or(manager)
+            ),
+        });
+    }
+
+    for &(marker, manager) in NODE_MODULES_MARKERS {
+        if is_present(manager) && root.join(marker).exists() {
+            votes.push(Vote {
+                manager,
+                weight: NODE_MODULES_WEIGHT,
+                reason: format!("node_modules was installed by {} ({})", manager, marker),
+            });
+            break;
+        }
+    }
+
+    if let Some((file, manager)) = latest(&present, |f| last_commit_time(root, f)) {
+        votes.push(Vote {
+            manager,
+            weight: GIT_WEIGHT,
+            reason: format!("{} is the most recently committed lockfile", file),
+        });
+    }
+
+    if let Some((file, manager)) = latest(&present, |f| modified(root, f)) {
+        votes.push(Vote {
+            manager,
+            weight: MTIME_WEIGHT,
+            reason: format!("{} is the most recently modified lockfile", file),
+        });
+    }
+
+    let score = |manager: &str| -> u32 {
+        votes
+            .iter()
+            .filter(|v| v.manager == manager)
+            .map(|v| v.weight)
+            .sum()
+    };
+    let mut ranked: Vec<(&'static str, &'static str)> = present.clone();
+    ranked.sort_by_key(|(_, m)| std::cmp::Reverse(score(m)));
+    let (best_file, best_manager) = ranked[0];
+    let decided = score(best_manager) > 0 && score(best_manager) > score(ranked[1].1);
+
+    // Votes for the winner first, then the ones it overruled
+    votes.sort_by_key(|v| v.manager != best_manager);
+    for vote in votes {
+        if decided && vote.manager != best_manager {
+            reasons.push(format!("{} (outweighed)", vote.reason));
+        } else {
+            reasons.push(vote.reason);
+        }
+    }
+
+    let files: Vec<&'static str> = present.iter().map(|(f, _)| *f).collect();
+    Some(if decided {
+        LockfileChoice {
+            keep: Some(best_file),
+            remove: files.into_iter().filter(|f| *f != best_file).collect(),
+            reasons,
+        }
+    } else {
+        reasons.push(format!(
+            "The signals don't agree on one of {}",
+            files.join(", ")
+        ));
+        LockfileChoice {
+            keep: None,
+            remove: Vec::new(),
+            reasons,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_choose_weighs_signals() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("package-lock.json"), "{}").unwrap();
+        fs::write(root.join("pnpm-lock.yaml"), "lockfileVersion: '9.0'\n").unwrap();
+        fs::write(
+            root.join("package.json"),
+            r#"{"packageManager": "pnpm@9.1.0"}"#,
+        )
+        .unwrap();
+
+        // packageManager outweighs env.lock
+        let choice = choose(root, Some("npm")).unwrap();
+        assert_eq!(choice.keep, Some("pnpm-lock.yaml"));
+        assert_eq!(choice.remove, vec!["package-lock.json"]);
+        assert_eq!(
+            choice.reasons[0],
+            "package.json sets packageManager to pnpm"
+        );
+        assert!(choice
+            .reasons
+            .contains(&"env.lock was created from package-lock.json (outweighed)".to_string()));
+
+        // ...but not packageManager and env.lock against an install by pnpm
+        fs::write(
+            root.join("package.json"),
+            r#"{"packageManager": "npm@10.2.0"}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("node_modules/.pnpm")).unwrap();
+        let choice = choose(root, Some("npm")).unwrap();
+        assert_eq!(choice.keep, Some("package-lock.json"));
+        assert!(choice.reasons.contains(
+            &"node_modules was installed by pnpm (node_modules/.pnpm) (outweighed)".to_string()
+        ));
+
+        fs::remove_file(root.join("pnpm-lock.yaml")).unwrap();
+        assert_eq!(choose(root, Some("npm")), None);
+    }
+}
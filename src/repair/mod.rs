@@ -1,13 +1,60 @@
+pub mod backup;
+pub mod emit;
+mod json_edit;
+pub mod lockfiles;
+pub mod phantom;
+pub mod pin;
+pub mod plan;
+pub mod runner;
+pub mod step;
+pub mod verify;
+
 use anyhow::Result;
-use std::process::Command;
+use std::path::Path;
 
 use crate::checks::CheckResult;
+use crate::config::{ImportsConfig, NodePinFile, ZenvoConfig};
+use crate::lockfile::EnvLock;
+
+pub use backup::Snapshot;
+pub use plan::RepairTarget;
+pub use runner::{ActionRun, RunOptions, StepStatus};
+pub use step::RepairStep;
+pub use verify::{IssueVerdict, VerifiedIssue};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct RepairAction {
     pub description: String,
-    pub command: String,
+    pub steps: Vec<RepairStep>,
     pub is_safe: bool,
+    /// What the action fixes
+    pub provides: Vec<RepairTarget>,
+    /// What has to be fixed before it runs
+    pub requires: Vec<RepairTarget>,
+    /// What it breaks again and has to be redone afterwards
+    pub invalidates: Vec<RepairTarget>,
+    /// Positions of the earlier actions in the plan it depends on (filled in by the planner)
+    pub after: Vec<usize>,
+    /// Issues the action is meant to fix (empty for follow-ups)
+    pub triggered_by: Vec<CheckResult>,
+    /// Why zenvo chose these steps, shown with the plan
+    pub reasons: Vec<String>,
+}
+
+impl RepairAction {
+    /// Shell-like rendering of every step, for display
+    pub fn command(&self) -> String {
+        self.steps
+            .iter()
+            .map(|s| s.preview())
+            .collect::<Vec<_>>()
+            .join(" && ")
+    }
+
+    /// Whether every step has to be done by hand
+    pub fn is_manual(&self) -> bool {
+        self.steps.iter().all(|s| s.is_manual())
+    }
 }
 
 /// Context for generating repair actions
@@ -15,21 +62,41 @@ pub struct RepairAction {
 pub struct RepairContext {
     /// Current package manager (npm, yarn, pnpm, bun)
     pub package_manager: String,
+    /// Version of the package manager from env.lock
+    pub package_manager_version: Option<String>,
     /// Node version manager if detected (volta, fnm, nvm, system)
     pub node_version_manager: Option<String>,
     /// Target Node version from env.lock
     pub target_node_version: Option<String>,
+    /// Files to pin the target Node version in (see `pin::pins_to_write`)
+    pub node_pin_files: Vec<NodePinFile>,
+    /// Lockfile type recorded in env.lock (npm, yarn, pnpm, bun)
+    pub lockfile_type: Option<String>,
+    /// Packages never to remove as unused (`checks.unused_ignore`)
+    pub unused_ignore: Vec<String>,
+    /// Where and how to look for imports (`checks.imports`)
+    pub imports: ImportsConfig,
 }
 
 impl RepairContext {
     pub fn new(package_manager: &str) -> Self {
         Self {
             package_manager: package_manager.to_string(),
+            package_manager_version: None,
             node_version_manager: None,
             target_node_version: None,
+            node_pin_files: Vec::new(),
+            lockfile_type: None,
+            unused_ignore: Vec::new(),
+            imports: ImportsConfig::default(),
         }
     }
 
+    pub fn with_package_manager_version(mut self, version: Option<String>) -> Self {
+        self.package_manager_version = version.filter(|v| !v.is_empty());
+        self
+    }
+
     pub fn with_node_version_manager(mut self, manager: Option<String>) -> Self {
         self.node_version_manager = manager;
         self
@@ -40,56 +107,187 @@ impl RepairContext {
         self
     }
 
+    pub fn with_node_pin_files(mut self, pins: Vec<NodePinFile>) -> Self {
+        self.node_pin_files = pins;
+        self
+    }
+
+    pub fn with_lockfile_type(mut self, lockfile_type: Option<String>) -> Self {
+        self.lockfile_type = lockfile_type;
+        self
+    }
+
+    pub fn with_unused_ignore(mut self, ignore: Vec<String>) -> Self {
+        self.unused_ignore = ignore;
+        self
+    }
+
+    pub fn with_imports(mut self, imports: ImportsConfig) -> Self {
+        self.imports = imports;
+        self
+    }
+
+    /// Context for repairing the current directory towards `env_lock`
+    pub fn from_env(env_lock: &EnvLock, config: &Option<ZenvoConfig>) -> Self {
+        let checks = config.as_ref().map(|c| &c.checks);
+        Self::new(&env_lock.toolchain.package_manager)
+            .with_package_manager_version(Some(env_lock.toolchain.package_manager_version.clone()))
+            .with_node_version_manager(env_lock.toolchain.node_version_source.clone())
+            .with_target_node_version(Some(env_lock.toolchain.node.clone()))
+            .with_node_pin_files(pin::pins_to_write(
+                Path::new("."),
+                config.as_ref().and_then(|c| c.repair.node_pin_files.as_deref()),
+            ))
+            .with_lockfile_type(env_lock.lockfile.as_ref().map(|l| l.lockfile_type.clone()))
+            .with_unused_ignore(checks.map(|c| c.unused_ignore.clone()).unwrap_or_default())
+            .with_imports(checks.map(|c| c.imports.clone()).unwrap_or_default())
+    }
+
     /// Get the install command for the current package manager
-    pub fn install_command(&self) -> &'static str {
+    pub fn install_command(&self) -> &'static [&'static str] {
         match self.package_manager.as_str() {
-            "pnpm" => "pnpm install --frozen-lockfile",
-            "yarn" => "yarn install --frozen-lockfile",
-            "bun" => "bun install --frozen-lockfile",
-            _ => "npm ci",
+            "pnpm" => &["pnpm", "install", "--frozen-lockfile"],
+            "yarn" => &["yarn", "install", "--frozen-lockfile"],
+            "bun" => &["bun", "install", "--frozen-lockfile"],
+            _ => &["npm", "ci"],
         }
     }
 
     /// Get the install command (without frozen lockfile - for generating lockfile)
-    pub fn install_command_no_frozen(&self) -> &'static str {
+    pub fn install_command_no_frozen(&self) -> &'static [&'static str] {
         match self.package_manager.as_str() {
-            "pnpm" => "pnpm install",
-            "yarn" => "yarn install",
-            "bun" => "bun install",
-            _ => "npm install",
+            "pnpm" => &["pnpm", "install"],
+            "yarn" => &["yarn", "install"],
+            "bun" => &["bun", "install"],
+            _ => &["npm", "install"],
         }
     }
 
-    /// Get the command to switch Node version
-    pub fn node_switch_command(&self, version: &str) -> String {
+    /// Get the steps to switch Node version
+    ///
+    /// nvm and fnm only change the Node of the calling shell, so they can't be run for
+    /// the user; `volta pin` records the version in package.json and can.
+    pub fn node_switch_steps(&self, version: &str) -> Vec<RepairStep> {
         match self.node_version_manager.as_deref() {
-            Some("volta") => format!("volta pin node@{}", version),
-            Some("fnm") => format!("fnm use {}", version),
-            Some("nvm") => format!("nvm use {}", version),
-            _ => {
-                // Default to nvm if no manager detected, but mention alternatives
-                format!("nvm use {} (or volta pin node@{} / fnm use {})", version, version, version)
-            }
+            Some("volta") => vec![RepairStep::exec(&["volta", "pin", &format!("node@{}", version)])],
+            Some(manager @ ("nvm" | "fnm")) => vec![RepairStep::UseNode {
+                version: version.to_string(),
+                manager: manager.to_string(),
+            }],
+            _ => vec![RepairStep::manual(format!(
+                "Switch to Node.js {} with your version manager (`nvm use {}`, `fnm use {}` or `volta pin node@{}`)",
+                version, version, version, version
+            ))],
         }
     }
 
-    /// Get commands to clear package manager caches
-    pub fn clear_cache_commands(&self) -> Vec<(&'static str, &'static str)> {
+    /// Get the steps writing `version` into the project's Node pin files
+    ///
+    /// When Volta manages Node, `volta pin` already writes `volta.node`.
+    pub fn node_pin_steps(&self, version: &str) -> Vec<RepairStep> {
+        let volta = self.node_version_manager.as_deref() == Some("volta");
+        let pins: Vec<NodePinFile> = self
+            .node_pin_files
+            .iter()
+            .copied()
+            .filter(|p| !(volta && *p == NodePinFile::Volta))
+            .collect();
+        pin::pin_steps(Path::new("."), &pins, version)
+    }
+
+    /// Pin `version` in the project files, if any of them needs it
+    pub fn node_pin_action(&self, version: &str) -> Option<RepairAction> {
+        let steps = self.node_pin_steps(version);
+        if steps.is_empty() {
+            return None;
+        }
+        let mut files: Vec<String> = steps
+            .iter()
+            .filter_map(|s| s.touched_path())
+            .map(|p| p.display().to_string())
+            .collect();
+        files.dedup();
+        // package.json is published with the package, so editing it needs a confirmation
+        let edits_package_json = files.iter().any(|f| f == "package.json");
+        Some(RepairAction {
+            description: format!("Pin Node.js {} in {}", version, files.join(", ")),
+            steps,
+            is_safe: !edits_package_json,
+            ..Default::default()
+        })
+    }
+
+    /// Get the step to clear the package manager cache, and whether it's safe to run unasked
+    pub fn clear_cache_step(&self) -> (&'static str, RepairStep, bool) {
         match self.package_manager.as_str() {
-            "pnpm" => vec![
-                ("Clear pnpm cache", "pnpm store prune"),
-            ],
-            "yarn" => vec![
-                ("Clear yarn cache", "yarn cache clean"),
+            "pnpm" => ("Clear pnpm cache", RepairStep::exec(&["pnpm", "store", "prune"]), true),
+            "yarn" => ("Clear yarn cache", RepairStep::exec(&["yarn", "cache", "clean"]), true),
+            "bun" => {
+                let cache = dirs::home_dir()
+                    .unwrap_or_else(|| "~".into())
+                    .join(".bun/install/cache");
+                ("Clear bun cache", RepairStep::remove(cache), false)
+            }
+            _ => ("Clear npm cache", RepairStep::exec(&["npm", "cache", "clean", "--force"]), true),
+        }
+    }
+
+    /// Remove node_modules and install again from the lockfile
+    pub fn reinstall_action(&self) -> RepairAction {
+        RepairAction {
+            description: format!("Reinstall dependencies using {}", self.package_manager),
+            steps: vec![
+                RepairStep::remove("node_modules"),
+                RepairStep::exec(self.install_command()),
             ],
-            "bun" => vec![
-                ("Clear bun cache (manual)", "rm -rf ~/.bun/install/cache"),
+            is_safe: true,
+            provides: vec![RepairTarget::NodeModules],
+            requires: vec![
+                RepairTarget::Node,
+                RepairTarget::PackageManager,
+                RepairTarget::Cache,
+                RepairTarget::Lockfile,
             ],
-            _ => vec![
-                ("Clear npm cache", "npm cache clean --force"),
+            ..Default::default()
+        }
+    }
+
+    /// Regenerate env.lock from the current environment
+    pub fn relock_action(&self) -> RepairAction {
+        RepairAction {
+            description: "Update env.lock to match current lockfile".to_string(),
+            steps: vec![RepairStep::exec(&["zenvo", "lock"])],
+            is_safe: true,
+            provides: vec![RepairTarget::EnvLock],
+            requires: vec![
+                RepairTarget::Node,
+                RepairTarget::PackageManager,
+                RepairTarget::Lockfile,
+                RepairTarget::NodeModules,
             ],
+            ..Default::default()
+        }
+    }
+
+    /// Action that redoes `target` after another action invalidated it
+    pub fn follow_up(&self, target: RepairTarget) -> Option<RepairAction> {
+        match target {
+            RepairTarget::NodeModules => Some(self.reinstall_action()),
+            RepairTarget::EnvLock => Some(self.relock_action()),
+            _ => None,
         }
     }
+
+    /// `<pm> <args>` run through the package manager's one-off runner
+    fn run_tool(&self, args: &[&str]) -> RepairStep {
+        let runner: &[&str] = match self.package_manager.as_str() {
+            "pnpm" => &["pnpm", "exec"],
+            "yarn" => &["yarn"],
+            "bun" => &["bun", "x"],
+            _ => &["npx"],
+        };
+        RepairStep::exec(&[runner, args].concat())
+    }
 }
 
 /// Generate repair plan with context (preferred method)
@@ -100,15 +298,24 @@ pub fn generate_repair_plan_with_context(
     let mut actions = Vec::new();
 
     for issue in issues {
-        if let Some(action) = issue_to_action_with_context(issue, context) {
+        if let Some(mut action) = issue_to_action_with_context(issue, context) {
+            action.triggered_by.push((*issue).clone());
             actions.push(action);
         }
-    }
 
-    // Sort: safe actions first
-    actions.sort_by(|a, b| b.is_safe.cmp(&a.is_safe));
+        // Switching only fixes one shell; the pin files keep every tool on the version
+        if issue.name == "Node version match" {
+            let version = extract_target_version(&issue.message)
+                .or_else(|| context.target_node_version.clone());
+            if let Some(mut action) = version.and_then(|v| context.node_pin_action(&v)) {
+                action.triggered_by.push((*issue).clone());
+                actions.push(action);
+            }
+        }
+    }
 
-    Ok(actions)
+    // Drop overlapping actions and order the rest by what they depend on
+    Ok(plan::order(actions, context))
 }
 
 
@@ -123,137 +330,310 @@ fn issue_to_action_with_context(issue: &CheckResult, context: &RepairContext) ->
 
             Some(RepairAction {
                 description: format!("Switch Node.js to version {}", target_version),
-                command: context.node_switch_command(&target_version),
+                steps: context.node_switch_steps(&target_version),
                 is_safe: true,
+                provides: vec![RepairTarget::Node],
+                invalidates: vec![RepairTarget::NodeModules],
+                ..Default::default()
             })
         }
 
         "Package manager match" => Some(RepairAction {
             description: "Use correct package manager".to_string(),
-            command: issue.suggested_fix.clone().unwrap_or_else(|| {
+            steps: vec![RepairStep::manual(issue.suggested_fix.clone().unwrap_or_else(|| {
                 format!("Use {} instead", context.package_manager)
-            }),
+            }))],
             is_safe: true,
+            requires: vec![RepairTarget::Node],
+            ..Default::default()
         }),
 
         "node_modules exists" => Some(RepairAction {
             description: format!("Install dependencies using {}", context.package_manager),
-            command: context.install_command().to_string(),
+            steps: vec![RepairStep::exec(context.install_command())],
             is_safe: true,
+            provides: vec![RepairTarget::NodeModules],
+            requires: vec![
+                RepairTarget::Node,
+                RepairTarget::PackageManager,
+                RepairTarget::Cache,
+                RepairTarget::Lockfile,
+            ],
+            ..Default::default()
         }),
 
-        "node_modules in sync" | "node_modules integrity" => Some(RepairAction {
-            description: format!("Reinstall dependencies using {}", context.package_manager),
-            command: format!("rm -rf node_modules && {}", context.install_command()),
-            is_safe: true,
-        }),
+        "node_modules in sync" | "node_modules integrity" => Some(context.reinstall_action()),
 
         "Lockfile exists" => Some(RepairAction {
             // Need to regenerate lockfile - not safe
             description: format!("Generate lockfile using {}", context.package_manager),
-            command: context.install_command_no_frozen().to_string(),
+            steps: vec![RepairStep::exec(context.install_command_no_frozen())],
             is_safe: false,
+            provides: vec![RepairTarget::Lockfile, RepairTarget::NodeModules],
+            requires: vec![RepairTarget::Node, RepairTarget::PackageManager, RepairTarget::Cache],
+            invalidates: vec![RepairTarget::EnvLock],
+            ..Default::default()
         }),
 
-        "Lockfile integrity" | "Lockfile hash match" => Some(RepairAction {
-            description: "Update env.lock to match current lockfile".to_string(),
-            command: "zenvo lock".to_string(),
-            is_safe: true,
-        }),
+        "Lockfile integrity" | "Lockfile hash match" => Some(context.relock_action()),
 
         "Lockfile corrupted" => {
             // Need to regenerate lockfile - not safe
             Some(RepairAction {
                 description: format!("Regenerate corrupted lockfile using {}", context.package_manager),
-                command: format!("rm -f {} && {}",
-                    get_lockfile_name(&context.package_manager),
-                    context.install_command_no_frozen()
+                steps: vec![
+                    RepairStep::remove(get_lockfile_name(&context.package_manager)),
+                    RepairStep::exec(context.install_command_no_frozen()),
+                ],
+                is_safe: false,
+                provides: vec![RepairTarget::Lockfile, RepairTarget::NodeModules],
+                requires: vec![
+                    RepairTarget::Node,
+                    RepairTarget::PackageManager,
+                    RepairTarget::Cache,
+                ],
+                invalidates: vec![RepairTarget::EnvLock],
+                ..Default::default()
+            })
+        }
+
+        "Single lockfile" => {
+            let choice = lockfiles::choose(Path::new("."), context.lockfile_type.as_deref())?;
+            // env.lock has to be made again if it came from one of the removed lockfiles
+            let env_lock_stale = context
+                .lockfile_type
+                .as_deref()
+                .and_then(lockfiles::lockfile_for)
+                .is_some_and(|f| choice.remove.contains(&f));
+            match choice.keep {
+                // Deleting a lockfile loses its resolutions, so always ask
+                Some(keep) => Some(RepairAction {
+                    description: format!(
+                        "Keep {} and remove {}",
+                        keep,
+                        choice.remove.join(", ")
+                    ),
+                    steps: choice.remove.iter().map(|f| RepairStep::remove(*f)).collect(),
+                    is_safe: false,
+                    provides: vec![RepairTarget::Lockfile],
+                    invalidates: if env_lock_stale {
+                        vec![RepairTarget::EnvLock]
+                    } else {
+                        Vec::new()
+                    },
+                    reasons: choice.reasons,
+                    ..Default::default()
+                }),
+                None => Some(RepairAction {
+                    description: "Remove duplicate lockfiles".to_string(),
+                    steps: vec![RepairStep::manual(
+                        "Review and remove unused lockfile manually",
+                    )],
+                    is_safe: false,
+                    reasons: choice.reasons,
+                    ..Default::default()
+                }),
+            }
+        }
+
+        "Phantom dependencies" => {
+            let phantoms = crate::checks::dependencies::find_phantom_dependencies(
+                Path::new("."),
+                &context.imports,
+            )
+            .ok()??;
+            let fixes = phantom::phantom_fixes(Path::new("."), phantoms);
+            if fixes.is_empty() {
+                return None;
+            }
+            let mut steps: Vec<RepairStep> = fixes.iter().map(|f| f.step()).collect();
+            // Bring the lockfile in line with the new declarations
+            if steps.iter().any(|s| !s.is_manual()) {
+                steps.push(RepairStep::exec(context.install_command_no_frozen()));
+            }
+            Some(RepairAction {
+                description: format!(
+                    "Declare {} phantom {} in package.json",
+                    fixes.len(),
+                    if fixes.len() == 1 { "dependency" } else { "dependencies" }
                 ),
+                steps,
                 is_safe: false,
+                requires: vec![
+                    RepairTarget::Node,
+                    RepairTarget::PackageManager,
+                    RepairTarget::Cache,
+                    RepairTarget::Lockfile,
+                ],
+                invalidates: vec![RepairTarget::EnvLock],
+                reasons: fixes.iter().map(|f| f.reason()).collect(),
+                ..Default::default()
             })
         }
 
-        "Single lockfile" => Some(RepairAction {
-            // Requires manual review
-            description: "Remove duplicate lockfiles".to_string(),
-            command: "Review and remove unused lockfile manually".to_string(),
-            is_safe: false,
-        }),
+        "Unused dependencies" => {
+            let unused = crate::checks::unused::find_unused_dependencies(
+                Path::new("."),
+                &context.imports,
+                &context.unused_ignore,
+            )
+            .ok()??;
+            if unused.is_empty() {
+                return None;
+            }
+            let mut steps: Vec<RepairStep> = unused
+                .iter()
+                .map(|u| RepairStep::EditJson {
+                    path: "package.json".into(),
+                    key: format!("{}.{}", u.section, json_edit::escape_key(&u.name)),
+                    value: serde_json::Value::Null,
+                })
+                .collect();
+            steps.push(RepairStep::exec(context.install_command_no_frozen()));
+            Some(RepairAction {
+                description: format!(
+                    "Remove {} unused {} from package.json",
+                    unused.len(),
+                    if unused.len() == 1 { "dependency" } else { "dependencies" }
+                ),
+                steps,
+                // Usage zenvo can't see (dynamic requires, other tools) would break
+                is_safe: false,
+                requires: vec![
+                    RepairTarget::Node,
+                    RepairTarget::PackageManager,
+                    RepairTarget::Cache,
+                    RepairTarget::Lockfile,
+                ],
+                invalidates: vec![RepairTarget::EnvLock],
+                reasons: unused
+                    .iter()
+                    .map(|u| {
+                        format!(
+                            "{} ({}) isn't imported, run from scripts, named in config files or a peer of another dependency",
+                            u.name, u.section
+                        )
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+        }
 
         "npm cache integrity" | "Cache corrupted" => {
-            let cache_cmds = context.clear_cache_commands();
-            if let Some((desc, cmd)) = cache_cmds.first() {
-                // Manual commands (like bun) need user review - not safe
-                let is_safe = !desc.contains("manual");
-                Some(RepairAction {
-                    description: desc.to_string(),
-                    command: cmd.to_string(),
-                    is_safe,
-                })
-            } else {
-                None
-            }
+            let (description, step, is_safe) = context.clear_cache_step();
+            Some(RepairAction {
+                description: description.to_string(),
+                steps: vec![step],
+                is_safe,
+                provides: vec![RepairTarget::Cache],
+                requires: vec![RepairTarget::PackageManager],
+                ..Default::default()
+            })
         }
 
         "TypeScript config" => Some(RepairAction {
             description: "Initialize TypeScript config".to_string(),
-            command: match context.package_manager.as_str() {
-                "pnpm" => "pnpm exec tsc --init".to_string(),
-                "yarn" => "yarn tsc --init".to_string(),
-                "bun" => "bun x tsc --init".to_string(),
-                _ => "npx tsc --init".to_string(),
-            },
+            steps: vec![context.run_tool(&["tsc", "--init"])],
             is_safe: true,
+            provides: vec![RepairTarget::Tooling],
+            requires: vec![RepairTarget::NodeModules],
+            ..Default::default()
         }),
 
         "ESLint config" => Some(RepairAction {
             description: "Initialize ESLint config".to_string(),
-            command: match context.package_manager.as_str() {
-                "pnpm" => "pnpm create @eslint/config".to_string(),
-                "yarn" => "yarn create @eslint/config".to_string(),
-                "bun" => "bun create @eslint/config".to_string(),
-                _ => "npm init @eslint/config".to_string(),
-            },
+            steps: vec![match context.package_manager.as_str() {
+                "pnpm" => RepairStep::exec(&["pnpm", "create", "@eslint/config"]),
+                "yarn" => RepairStep::exec(&["yarn", "create", "@eslint/config"]),
+                "bun" => RepairStep::exec(&["bun", "create", "@eslint/config"]),
+                _ => RepairStep::exec(&["npm", "init", "@eslint/config"]),
+            }],
             is_safe: false,
+            provides: vec![RepairTarget::Tooling],
+            requires: vec![RepairTarget::NodeModules],
+            ..Default::default()
         }),
 
+        // Pin the package manager in package.json so Corepack picks it up
+        "Corepack available" if issue.message.contains("packageManager field not set") => {
+            let version = context.package_manager_version.as_deref()?;
+            let spec = format!("{}@{}", context.package_manager, version);
+            Some(RepairAction {
+                description: format!("Pin {} with Corepack", spec),
+                steps: vec![
+                    RepairStep::EditJson {
+                        path: "package.json".into(),
+                        key: "packageManager".to_string(),
+                        value: serde_json::Value::String(spec),
+                    },
+                    RepairStep::exec(&["corepack", "enable"]),
+                ],
+                is_safe: false,
+                provides: vec![RepairTarget::PackageManager],
+                requires: vec![RepairTarget::Node],
+                invalidates: vec![RepairTarget::EnvLock],
+                ..Default::default()
+            })
+        }
+
         "Corepack available" | "Corepack enabled" => Some(RepairAction {
             description: "Enable Corepack".to_string(),
-            command: "corepack enable".to_string(),
+            steps: vec![RepairStep::exec(&["corepack", "enable"])],
             is_safe: true,
+            provides: vec![RepairTarget::PackageManager],
+            requires: vec![RepairTarget::Node],
+            ..Default::default()
         }),
 
         "Prettier config" => Some(RepairAction {
             description: "Create Prettier config".to_string(),
-            command: "echo '{}' > .prettierrc".to_string(),
+            steps: vec![RepairStep::WriteFile {
+                path: ".prettierrc".into(),
+                contents: "{}\n".to_string(),
+            }],
             is_safe: true,
+            provides: vec![RepairTarget::Tooling],
+            ..Default::default()
         }),
 
         "Peer dependencies" => Some(RepairAction {
             description: "Install missing peer dependencies".to_string(),
-            command: match context.package_manager.as_str() {
-                "pnpm" => "pnpm install".to_string(),
-                "yarn" => "yarn install".to_string(),
-                "bun" => "bun install".to_string(),
-                _ => "npm install".to_string(),
-            },
+            steps: vec![RepairStep::exec(context.install_command_no_frozen())],
             is_safe: true,
+            provides: vec![RepairTarget::NodeModules],
+            requires: vec![
+                RepairTarget::Node,
+                RepairTarget::PackageManager,
+                RepairTarget::Lockfile,
+            ],
+            invalidates: vec![RepairTarget::EnvLock],
+            ..Default::default()
         }),
 
         // Package manager not accessible - provide installation instructions
         "npm accessible" | "yarn accessible" | "pnpm accessible" | "bun accessible" => {
             // Requires review as it installs globally - not safe
             let pm = issue.name.replace(" accessible", "");
+            // Use corepack for yarn/pnpm, or provide manual instructions
+            let steps = match pm.as_str() {
+                "yarn" => vec![
+                    RepairStep::exec(&["corepack", "enable"]),
+                    RepairStep::exec(&["corepack", "prepare", "yarn@stable", "--activate"]),
+                ],
+                "pnpm" => vec![
+                    RepairStep::exec(&["corepack", "enable"]),
+                    RepairStep::exec(&["corepack", "prepare", "pnpm@latest", "--activate"]),
+                ],
+                "bun" => vec![RepairStep::exec(&["npm", "install", "-g", "bun"])],
+                _ => vec![RepairStep::manual("npm is included with Node.js - reinstall Node.js")],
+            };
             Some(RepairAction {
                 description: format!("Install {} package manager", pm),
-                // Use corepack for yarn/pnpm, or provide manual instructions
-                command: match pm.as_str() {
-                    "yarn" => "corepack enable && corepack prepare yarn@stable --activate".to_string(),
-                    "pnpm" => "corepack enable && corepack prepare pnpm@latest --activate".to_string(),
-                    "bun" => "npm install -g bun".to_string(),
-                    _ => "npm is included with Node.js - reinstall Node.js".to_string(),
-                },
+                steps,
                 is_safe: false,
+                provides: vec![RepairTarget::PackageManager],
+                requires: vec![RepairTarget::Node],
+                ..Default::default()
             })
         }
 
@@ -263,22 +643,23 @@ fn issue_to_action_with_context(issue: &CheckResult, context: &RepairContext) ->
             let major_version = target_version.split('.').next().unwrap_or("20");
 
             // Detect version manager via env vars (works even if node isn't installed)
-            let cmd = if std::env::var("VOLTA_HOME").is_ok() {
-                format!("volta install node@{}", target_version)
+            let step = if std::env::var("VOLTA_HOME").is_ok() {
+                RepairStep::exec(&["volta", "install", &format!("node@{}", target_version)])
             } else if std::env::var("FNM_DIR").is_ok() || std::env::var("FNM_MULTISHELL_PATH").is_ok() {
-                format!("fnm install {}", target_version)
+                RepairStep::exec(&["fnm", "install", target_version])
             } else if std::env::var("NVM_DIR").is_ok() {
-                format!("nvm install {}", target_version)
+                // nvm is a shell function, not a program
+                RepairStep::manual(format!("Run `nvm install {}` in your shell", target_version))
             } else if cfg!(windows) {
-                "winget install OpenJS.NodeJS.LTS".to_string()
+                RepairStep::exec(&["winget", "install", "OpenJS.NodeJS.LTS"])
             } else if cfg!(target_os = "macos") {
-                format!("brew install node@{}", major_version)
+                RepairStep::exec(&["brew", "install", &format!("node@{}", major_version)])
             } else {
-                // Linux: use NodeSource setup script
-                format!(
+                // Linux: the NodeSource setup script needs root, so leave it to the user
+                RepairStep::manual(format!(
                     "curl -fsSL https://deb.nodesource.com/setup_{}.x | sudo -E bash - && sudo apt-get install -y nodejs",
                     major_version
-                )
+                ))
             };
 
             let desc = if target_version == "--lts" {
@@ -289,22 +670,22 @@ fn issue_to_action_with_context(issue: &CheckResult, context: &RepairContext) ->
 
             Some(RepairAction {
                 description: desc,
-                command: cmd,
+                steps: vec![step],
                 is_safe: false,
+                provides: vec![RepairTarget::Node],
+                invalidates: vec![RepairTarget::NodeModules],
+                ..Default::default()
             })
         }
 
         _ => {
             // For issues without specific repair, suggest manual fix
-            if issue.suggested_fix.is_some() {
-                Some(RepairAction {
-                    description: issue.name.clone(),
-                    command: issue.suggested_fix.clone().unwrap(),
-                    is_safe: false,
-                })
-            } else {
-                None
-            }
+            issue.suggested_fix.as_ref().map(|fix| RepairAction {
+                description: issue.name.clone(),
+                steps: vec![RepairStep::manual(fix.clone())],
+                is_safe: false,
+                ..Default::default()
+            })
         }
     }
 }
@@ -332,46 +713,9 @@ fn get_lockfile_name(package_manager: &str) -> &'static str {
     }
 }
 
-pub fn execute_repair(action: &RepairAction) -> Result<()> {
-    // Skip non-executable commands (manual instructions)
-    if action.command.contains("manually")
-        || action.command.contains("Manual")
-        || action.command.contains("reinstall Node.js")
-    {
-        return Ok(());
-    }
-
-    // Execute command through shell to properly resolve PATH and handle operators like &&
-    #[cfg(windows)]
-    let output = Command::new("cmd")
-        .args(["/C", &action.command])
-        .output()?;
-
-    #[cfg(not(windows))]
-    let output = Command::new("sh")
-        .args(["-c", &action.command])
-        .output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-
-        // Filter out common warning lines that don't indicate real failures
-        let is_only_warnings = stderr.lines().all(|line| {
-            line.trim().is_empty()
-                || line.starts_with("warning ")
-                || line.starts_with("npm WARN")
-                || line.contains("deprecated")
-        });
-
-        // If stderr only contains warnings and stdout looks successful, don't fail
-        if is_only_warnings && !stdout.contains("error") && !stdout.contains("ERR!") {
-            return Ok(());
-        }
-
-        let error_msg = if stderr.is_empty() { stdout } else { stderr };
-        anyhow::bail!("Command failed: {}", error_msg.trim());
-    }
-
-    Ok(())
+/// Run every step of `action` in order, stopping at the first failure
+///
+/// Manual instructions are skipped; they're shown to the user instead.
+pub fn execute_repair(action: &RepairAction, options: &RunOptions) -> ActionRun {
+    runner::run_action(action, options)
 }
@@ -0,0 +1,194 @@
+//! Declaring phantom dependencies
+//!
+//! A phantom dependency is imported by the project without being declared in
+//! package.json; it only resolves because something else pulled it into node_modules.
+//! The repair declares each one at the version installed there, under `devDependencies`
+//! when only tests and config files import it and under `dependencies` otherwise.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use super::json_edit::escape_key;
+use super::RepairStep;
+
+/// Directories holding tests, fixtures and stories
+const DEV_DIRS: &[&str] = &[
+    "__tests__",
+    "__mocks__",
+    "test",
+    "tests",
+    "spec",
+    "e2e",
+    "cypress",
+    "playwright",
+    "stories",
+    "fixtures",
+];
+
+/// File name parts marking tests, stories and tooling configuration
+const DEV_FILE_MARKERS: &[&str] = &[
+    ".test.",
+    ".spec.",
+    ".stories.",
+    ".story.",
+    ".e2e.",
+    ".config.",
+    ".setup.",
+    "setupTests.",
+];
+
+/// Whether `file` is a test, story or config file rather than application code
+pub fn is_dev_file(file: &Path) -> bool {
+    let in_dev_dir = file.parent().is_some_and(|dir| {
+        dir.components().any(|c| match c {
+            Component::Normal(name) => DEV_DIRS.contains(&name.to_string_lossy().as_ref()),
+            _ => false,
+        })
+    });
+    let name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    in_dev_dir || DEV_FILE_MARKERS.iter().any(|m| name.contains(m))
+}
+
+/// Version of `package` installed in `root`'s node_modules
+fn installed_version(root: &Path, package: &str) -> Option<String> {
+    let manifest = root.join("node_modules").join(package).join("package.json");
+    let content = fs::read_to_string(manifest).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    json.get("version")?.as_str().map(str::to_string)
+}
+
+/// How a phantom dependency gets declared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhantomFix {
+    pub package: String,
+    /// `dependencies` or `devDependencies`
+    pub section: &'static str,
+    /// Installed version, `None` when it isn't in node_modules
+    pub version: Option<String>,
+    /// Files importing it
+    pub files: Vec<PathBuf>,
+}
+
+impl PhantomFix {
+    /// Step adding the package to package.json, or telling the user to install it
+    pub fn step(&self) -> RepairStep {
+        match &self.version {
+            Some(version) => RepairStep::EditJson {
+                path: "package.json".into(),
+                key: format!("{}.{}", self.section, escape_key(&self.package)),
+                value: serde_json::Value::String(format!("^{}", version)),
+            },
+            None => RepairStep::manual(format!(
+                "{} isn't installed; add it to {} with your package manager",
+                self.package, self.section
+            )),
+        }
+    }
+
+    /// Why it goes where it goes
+    pub fn reason(&self) -> String {
+        let files: Vec<String> = self
+            .files
+            .iter()
+            .take(3)
+            .map(|f| f.display().to_string())
+            .collect();
+        let more = if self.files.len() > 3 {
+            format!(" and {} more", self.files.len() - 3)
+        } else {
+            String::new()
+        };
+        let place = if self.section == "devDependencies" {
+            "only by tests and config"
+        } else {
+            "by application code"
+        };
+        format!(
+            "{} is imported {} ({}{})",
+            self.package,
+            place,
+            files.join(", "),
+            more
+        )
+    }
+}
+
+/// Where each of `phantoms` (package -> importing files) should be declared
+pub fn phantom_fixes(root: &Path, phantoms: BTreeMap<String, Vec<PathBuf>>) -> Vec<PhantomFix> {
+    phantoms
+        .into_iter()
+        .map(|(package, files)| {
+            let section = if files.iter().all(|f| is_dev_file(f)) {
+                "devDependencies"
+            } else {
+                "dependencies"
+            };
+            PhantomFix {
+                version: installed_version(root, &package),
+                package,
+                section,
+                files,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_phantom_fixes() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        for (package, version) in [("lodash", "4.17.21"), ("socket.io", "4.7.2")] {
+            let package_dir = root.join("node_modules").join(package);
+            fs::create_dir_all(&package_dir).unwrap();
+            fs::write(
+                package_dir.join("package.json"),
+                format!(r#"{{"name": "{}", "version": "{}"}}"#, package, version),
+            )
+            .unwrap();
+        }
+
+        let phantoms = BTreeMap::from([
+            (
+                "lodash".to_string(),
+                vec![
+                    PathBuf::from("src/app.ts"),
+                    PathBuf::from("src/app.test.ts"),
+                ],
+            ),
+            (
+                "socket.io".to_string(),
+                vec![
+                    PathBuf::from("vitest.config.ts"),
+                    PathBuf::from("src/__tests__/io.ts"),
+                ],
+            ),
+            ("zod".to_string(), vec![PathBuf::from("src/schema.ts")]),
+        ]);
+        let fixes = phantom_fixes(root, phantoms);
+
+        assert_eq!(fixes[0].section, "dependencies");
+        assert_eq!(
+            fixes[0].step().preview(),
+            "set \"dependencies.lodash\" to \"^4.17.21\" in package.json"
+        );
+        assert_eq!(fixes[1].section, "devDependencies");
+        assert_eq!(
+            fixes[1].step().preview(),
+            "set \"devDependencies.socket\\.io\" to \"^4.7.2\" in package.json"
+        );
+        assert_eq!(
+            fixes[1].reason(),
+            "socket.io is imported only by tests and config (vitest.config.ts, src/__tests__/io.ts)"
+        );
+        assert!(fixes[2].step().is_manual());
+    }
+}
@@ -0,0 +1,224 @@
+//! Pinning the project's Node version
+//!
+//! Switching Node only fixes the current shell. Writing the locked version into the
+//! project's pin files (`.nvmrc`, `.node-version`, package.json `volta.node` and
+//! `engines.node`, `.tool-versions`) makes nvm, fnm, Volta, asdf/mise and engine checks
+//! agree on it. Each file keeps its style: a `v` prefix in `.nvmrc`, the range operator
+//! in `engines.node`, the other tools in `.tool-versions`.
+//!
+//! `engines.node` is the range of Node versions a package supports rather than a pin, so
+//! it's only rewritten when it doesn't admit the locked version.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::RepairStep;
+use crate::config::NodePinFile;
+use crate::utils::npm_range;
+
+/// The Node version of a `.tool-versions` line, if it's the Node line
+fn tool_versions_node(line: &str) -> Option<&str> {
+    let mut parts = line.split_whitespace();
+    match parts.next() {
+        Some("nodejs") | Some("node") => parts.next(),
+        _ => None,
+    }
+}
+
+/// What `pin` is currently set to in `root`
+fn current_value(root: &Path, pin: NodePinFile) -> Option<String> {
+    let content = fs::read_to_string(root.join(pin.file())).ok()?;
+    match pin {
+        NodePinFile::Nvmrc | NodePinFile::NodeVersion => content
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(str::to_string),
+        NodePinFile::Volta | NodePinFile::Engines => {
+            let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+            json.pointer(&format!("/{}", pin.name().replace('.', "/")))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        }
+        NodePinFile::ToolVersions => content
+            .lines()
+            .find_map(tool_versions_node)
+            .map(str::to_string),
+    }
+}
+
+/// Pins the project already has
+fn existing_pins(root: &Path) -> Vec<NodePinFile> {
+    NodePinFile::ALL
+        .into_iter()
+        .filter(|pin| current_value(root, *pin).is_some())
+        .collect()
+}
+
+/// Pins the Node version repair should write
+///
+/// The configured list if there is one, otherwise the pins the project already has, or
+/// `.nvmrc` when it has none.
+pub fn pins_to_write(root: &Path, configured: Option<&[NodePinFile]>) -> Vec<NodePinFile> {
+    if let Some(configured) = configured {
+        return configured.to_vec();
+    }
+    let existing = existing_pins(root);
+    if existing.is_empty() {
+        vec![NodePinFile::Nvmrc]
+    } else {
+        existing
+    }
+}
+
+/// `version` written the way `current` is
+fn styled(pin: NodePinFile, current: Option<&str>, version: &str) -> String {
+    let current = current.unwrap_or_default().trim();
+    match pin {
+        NodePinFile::Nvmrc | NodePinFile::NodeVersion if current.starts_with('v') => {
+            format!("v{}", version)
+        }
+        // Keep `>=` or `^` from a simple range; replace compound ones with a lower bound
+        NodePinFile::Engines if !current.is_empty() => {
+            let operator: String = current
+                .chars()
+                .take_while(|c| "<>=^~".contains(*c))
+                .collect();
+            let rest = current[operator.len()..].trim();
+            if rest.contains(char::is_whitespace) || rest.contains("||") {
+                format!(">={}", version)
+            } else {
+                format!("{}{}", operator, version)
+            }
+        }
+        _ => version.to_string(),
+    }
+}
+
+/// `.tool-versions` with its Node line set to `version`
+fn tool_versions_content(existing: Option<&str>, version: &str) -> String {
+    let Some(existing) = existing else {
+        return format!("nodejs {}\n", version);
+    };
+    let mut found = false;
+    let mut lines: Vec<String> = existing
+        .lines()
+        .map(|line| match tool_versions_node(line) {
+            Some(old) if !found => {
+                found = true;
+                line.replacen(old, version, 1)
+            }
+            _ => line.to_string(),
+        })
+        .collect();
+    if !found {
+        lines.push(format!("nodejs {}", version));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Steps writing `version` into each of `pins` under `root`, skipping pins already at it
+///
+/// Paths in the steps are relative to the project root.
+pub fn pin_steps(root: &Path, pins: &[NodePinFile], version: &str) -> Vec<RepairStep> {
+    let version = version.trim_start_matches('v');
+    let mut steps = Vec::new();
+    for &pin in pins {
+        let current = current_value(root, pin);
+        // Narrowing a range that already admits the version would drop supported versions
+        if let (NodePinFile::Engines, Some(range)) = (pin, current.as_deref()) {
+            if npm_range::satisfies(version, range) != Some(false) {
+                continue;
+            }
+        }
+        let value = styled(pin, current.as_deref(), version);
+        if current.as_deref() == Some(value.as_str()) {
+            continue;
+        }
+        let path = PathBuf::from(pin.file());
+        let step = match pin {
+            NodePinFile::Nvmrc | NodePinFile::NodeVersion => RepairStep::WriteFile {
+                path,
+                contents: format!("{}\n", value),
+            },
+            // Nothing to pin in without a package.json
+            NodePinFile::Volta | NodePinFile::Engines if !root.join("package.json").is_file() => {
+                continue
+            }
+            NodePinFile::Volta | NodePinFile::Engines => RepairStep::EditJson {
+                path,
+                key: pin.name().to_string(),
+                value: serde_json::Value::String(value),
+            },
+            NodePinFile::ToolVersions => {
+                let existing = fs::read_to_string(root.join(pin.file())).ok();
+                RepairStep::WriteFile {
+                    path,
+                    contents: tool_versions_content(existing.as_deref(), version),
+                }
+            }
+        };
+        steps.push(step);
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pin_steps_keep_each_files_style() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join(".nvmrc"), "v18.19.0\n").unwrap();
+        fs::write(
+            root.join(".tool-versions"),
+            "python 3.12.1\nnodejs 18.19.0\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("package.json"),
+            "{\n  \"name\": \"app\",\n  \"engines\": { \"node\": \"^18.0.0\" }\n}\n",
+        )
+        .unwrap();
+
+        let pins = existing_pins(root);
+        assert_eq!(
+            pins,
+            vec![
+                NodePinFile::Nvmrc,
+                NodePinFile::Engines,
+                NodePinFile::ToolVersions
+            ]
+        );
+
+        let steps = pin_steps(root, &pins, "20.11.0");
+        let previews: Vec<String> = steps.iter().map(|s| s.preview()).collect();
+        assert_eq!(
+            previews,
+            vec![
+                "write .nvmrc (9 bytes)",
+                "set \"engines.node\" to \"^20.11.0\" in package.json",
+                "write .tool-versions (29 bytes)",
+            ]
+        );
+        assert!(matches!(
+            &steps[2],
+            RepairStep::WriteFile { contents, .. } if contents == "python 3.12.1\nnodejs 20.11.0\n"
+        ));
+
+        // A range that admits the version is left alone
+        fs::write(
+            root.join("package.json"),
+            "{\n  \"name\": \"app\",\n  \"engines\": { \"node\": \">=18\" }\n}\n",
+        )
+        .unwrap();
+        assert!(pin_steps(root, &[NodePinFile::Engines], "20.11.0").is_empty());
+
+        // Already pinned: nothing to do
+        fs::write(root.join(".node-version"), "20.11.0\n").unwrap();
+        assert!(pin_steps(root, &[NodePinFile::NodeVersion], "20.11.0").is_empty());
+    }
+}
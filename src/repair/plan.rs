@@ -0,0 +1,235 @@
+//! Ordering repair actions
+//!
+//! Every action declares the parts of the environment it fixes (`provides`), the parts
+//! that must be fixed before it can run (`requires`) and the parts it breaks again
+//! (`invalidates`, e.g. switching Node invalidates node_modules). The planner drops
+//! actions another one already covers, adds follow-ups for anything invalidated that
+//! nothing in the plan fixes, and sorts the result topologically.
+
+use serde::Serialize;
+
+use super::{RepairAction, RepairContext};
+
+/// A part of the environment a repair action works on, in the order they're usually fixed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairTarget {
+    Node,
+    PackageManager,
+    Cache,
+    Lockfile,
+    NodeModules,
+    Tooling,
+    EnvLock,
+}
+
+/// Whether `other` makes `action` unnecessary
+fn covers(other: &RepairAction, action: &RepairAction) -> bool {
+    if other.is_manual() || action.provides.is_empty() {
+        return action.steps == other.steps;
+    }
+    // Don't trade a safe action for one that needs confirmation
+    if action.is_safe && !other.is_safe {
+        return false;
+    }
+    if !action.provides.iter().all(|t| other.provides.contains(t)) {
+        return false;
+    }
+    // Of two actions fixing the same things, the one doing more (reinstall over install) wins
+    other.provides.len() > action.provides.len() || other.steps.len() >= action.steps.len()
+}
+
+/// Drop actions covered by another one, keeping the first of two identical ones
+fn dedupe(actions: Vec<RepairAction>) -> Vec<RepairAction> {
+    let mut kept: Vec<RepairAction> = Vec::new();
+    for mut action in actions {
+        // The covering action takes over the issues of the one it replaces
+        if let Some(k) = kept.iter_mut().find(|k| covers(k, &action)) {
+            k.triggered_by.append(&mut action.triggered_by);
+            continue;
+        }
+        let (covered, rest): (Vec<_>, Vec<_>) =
+            kept.into_iter().partition(|k| covers(&action, k));
+        kept = rest;
+        for mut k in covered {
+            action.triggered_by.append(&mut k.triggered_by);
+        }
+        kept.push(action);
+    }
+    kept
+}
+
+/// Add the follow-up for every target an executable action invalidates and nothing fixes
+fn add_follow_ups(actions: &mut Vec<RepairAction>, context: &RepairContext) {
+    let mut invalidated: Vec<RepairTarget> = actions
+        .iter()
+        .filter(|a| !a.is_manual())
+        .flat_map(|a| a.invalidates.iter().copied())
+        .collect();
+    invalidated.sort();
+    invalidated.dedup();
+
+    for target in invalidated {
+        if actions.iter().any(|a| a.provides.contains(&target)) {
+            continue;
+        }
+        if let Some(action) = context.follow_up(target) {
+            actions.push(action);
+        }
+    }
+}
+
+/// Whether `before` has to run ahead of `after`
+fn must_precede(before: &RepairAction, after: &RepairAction) -> bool {
+    before.provides.iter().any(|t| after.requires.contains(t))
+        || before
+            .invalidates
+            .iter()
+            .any(|t| after.provides.contains(t))
+}
+
+/// Sort key for actions with no ordering constraint between them
+fn rank(action: &RepairAction) -> (RepairTarget, bool) {
+    let phase = action
+        .provides
+        .iter()
+        .min()
+        .copied()
+        .unwrap_or(RepairTarget::Tooling);
+    (phase, !action.is_safe)
+}
+
+/// Dedupe, complete and order `actions`, filling in each action's `after`
+pub fn order(actions: Vec<RepairAction>, context: &RepairContext) -> Vec<RepairAction> {
+    let mut actions = dedupe(actions);
+    add_follow_ups(&mut actions, context);
+
+    let n = actions.len();
+    let edges: Vec<Vec<usize>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .filter(|&j| i != j && must_precede(&actions[j], &actions[i]))
+                .collect()
+        })
+        .collect();
+
+    // Kahn's algorithm, picking the lowest-ranked ready action each time; on a cycle the
+    // lowest-ranked remaining action goes next
+    let mut placed = vec![false; n];
+    let mut order: Vec<usize> = Vec::with_capacity(n);
+    while order.len() < n {
+        let remaining = (0..n).filter(|&i| !placed[i]);
+        let ready: Vec<usize> = remaining
+            .clone()
+            .filter(|&i| edges[i].iter().all(|&j| placed[j]))
+            .collect();
+        let candidates: Vec<usize> = if ready.is_empty() {
+            remaining.collect()
+        } else {
+            ready
+        };
+        let next = candidates
+            .into_iter()
+            .min_by_key(|&i| (rank(&actions[i]), i))
+            .unwrap_or_default();
+        placed[next] = true;
+        order.push(next);
+    }
+
+    let mut position = vec![0; n];
+    for (pos, &i) in order.iter().enumerate() {
+        position[i] = pos;
+    }
+
+    let mut ordered: Vec<(usize, RepairAction)> = actions
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut action)| {
+            let mut after: Vec<usize> = edges[i]
+                .iter()
+                .map(|&j| position[j])
+                .filter(|&p| p < position[i])
+                .collect();
+            after.sort_unstable();
+            action.after = after;
+            (position[i], action)
+        })
+        .collect();
+    ordered.sort_by_key(|(pos, _)| *pos);
+    ordered.into_iter().map(|(_, action)| action).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::CheckResult;
+    use crate::repair::generate_repair_plan_with_context;
+
+    fn descriptions(actions: &[RepairAction]) -> Vec<&str> {
+        actions.iter().map(|a| a.description.as_str()).collect()
+    }
+
+    #[test]
+    fn test_plan_dedupes_and_orders() {
+        let context = RepairContext::new("npm")
+            .with_package_manager_version(Some("10.8.2".to_string()))
+            .with_node_version_manager(Some("volta".to_string()));
+        let issues = [
+            CheckResult::error("node_modules exists", "deps", "node_modules not found"),
+            CheckResult::error("Lockfile hash match", "lockfile", "Hash mismatch"),
+            CheckResult::error("node_modules in sync", "deps", "Out of sync"),
+            CheckResult::warning("Corepack available", "toolchain", "Corepack not enabled"),
+            CheckResult::error(
+                "Node version match",
+                "toolchain",
+                "Expected 20.11.0 but found 18.19.0",
+            ),
+        ];
+        let issues: Vec<&CheckResult> = issues.iter().collect();
+
+        let plan = generate_repair_plan_with_context(&issues, &context).unwrap();
+        assert_eq!(
+            descriptions(&plan),
+            vec![
+                "Switch Node.js to version 20.11.0",
+                "Enable Corepack",
+                "Reinstall dependencies using npm",
+                "Update env.lock to match current lockfile",
+            ]
+        );
+        assert_eq!(plan[2].after, vec![0, 1]);
+        assert_eq!(plan[3].after, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_plan_adds_follow_ups() {
+        let context = RepairContext::new("pnpm")
+            .with_package_manager_version(Some("9.1.0".to_string()))
+            .with_node_version_manager(Some("volta".to_string()));
+        let issues = [
+            CheckResult::warning(
+                "Corepack available",
+                "toolchain",
+                "Corepack is available but packageManager field not set",
+            ),
+            CheckResult::error(
+                "Node version match",
+                "toolchain",
+                "Expected 20.11.0 but found 18.19.0",
+            ),
+        ];
+        let issues: Vec<&CheckResult> = issues.iter().collect();
+
+        // Pinning edits package.json (relock); switching Node breaks native modules (reinstall)
+        let plan = generate_repair_plan_with_context(&issues, &context).unwrap();
+        assert_eq!(
+            descriptions(&plan),
+            vec![
+                "Switch Node.js to version 20.11.0",
+                "Pin pnpm@9.1.0 with Corepack",
+                "Reinstall dependencies using pnpm",
+                "Update env.lock to match current lockfile",
+            ]
+        );
+    }
+}
@@ -0,0 +1,622 @@
+//! Running repair actions
+//!
+//! Commands run in their own process group with stdin closed, and their stdout and
+//! stderr are streamed line by line under a spinner as they arrive. When an action runs
+//! past its timeout, or the user presses Ctrl-C, the whole group is killed so an
+//! `npm ci` doesn't leave install scripts running behind it. Each step's exit code and
+//! duration are recorded for the output.
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{RepairAction, RepairStep};
+use crate::config::ZenvoConfig;
+
+/// Time an action gets when the config doesn't say otherwise
+pub const DEFAULT_REPAIR_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Output lines kept for the message of a failed command
+const TAIL_LINES: usize = 20;
+
+/// How long to keep reading output after the command exits (background children may
+/// hold the pipes open)
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Set by Ctrl-C while a command is running
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+/// Whether a command is running, i.e. whether Ctrl-C should cancel it or exit zenvo
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static CTRLC_HANDLER: Once = Once::new();
+
+fn install_ctrlc_handler() {
+    CTRLC_HANDLER.call_once(|| {
+        let _ = ctrlc::set_handler(|| {
+            if RUNNING.load(Ordering::SeqCst) {
+                CANCELLED.store(true, Ordering::SeqCst);
+            } else {
+                std::process::exit(130);
+            }
+        });
+    });
+}
+
+/// Whether Ctrl-C cancelled a command; nothing else should run after that
+fn cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+/// How a step ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Succeeded,
+    Failed,
+    TimedOut,
+    Cancelled,
+    /// Manual steps, and steps after a failure
+    Skipped,
+}
+
+/// Result of one step
+#[derive(Debug, Clone, Serialize)]
+pub struct StepRun {
+    /// Shell-like preview of the step
+    pub step: String,
+    pub status: StepStatus,
+    /// Exit code of a command (none if it was killed or never started)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+}
+
+/// Result of running every step of an action
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionRun {
+    pub steps: Vec<StepRun>,
+    pub duration_ms: u64,
+    /// Why the action failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ActionRun {
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+}
+
+/// How to run an action
+#[derive(Debug, Clone, Copy)]
+pub struct RunOptions {
+    /// Time the whole action gets
+    pub timeout: Duration,
+    /// Show a spinner and stream command output to stderr
+    pub live: bool,
+}
+
+impl RunOptions {
+    /// Options for `action`, with the timeout from `[repair]` in the config
+    ///
+    /// A `timeouts` entry for a program the action runs wins over `timeout_seconds`;
+    /// with several, the longest applies.
+    pub fn for_action(action: &RepairAction, config: &Option<ZenvoConfig>, live: bool) -> Self {
+        let repair = config.as_ref().map(|c| &c.repair);
+        let per_program = repair.and_then(|r| {
+            action
+                .steps
+                .iter()
+                .filter_map(|step| match step {
+                    RepairStep::Exec { argv, .. } => argv.first(),
+                    _ => None,
+                })
+                .filter_map(|program| r.timeouts.get(program))
+                .max()
+                .copied()
+        });
+        let timeout = per_program
+            .or_else(|| repair.and_then(|r| r.timeout_seconds))
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_REPAIR_TIMEOUT);
+        Self { timeout, live }
+    }
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_REPAIR_TIMEOUT,
+            live: false,
+        }
+    }
+}
+
+/// Run every step of `action` in order, stopping at the first failure
+///
+/// Manual steps are skipped; they're shown to the user instead.
+pub fn run_action(action: &RepairAction, options: &RunOptions) -> ActionRun {
+    let started = Instant::now();
+    let deadline = started + options.timeout;
+    let mut steps = Vec::new();
+    let mut error = None;
+
+    for step in &action.steps {
+        let step_started = Instant::now();
+        let preview = step.preview();
+        let (status, exit_code) = if error.is_some() || step.is_manual() {
+            (StepStatus::Skipped, None)
+        } else {
+            let outcome = match step {
+                RepairStep::Exec { argv, cwd, env } => {
+                    run_command(argv, cwd.as_deref(), env, deadline, options)
+                }
+                other => CommandOutcome::from_result(other.execute()),
+            };
+            error = outcome.error;
+            (outcome.status, outcome.exit_code)
+        };
+        steps.push(StepRun {
+            step: preview,
+            status,
+            exit_code,
+            duration_ms: millis(step_started.elapsed()),
+        });
+    }
+
+    ActionRun {
+        steps,
+        duration_ms: millis(started.elapsed()),
+        error,
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// How a command ended
+pub(crate) struct CommandOutcome {
+    pub status: StepStatus,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+impl CommandOutcome {
+    fn from_result(result: Result<()>) -> Self {
+        match result {
+            Ok(()) => Self {
+                status: StepStatus::Succeeded,
+                exit_code: None,
+                error: None,
+            },
+            Err(e) => Self::failed(None, e.to_string()),
+        }
+    }
+
+    fn failed(exit_code: Option<i32>, error: String) -> Self {
+        Self {
+            status: StepStatus::Failed,
+            exit_code,
+            error: Some(error),
+        }
+    }
+
+    pub fn into_result(self) -> Result<()> {
+        match self.error {
+            Some(error) => bail!(error),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Resolve `program` through PATH (and PATHEXT on Windows, for npm.cmd and friends)
+///
+/// `zenvo` itself may not be installed on PATH, so it falls back to the running binary.
+fn resolve_program(program: &str) -> PathBuf {
+    which::which(program)
+        .or_else(|e| match program {
+            "zenvo" => std::env::current_exe().map_err(|_| e),
+            _ => Err(e),
+        })
+        .unwrap_or_else(|_| PathBuf::from(program))
+}
+
+fn spawn(argv: &[String], cwd: Option<&Path>, env: &BTreeMap<String, String>) -> Result<Child> {
+    let Some((program, args)) = argv.split_first() else {
+        bail!("Empty command");
+    };
+
+    let mut command = Command::new(resolve_program(program));
+    command
+        .args(args)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    // A group of its own, so it can be killed with everything it started and the
+    // terminal's Ctrl-C reaches only zenvo
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    }
+
+    command
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to start '{}': {}", program, e))
+}
+
+/// Kill `child` and every process in its group
+#[cfg(unix)]
+fn kill_tree(child: &mut Child) {
+    if let Ok(pgid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill() has no memory-safety preconditions; a negative pid targets the
+        // process group the child leads
+        unsafe {
+            libc::kill(-pgid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Kill `child` and every process it started
+#[cfg(not(unix))]
+fn kill_tree(child: &mut Child) {
+    let _ = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &child.id().to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Send each line of `stream` to `tx`, tagged with whether it came from stderr
+fn forward_lines(
+    stream: impl Read + Send + 'static,
+    is_stderr: bool,
+    tx: mpsc::Sender<(bool, String)>,
+) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut buf = Vec::new();
+        // Not `lines()`: it stops at invalid UTF-8 and the child would block on a full pipe
+        while matches!(reader.read_until(b'\n', &mut buf), Ok(n) if n > 0) {
+            let line = String::from_utf8_lossy(&buf).trim_end().to_string();
+            buf.clear();
+            if tx.send((is_stderr, line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn spinner(live: bool, message: String) -> ProgressBar {
+    if !live {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::new_spinner();
+    if let Ok(style) = ProgressStyle::with_template("  {spinner:.cyan} {msg} {elapsed:.dim}") {
+        bar.set_style(style);
+    }
+    bar.set_message(message);
+    bar.enable_steady_tick(Duration::from_millis(100));
+    bar
+}
+
+/// Output seen so far
+#[derive(Default)]
+struct Captured {
+    stdout: VecDeque<String>,
+    stderr: VecDeque<String>,
+    /// Recognised warning lines on stderr
+    stderr_warnings: usize,
+    /// Whether stderr had anything besides warnings and blank lines
+    stderr_other: bool,
+    stdout_has_error: bool,
+}
+
+impl Captured {
+    fn push(&mut self, is_stderr: bool, line: String) {
+        let tail = if is_stderr {
+            if line.starts_with("warning ")
+                || line.starts_with("npm WARN")
+                || line.contains("deprecated")
+            {
+                self.stderr_warnings += 1;
+            } else if !line.trim().is_empty() {
+                self.stderr_other = true;
+            }
+            &mut self.stderr
+        } else {
+            self.stdout_has_error |= line.contains("error") || line.contains("ERR!");
+            &mut self.stdout
+        };
+        if tail.len() == TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+
+    /// Whether a non-zero exit was over warnings alone: some were printed and nothing else
+    fn only_warnings(&self) -> bool {
+        self.stderr_warnings > 0 && !self.stderr_other && !self.stdout_has_error
+    }
+
+    /// The end of stderr, or of stdout if stderr is empty
+    fn tail(&self) -> String {
+        let lines = if self.stderr.iter().all(|l| l.trim().is_empty()) {
+            &self.stdout
+        } else {
+            &self.stderr
+        };
+        lines
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+enum Ended {
+    Exited(ExitStatus),
+    TimedOut,
+    Cancelled,
+    WaitFailed(std::io::Error),
+}
+
+/// Run a command, streaming its output, until it exits or `deadline` passes
+pub(crate) fn run_command(
+    argv: &[String],
+    cwd: Option<&Path>,
+    env: &BTreeMap<String, String>,
+    deadline: Instant,
+    options: &RunOptions,
+) -> CommandOutcome {
+    install_ctrlc_handler();
+    if cancelled() {
+        return CommandOutcome {
+            status: StepStatus::Cancelled,
+            exit_code: None,
+            error: Some("Cancelled".to_string()),
+        };
+    }
+
+    let mut child = match spawn(argv, cwd, env) {
+        Ok(child) => child,
+        Err(e) => return CommandOutcome::failed(None, e.to_string()),
+    };
+    RUNNING.store(true, Ordering::SeqCst);
+
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        forward_lines(stdout, false, tx.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward_lines(stderr, true, tx);
+    }
+
+    let preview = RepairStep::exec(argv).preview();
+    let bar = spinner(options.live, preview.clone());
+    let mut captured = Captured::default();
+    let show = |captured: &mut Captured, is_stderr: bool, line: String| {
+        if options.live {
+            let text = format!("  {} {}", "│".dimmed(), line.dimmed());
+            if bar.is_hidden() {
+                eprintln!("{}", text);
+            } else {
+                bar.println(text);
+            }
+        }
+        captured.push(is_stderr, line);
+    };
+
+    let mut streams_open = true;
+    let ended = loop {
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok((is_stderr, line)) => show(&mut captured, is_stderr, line),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                // Both pipes are closed; don't spin while waiting for the exit
+                if streams_open {
+                    streams_open = false;
+                } else {
+                    thread::sleep(Duration::from_millis(50));
+                }
+            }
+        }
+        if cancelled() {
+            kill_tree(&mut child);
+            break Ended::Cancelled;
+        }
+        if Instant::now() >= deadline {
+            kill_tree(&mut child);
+            break Ended::TimedOut;
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break Ended::Exited(status),
+            Ok(None) => {}
+            Err(e) => {
+                kill_tree(&mut child);
+                break Ended::WaitFailed(e);
+            }
+        }
+    };
+    RUNNING.store(false, Ordering::SeqCst);
+
+    // Whatever was written before the exit
+    let drain_until = Instant::now() + DRAIN_TIMEOUT;
+    while let Some(remaining) = drain_until.checked_duration_since(Instant::now()) {
+        match rx.recv_timeout(remaining) {
+            Ok((is_stderr, line)) => show(&mut captured, is_stderr, line),
+            Err(_) => break,
+        }
+    }
+    bar.finish_and_clear();
+
+    match ended {
+        Ended::Exited(status) if status.success() => CommandOutcome {
+            status: StepStatus::Succeeded,
+            exit_code: status.code(),
+            error: None,
+        },
+        // Some tools exit non-zero over warnings alone
+        Ended::Exited(status) if captured.only_warnings() => {
+            CommandOutcome {
+                status: StepStatus::Succeeded,
+                exit_code: status.code(),
+                error: None,
+            }
+        }
+        Ended::Exited(status) => {
+            let code = status
+                .code()
+                .map_or_else(|| "killed by a signal".to_string(), |c| format!("exit code {}", c));
+            let tail = captured.tail();
+            let error = if tail.trim().is_empty() {
+                format!("Command failed ({}): {}", code, preview)
+            } else {
+                format!("Command failed ({}): {}", code, tail.trim())
+            };
+            CommandOutcome::failed(status.code(), error)
+        }
+        Ended::TimedOut => CommandOutcome {
+            status: StepStatus::TimedOut,
+            exit_code: None,
+            error: Some(format!(
+                "Timed out after {}s and was killed: {} (raise repair.timeout_seconds to allow more time)",
+                options.timeout.as_secs(),
+                preview
+            )),
+        },
+        Ended::Cancelled => CommandOutcome {
+            status: StepStatus::Cancelled,
+            exit_code: None,
+            error: Some(format!("Cancelled with Ctrl-C: {}", preview)),
+        },
+        Ended::WaitFailed(e) => {
+            CommandOutcome::failed(None, format!("Failed to wait for '{}': {}", preview, e))
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> Vec<String> {
+        vec!["sh".to_string(), "-c".to_string(), script.to_string()]
+    }
+
+    #[test]
+    fn test_run_records_exit_codes() {
+        let action = RepairAction {
+            description: "Test".to_string(),
+            steps: vec![
+                RepairStep::Exec {
+                    argv: sh("echo one; echo two >&2"),
+                    cwd: None,
+                    env: BTreeMap::new(),
+                },
+                RepairStep::Exec {
+                    argv: sh("echo 'npm ERR! broken' >&2; exit 3"),
+                    cwd: None,
+                    env: BTreeMap::new(),
+                },
+                RepairStep::exec(&["true"]),
+            ],
+            ..Default::default()
+        };
+
+        let run = run_action(&action, &RunOptions::default());
+        let statuses: Vec<(StepStatus, Option<i32>)> =
+            run.steps.iter().map(|s| (s.status, s.exit_code)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (StepStatus::Succeeded, Some(0)),
+                (StepStatus::Failed, Some(3)),
+                (StepStatus::Skipped, None),
+            ]
+        );
+        assert_eq!(
+            run.error.as_deref(),
+            Some("Command failed (exit code 3): npm ERR! broken")
+        );
+    }
+
+    #[test]
+    fn test_non_zero_exit_fails_unless_only_warnings() {
+        let status = |script: &str| {
+            let action = RepairAction {
+                description: "Test".to_string(),
+                steps: vec![RepairStep::Exec {
+                    argv: sh(script),
+                    cwd: None,
+                    env: BTreeMap::new(),
+                }],
+                ..Default::default()
+            };
+            run_action(&action, &RunOptions::default()).steps[0].status
+        };
+
+        assert_eq!(status("exit 1"), StepStatus::Failed);
+        assert_eq!(status("echo installing; exit 1"), StepStatus::Failed);
+        assert_eq!(
+            status("echo 'npm WARN deprecated x@1.0.0' >&2; echo 'killed' >&2; exit 1"),
+            StepStatus::Failed
+        );
+        assert_eq!(
+            status("echo 'npm WARN deprecated x@1.0.0' >&2; exit 1"),
+            StepStatus::Succeeded
+        );
+    }
+
+    #[test]
+    fn test_timeout_kills_process_group() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let marker = dir.path().join("survived");
+        // The background child would outlive a plain kill of the shell
+        let script = format!("(sleep 2; touch {}) & sleep 30", marker.display());
+        let action = RepairAction {
+            description: "Hang".to_string(),
+            steps: vec![RepairStep::Exec {
+                argv: sh(&script),
+                cwd: None,
+                env: BTreeMap::new(),
+            }],
+            ..Default::default()
+        };
+        let options = RunOptions {
+            timeout: Duration::from_millis(300),
+            live: false,
+        };
+
+        let started = Instant::now();
+        let run = run_action(&action, &options);
+        assert_eq!(run.steps[0].status, StepStatus::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        thread::sleep(Duration::from_secs(3));
+        assert!(!marker.exists());
+    }
+}
@@ -0,0 +1,214 @@
+//! Typed repair steps
+//!
+//! Each `RepairStep` says exactly what it does, so it can be previewed, serialized and
+//! executed without going through a shell: program arguments are passed as argv and
+//! package names never get interpreted as shell syntax.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::json_edit;
+use super::runner::{run_command, RunOptions, DEFAULT_REPAIR_TIMEOUT};
+
+/// One operation of a repair action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RepairStep {
+    /// Run a program with arguments (no shell)
+    Exec {
+        argv: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cwd: Option<PathBuf>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        env: BTreeMap<String, String>,
+    },
+    /// Delete a file or directory
+    RemovePath { path: PathBuf },
+    /// Create or replace a file
+    WriteFile { path: PathBuf, contents: String },
+    /// Set a value in a JSON file (`key` is a dotted path), or remove it when `value` is null
+    EditJson {
+        path: PathBuf,
+        key: String,
+        value: serde_json::Value,
+    },
+    /// Activate a Node version with nvm or fnm
+    ///
+    /// Both only change the shell they run in, so zenvo can't do this for the user, but
+    /// an exported script can.
+    UseNode { version: String, manager: String },
+    /// Something the user has to do themselves
+    ManualInstruction { instructions: String },
+}
+
+/// Quote `arg` for a POSIX shell
+pub(crate) fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,~^".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+impl RepairStep {
+    /// Run `argv` in the current directory
+    pub fn exec<S: AsRef<str>>(argv: &[S]) -> Self {
+        RepairStep::Exec {
+            argv: argv.iter().map(|a| a.as_ref().to_string()).collect(),
+            cwd: None,
+            env: BTreeMap::new(),
+        }
+    }
+
+    pub fn remove(path: impl Into<PathBuf>) -> Self {
+        RepairStep::RemovePath { path: path.into() }
+    }
+
+    pub fn manual(instructions: impl Into<String>) -> Self {
+        RepairStep::ManualInstruction {
+            instructions: instructions.into(),
+        }
+    }
+
+    /// Whether the step has to be done by hand
+    pub fn is_manual(&self) -> bool {
+        matches!(
+            self,
+            RepairStep::ManualInstruction { .. } | RepairStep::UseNode { .. }
+        )
+    }
+
+    /// The file or directory the step changes, if it changes one directly
+    pub fn touched_path(&self) -> Option<&Path> {
+        match self {
+            RepairStep::RemovePath { path }
+            | RepairStep::WriteFile { path, .. }
+            | RepairStep::EditJson { path, .. } => Some(path),
+            RepairStep::Exec { .. }
+            | RepairStep::UseNode { .. }
+            | RepairStep::ManualInstruction { .. } => None,
+        }
+    }
+
+    /// Human-readable, shell-like rendering of the step
+    pub fn preview(&self) -> String {
+        match self {
+            RepairStep::Exec { argv, cwd, env } => {
+                let mut parts = Vec::new();
+                if let Some(cwd) = cwd {
+                    parts.push(format!("cd {} &&", shell_quote(&cwd.display().to_string())));
+                }
+                for (key, value) in env {
+                    parts.push(format!("{}={}", key, shell_quote(value)));
+                }
+                parts.extend(argv.iter().map(|a| shell_quote(a)));
+                parts.join(" ")
+            }
+            RepairStep::RemovePath { path } => {
+                format!("rm -rf {}", shell_quote(&path.display().to_string()))
+            }
+            RepairStep::WriteFile { path, contents } => format!(
+                "write {} ({} bytes)",
+                shell_quote(&path.display().to_string()),
+                contents.len()
+            ),
+            RepairStep::EditJson { path, key, value } if value.is_null() => {
+                format!("remove \"{}\" from {}", key, path.display())
+            }
+            RepairStep::EditJson { path, key, value } => {
+                format!("set \"{}\" to {} in {}", key, value, path.display())
+            }
+            RepairStep::UseNode { version, manager } => format!("{} use {}", manager, version),
+            RepairStep::ManualInstruction { instructions } => instructions.clone(),
+        }
+    }
+
+    /// Perform the step without streaming output; manual instructions are left to the user
+    pub fn execute(&self) -> Result<()> {
+        match self {
+            RepairStep::Exec { argv, cwd, env } => {
+                let deadline = Instant::now() + DEFAULT_REPAIR_TIMEOUT;
+                run_command(argv, cwd.as_deref(), env, deadline, &RunOptions::default())
+                    .into_result()
+            }
+            RepairStep::RemovePath { path } => {
+                let result = if path.is_dir() {
+                    fs::remove_dir_all(path)
+                } else {
+                    fs::remove_file(path)
+                };
+                match result {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            RepairStep::WriteFile { path, contents } => fs::write(path, contents)
+                .with_context(|| format!("Failed to write {}", path.display())),
+            RepairStep::EditJson { path, key, value } => edit_json(path, key, value.clone()),
+            RepairStep::UseNode { .. } | RepairStep::ManualInstruction { .. } => Ok(()),
+        }
+    }
+}
+
+/// Set (or with a null `value`, remove) the dotted `key` in a JSON file
+fn edit_json(path: &Path, key: &str, value: serde_json::Value) -> Result<()> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let updated = json_edit::set(&content, key, &value)
+        .with_context(|| format!("Failed to edit {}", path.display()))?;
+    fs::write(path, updated).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_preview_quotes_arguments() {
+        let step = RepairStep::exec(&["npm", "install", "left-pad@^1.0.0", "$(rm -rf ~)"]);
+        assert_eq!(step.preview(), "npm install left-pad@^1.0.0 '$(rm -rf ~)'");
+
+        let json = serde_json::to_value(&step).unwrap();
+        assert_eq!(json["type"], "exec");
+        assert_eq!(json["argv"][3], "$(rm -rf ~)");
+        assert_eq!(serde_json::from_value::<RepairStep>(json).unwrap(), step);
+    }
+
+    #[test]
+    fn test_file_steps() {
+        let dir = TempDir::new().unwrap();
+        let pkg = dir.path().join("package.json");
+        fs::write(&pkg, "{\n  \"name\": \"app\",\n  \"version\": \"1.0.0\"\n}\n").unwrap();
+
+        RepairStep::EditJson {
+            path: pkg.clone(),
+            key: "engines.node".to_string(),
+            value: serde_json::json!(">=20"),
+        }
+        .execute()
+        .unwrap();
+        let content = fs::read_to_string(&pkg).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(json["engines"]["node"], ">=20");
+        assert_eq!(json["version"], "1.0.0");
+        assert!(content.contains("\n  \"name\": \"app\""));
+
+        let modules = dir.path().join("node_modules/.bin");
+        fs::create_dir_all(&modules).unwrap();
+        RepairStep::remove(dir.path().join("node_modules")).execute().unwrap();
+        assert!(!dir.path().join("node_modules").exists());
+        // Removing something that's already gone is fine
+        RepairStep::remove(dir.path().join("node_modules")).execute().unwrap();
+    }
+}
@@ -0,0 +1,178 @@
+//! Checking that a repair worked
+//!
+//! Commands exiting 0 doesn't mean the problem is gone, so after each action the checks
+//! behind its issues run again, and once the plan is done every issue is classified as
+//! fixed, still failing or newly introduced.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::RepairAction;
+use crate::checks::{run_all_checks, CheckCategory, CheckResult, CheckSeverity};
+use crate::config::ZenvoConfig;
+use crate::lockfile::EnvLock;
+
+/// What happened to an issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueVerdict {
+    Fixed,
+    StillFailing,
+    Introduced,
+}
+
+/// An issue and its state after the repair
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifiedIssue {
+    pub name: String,
+    pub category: String,
+    pub verdict: IssueVerdict,
+    /// Latest message (the original one for fixed issues)
+    pub message: String,
+}
+
+pub fn is_failing(result: &CheckResult) -> bool {
+    matches!(
+        result.severity,
+        CheckSeverity::Error | CheckSeverity::Warning
+    )
+}
+
+/// Whether two results come from the same check
+fn same_check(a: &CheckResult, b: &CheckResult) -> bool {
+    a.name == b.name && a.category == b.category
+}
+
+/// Run the checks in `categories`, or all of them if a category can't be run on its own
+fn run_categories(categories: &[&str], config: &Option<ZenvoConfig>) -> Result<Vec<CheckResult>> {
+    // The action may have rewritten env.lock
+    let env_lock = EnvLock::load_if_exists()?;
+
+    let parsed: Option<Vec<CheckCategory>> = categories
+        .iter()
+        .map(|c| CheckCategory::from_name(c))
+        .collect();
+    match parsed {
+        Some(parsed) => {
+            let mut results = Vec::new();
+            for category in parsed {
+                results.extend(run_all_checks(&env_lock, Some(category), config)?);
+            }
+            Ok(results)
+        }
+        None => run_all_checks(&env_lock, None, config),
+    }
+}
+
+/// Re-run the checks behind `action`'s issues
+///
+/// Returns whether all of them pass now, or `None` for actions no issue asked for.
+pub fn recheck(action: &RepairAction, config: &Option<ZenvoConfig>) -> Result<Option<bool>> {
+    if action.triggered_by.is_empty() {
+        return Ok(None);
+    }
+
+    let mut categories: Vec<&str> = action
+        .triggered_by
+        .iter()
+        .map(|r| r.category.as_str())
+        .collect();
+    categories.sort_unstable();
+    categories.dedup();
+
+    let results = run_categories(&categories, config)?;
+    let fixed = action.triggered_by.iter().all(|issue| {
+        !results
+            .iter()
+            .any(|r| same_check(r, issue) && is_failing(r))
+    });
+    Ok(Some(fixed))
+}
+
+/// Compare the issues found before the repair with the results after it
+pub fn classify(before: &[&CheckResult], after: &[CheckResult]) -> Vec<VerifiedIssue> {
+    let mut verified: Vec<VerifiedIssue> = before
+        .iter()
+        .map(
+            |issue| match after.iter().find(|r| same_check(r, issue) && is_failing(r)) {
+                Some(current) => VerifiedIssue {
+                    name: issue.name.clone(),
+                    category: issue.category.clone(),
+                    verdict: IssueVerdict::StillFailing,
+                    message: current.message.clone(),
+                },
+                None => VerifiedIssue {
+                    name: issue.name.clone(),
+                    category: issue.category.clone(),
+                    verdict: IssueVerdict::Fixed,
+                    message: issue.message.clone(),
+                },
+            },
+        )
+        .collect();
+
+    verified.extend(
+        after
+            .iter()
+            .filter(|r| is_failing(r) && !before.iter().any(|issue| same_check(issue, r)))
+            .map(|r| VerifiedIssue {
+                name: r.name.clone(),
+                category: r.category.clone(),
+                verdict: IssueVerdict::Introduced,
+                message: r.message.clone(),
+            }),
+    );
+    verified
+}
+
+/// Run every check again and classify the original issues
+pub fn verify_repair(
+    issues: &[&CheckResult],
+    config: &Option<ZenvoConfig>,
+) -> Result<Vec<VerifiedIssue>> {
+    let env_lock = EnvLock::load_if_exists()?;
+    let after = run_all_checks(&env_lock, None, config)?;
+    Ok(classify(issues, &after))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_issues() {
+        let before = [
+            CheckResult::error("node_modules exists", "deps", "node_modules not found"),
+            CheckResult::error(
+                "Node version match",
+                "toolchain",
+                "Expected 20.11.0 but found 18.19.0",
+            ),
+        ];
+        let before: Vec<&CheckResult> = before.iter().collect();
+        let after = vec![
+            CheckResult::pass("node_modules exists", "deps"),
+            CheckResult::error(
+                "Node version match",
+                "toolchain",
+                "Expected 20.11.0 but found 18.20.0",
+            ),
+            CheckResult::warning("Peer dependencies", "deps", "react-dom@18 wants react@18"),
+        ];
+
+        let verified = classify(&before, &after);
+        let verdicts: Vec<(&str, IssueVerdict)> = verified
+            .iter()
+            .map(|v| (v.name.as_str(), v.verdict))
+            .collect();
+        assert_eq!(
+            verdicts,
+            vec![
+                ("node_modules exists", IssueVerdict::Fixed),
+                ("Node version match", IssueVerdict::StillFailing),
+                ("Peer dependencies", IssueVerdict::Introduced),
+            ]
+        );
+        assert_eq!(verified[1].message, "Expected 20.11.0 but found 18.20.0");
+    }
+}
@@ -1,6 +1,8 @@
 //! Utility functions for Zenvo
 //! This module provides common utilities including command execution with timeout.
 
+pub mod npm_range;
+
 use anyhow::Result;
 use std::process::{Command, Output, Stdio};
 use std::time::Duration;
@@ -69,7 +71,6 @@ impl CommandResult {
 ///
 /// # Returns
 /// A `CommandResult` indicating success, failure, timeout, or spawn error
-
 pub fn run_command_with_timeout(cmd: &str, args: &[&str], timeout: Duration) -> CommandResult {
     // On Windows, run through cmd.exe to properly find .cmd/.bat files in PATH
     #[cfg(windows)]
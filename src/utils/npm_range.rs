@@ -0,0 +1,157 @@
+//! npm-style semver ranges
+//!
+//! The `semver` crate implements Cargo's flavour of requirements, where a bare
+//! `1.2.3` means `^1.2.3` and `||` / hyphen ranges don't exist. This translates npm
+//! range syntax into a set of alternative `VersionReq`s.
+
+use semver::{Version, VersionReq};
+
+const OPERATORS: [&str; 8] = [">=", "<=", "~>", ">", "<", "=", "^", "~"];
+
+/// Translate one npm comparator (`^1.2`, `>=2.x`, `1.2.3`) into Cargo syntax
+fn translate_comparator(token: &str) -> Option<Option<String>> {
+    let op = OPERATORS.iter().find(|op| token.starts_with(**op)).copied().unwrap_or("");
+    let version = token[op.len()..].trim().trim_start_matches(['v', '=']);
+    let version = version.split('+').next().unwrap_or(version);
+
+    // Drop wildcard components: `1.x` -> `1`, `*` -> match anything
+    let parts: Vec<&str> = version
+        .split('.')
+        .take_while(|p| !matches!(*p, "x" | "X" | "*" | ""))
+        .collect();
+    if parts.is_empty() {
+        return match op {
+            "" | ">=" | "<=" | "=" | "^" | "~" => Some(None),
+            _ => None,
+        };
+    }
+    let version = parts.join(".");
+    if !version.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let op = match op {
+        "" => "=",
+        "~>" => "~",
+        other => other,
+    };
+    Some(Some(format!("{}{}", op, version)))
+}
+
+/// Parse an npm range into its `||` alternatives
+///
+/// Returns `None` for specs that aren't semver ranges (tags, URLs, `workspace:`, ...).
+pub fn parse_range(range: &str) -> Option<Vec<VersionReq>> {
+    let range = range.trim();
+    if range.contains(':') || range.contains('/') {
+        return None;
+    }
+
+    let mut alternatives = Vec::new();
+    for alternative in range.split("||") {
+        // Glue detached operators back on (`>= 1.2.3`)
+        let mut tokens: Vec<String> = Vec::new();
+        let mut pending = String::new();
+        for token in alternative.split_whitespace() {
+            if OPERATORS.contains(&token) {
+                pending.push_str(token);
+            } else {
+                tokens.push(format!("{}{}", pending, token));
+                pending.clear();
+            }
+        }
+
+        let mut comparators = Vec::new();
+        if let Some(dash) = tokens.iter().position(|t| t == "-") {
+            let (from, to) = (tokens.get(dash.wrapping_sub(1))?, tokens.get(dash + 1)?);
+            comparators.extend(translate_comparator(&format!(">={}", from))?);
+            comparators.extend(translate_comparator(&format!("<={}", to))?);
+        } else {
+            for token in &tokens {
+                comparators.extend(translate_comparator(token)?);
+            }
+        }
+
+        let req = if comparators.is_empty() {
+            VersionReq::STAR
+        } else {
+            VersionReq::parse(&comparators.join(", ")).ok()?
+        };
+        alternatives.push(req);
+    }
+
+    Some(alternatives)
+}
+
+/// Whether `version` satisfies the npm `range`; `None` if either can't be parsed
+pub fn satisfies(version: &str, range: &str) -> Option<bool> {
+    let version = Version::parse(version.trim().trim_start_matches('v')).ok()?;
+    let alternatives = parse_range(range)?;
+    Some(alternatives.iter().any(|req| req.matches(&version)))
+}
+
+/// Representative versions a range admits: each comparator's bound plus the next
+/// patch, minor and major above it
+fn sample_versions(alternatives: &[VersionReq]) -> Vec<Version> {
+    let mut samples = Vec::new();
+    for req in alternatives {
+        for c in &req.comparators {
+            let (major, minor, patch) = (c.major, c.minor.unwrap_or(0), c.patch.unwrap_or(0));
+            samples.push(Version::new(major, minor, patch));
+            samples.push(Version::new(major, minor, patch + 1));
+            samples.push(Version::new(major, minor + 1, 0));
+            samples.push(Version::new(major + 1, 0, 0));
+        }
+        if req.comparators.is_empty() {
+            samples.push(Version::new(0, 0, 0));
+            samples.push(Version::new(u64::from(u16::MAX), 0, 0));
+        }
+    }
+    samples
+        .into_iter()
+        .filter(|v| alternatives.iter().any(|req| req.matches(v)))
+        .collect()
+}
+
+/// Whether every version `inner` can resolve to is also admitted by `outer`
+///
+/// This is a sampling heuristic rather than a full range intersection, which is
+/// enough to tell whether natural resolution could land outside `outer`.
+pub fn range_within(inner: &str, outer: &str) -> Option<bool> {
+    let inner = parse_range(inner)?;
+    let outer = parse_range(outer)?;
+    let samples = sample_versions(&inner);
+    if samples.is_empty() {
+        return None;
+    }
+    Some(samples.iter().all(|v| outer.iter().any(|req| req.matches(v))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_satisfies_npm_syntax() {
+        assert_eq!(satisfies("1.2.3", "1.2.3"), Some(true));
+        assert_eq!(satisfies("1.2.4", "1.2.3"), Some(false));
+        assert_eq!(satisfies("1.9.0", "^1.2.3"), Some(true));
+        assert_eq!(satisfies("2.0.0", "^1.2.3 || ^3.0.0"), Some(false));
+        assert_eq!(satisfies("3.1.0", "^1.2.3 || ^3.0.0"), Some(true));
+        assert_eq!(satisfies("1.5.0", ">= 1.2 <2"), Some(true));
+        assert_eq!(satisfies("2.3.9", "1.0.0 - 2.3"), Some(true));
+        assert_eq!(satisfies("2.4.0", "1.0.0 - 2.3"), Some(false));
+        assert_eq!(satisfies("4.1.0", "4.x"), Some(true));
+        assert_eq!(satisfies("4.1.0", "*"), Some(true));
+        assert_eq!(satisfies("1.0.0", "latest"), None);
+        assert_eq!(satisfies("1.0.0", "workspace:^"), None);
+    }
+
+    #[test]
+    fn test_range_within() {
+        assert_eq!(range_within("1.2.3", "1.2.3"), Some(true));
+        assert_eq!(range_within("^1.2.3", "1.2.3"), Some(false));
+        assert_eq!(range_within("^7.5.4", "^7.5.2"), Some(true));
+        assert_eq!(range_within("^7.0.0", "^7.5.2"), Some(false));
+    }
+}
@@ -674,15 +674,15 @@ packages:
 
     // Verify root and workspace packages are present
     assert!(
-        importers.get(&serde_yaml::Value::String(".".to_string())).is_some(),
+        importers.get(serde_yaml::Value::String(".".to_string())).is_some(),
         "Root importer should exist"
     );
     assert!(
-        importers.get(&serde_yaml::Value::String("packages/app".to_string())).is_some(),
+        importers.get(serde_yaml::Value::String("packages/app".to_string())).is_some(),
         "packages/app importer should exist"
     );
     assert!(
-        importers.get(&serde_yaml::Value::String("packages/shared-utils".to_string())).is_some(),
+        importers.get(serde_yaml::Value::String("packages/shared-utils".to_string())).is_some(),
         "packages/shared-utils importer should exist"
     );
 
@@ -446,7 +446,7 @@ fn test_init_with_system_info() {
 
     let dir = setup_test_project();
 
-    zenvo_cmd()
+    let _ = zenvo_cmd()
         .current_dir(dir.path())
         .args(["init", "--system-info"])
         .assert();